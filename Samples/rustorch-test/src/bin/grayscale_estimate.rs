@@ -0,0 +1,49 @@
+// グレースケール表示 (DisplayDriver::show_grayscale()) で出せるフレームレートの見積もり
+// (転送量と I2cTiming から計算するだけで、実機では計測していない)
+//
+// 使い方: grayscale_estimate [BMP_FILE]
+// - BMP_FILE を省略すると asserts/images/pomodoro_startup.gray.bmp を使う
+// - 全画面のグラデーション (毎サブフレーム全体が変わる最悪の場合) と合わせて、400kHz と 1MHz の I2C で見積もる
+// - 実機の値はメニューの Gray benchmark (DisplayDriver::get_flush_stats() による計測) と比べる
+
+use std::error::Error;
+
+use embedded_graphics::prelude::*;
+use rustorch_test::frame_buffer::{FrameBuffer, HEIGHT, WIDTH};
+use rustorch_test::grayscale::*;
+
+const DEFAULT_IMAGE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../rustorch/asserts/images/pomodoro_startup.gray.bmp");
+
+// 横方向のグラデーション
+fn gradient() -> Vec<u8> {
+    (0..HEIGHT).flat_map(|_| (0..WIDTH).map(|x| (x * 255 / (WIDTH - 1)) as u8)).collect()
+}
+
+fn report(name: &str, load: &dyn Fn(u8) -> Result<GrayscaleImage, GrayscaleError>) -> Result<(), Box<dyn Error>> {
+    println!("{}", name);
+    println!("  {:<15} {:>6} {:>9} {:>12} {:>12}", "method", "levels", "subframes", "400kHz [fps]", "1MHz [fps]");
+    println!("  (estimated from I2C transfer sizes, not measured on the device)");
+    for method in [GrayscaleMethod::Pwm, GrayscaleMethod::WeightedPlanes] {
+        for levels in MIN_LEVELS..=MAX_LEVELS {
+            let image = load(levels)?;
+            let subframes = subframes(&FrameBuffer::new(), &image, Point::zero(), method);
+            let standard = GrayscaleEstimate::estimate(&subframes, &I2cTiming::STANDARD_400K);
+            let fast = GrayscaleEstimate::estimate(&subframes, &I2cTiming::FAST_1M);
+            // 1 枚だけなら切り替えないので転送もしない
+            let rate = |estimate: &GrayscaleEstimate| if estimate.subframes > 1 { format!("{:.1}", estimate.frame_rate()) } else { "static".to_string() };
+            println!("  {:<15} {:>6} {:>9} {:>12} {:>12}",
+                format!("{:?}", method), levels, standard.subframes, rate(&standard), rate(&fast));
+        }
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let path = std::env::args().nth(1).unwrap_or_else(|| DEFAULT_IMAGE.to_string());
+    let data = std::fs::read(&path)?;
+
+    let luminance = gradient();
+    report("full screen gradient", &|levels| GrayscaleImage::from_luminance(WIDTH, HEIGHT, &luminance, levels))?;
+    report(&path, &|levels| GrayscaleImage::from_bmp(&data, levels))?;
+    Ok(())
+}
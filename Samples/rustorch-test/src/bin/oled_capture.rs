@@ -0,0 +1,99 @@
+// シリアルコンソールのログから OLED の画面キャプチャ (DisplayDriver::screenshot() / set_mirroring()) を取り出す
+//
+// 使い方: oled_capture [--pbm DIR] [--gif FILE] [--scale N] [--delay MS] [LOG_FILE]
+// - LOG_FILE を省略すると標準入力から読む (例: espflash monitor | tee log.txt)
+// - --pbm: 1 フレームずつ DIR/oled_<連番>.pbm に保存する (--gif も省略した場合はカレントディレクトリ)
+// - --gif: 全フレームをアニメーション GIF にする (--scale 倍に拡大、1 フレーム --delay ミリ秒)
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+
+use embedded_graphics::prelude::*;
+use rustorch_test::frame_buffer::{FrameBuffer, HEIGHT, WIDTH};
+use rustorch_test::screenshot;
+
+struct Options {
+    pbm_dir: Option<PathBuf>,
+    gif_path: Option<PathBuf>,
+    scale: u32,
+    delay_ms: u32,
+    input: Option<PathBuf>,
+}
+
+fn parse_options() -> Result<Options, Box<dyn Error>> {
+    let mut options = Options { pbm_dir: None, gif_path: None, scale: 2, delay_ms: 100, input: None };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} requires a value", arg));
+        match arg.as_str() {
+            "--pbm" => options.pbm_dir = Some(PathBuf::from(value()?)),
+            "--gif" => options.gif_path = Some(PathBuf::from(value()?)),
+            "--scale" => options.scale = value()?.parse()?,
+            "--delay" => options.delay_ms = value()?.parse()?,
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg).into()),
+            _ => options.input = Some(PathBuf::from(arg)),
+        }
+    }
+    if options.scale == 0 {
+        return Err("--scale must be 1 or more".into());
+    }
+    if options.pbm_dir.is_none() && options.gif_path.is_none() {
+        options.pbm_dir = Some(PathBuf::from("."));
+    }
+    Ok(options)
+}
+
+fn write_gif(path: &PathBuf, frames: &[(u32, FrameBuffer)], scale: u32, delay_ms: u32) -> Result<(), Box<dyn Error>> {
+    let (width, height) = (WIDTH * scale, HEIGHT * scale);
+    // 0: 消灯 (黒), 1: 点灯 (白)
+    let palette = [0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF];
+    let mut encoder = gif::Encoder::new(File::create(path)?, width as u16, height as u16, &palette)?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for (_, frame) in frames {
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let point = Point::new((x / scale) as i32, (y / scale) as i32);
+                pixels.push(frame.get_pixel(point).is_on() as u8);
+            }
+        }
+        let mut gif_frame = gif::Frame::from_indexed_pixels(width as u16, height as u16, pixels, None);
+        // 単位は 1/100 秒
+        gif_frame.delay = (delay_ms / 10) as u16;
+        encoder.write_frame(&gif_frame)?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let options = parse_options()?;
+    let reader: Box<dyn BufRead> = match &options.input {
+        Some(path) => Box::new(BufReader::new(File::open(path)?)),
+        None => Box::new(BufReader::new(std::io::stdin())),
+    };
+
+    let mut frames = Vec::new();
+    for (index, line) in reader.split(b'\n').enumerate() {
+        // ログには UTF-8 でない出力が混ざることがある
+        let line = String::from_utf8_lossy(&line?).into_owned();
+        match screenshot::decode_line(&line) {
+            Some(Ok(frame)) => frames.push(frame),
+            Some(Err(e)) => eprintln!("line {}: {}", index + 1, e),
+            None => (),
+        }
+    }
+    eprintln!("{} frame(s) found", frames.len());
+
+    if let Some(dir) = &options.pbm_dir {
+        std::fs::create_dir_all(dir)?;
+        for (sequence, frame) in frames.iter() {
+            std::fs::write(dir.join(format!("oled_{:05}.pbm", sequence)), screenshot::to_pbm(frame))?;
+        }
+    }
+    if let Some(path) = &options.gif_path {
+        write_gif(path, &frames, options.scale, options.delay_ms)?;
+    }
+    Ok(())
+}
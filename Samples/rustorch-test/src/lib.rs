@@ -1,3 +1,53 @@
+// 実機側のハードウェア非依存なモジュールはソースを共有してホスト上でテストする
+#[path = "../../rustorch/src/led_buffer.rs"]
+pub mod led_buffer;
+#[path = "../../rustorch/src/led_config.rs"]
+pub mod led_config;
+#[path = "../../rustorch/src/led_format.rs"]
+pub mod led_format;
+#[path = "../../rustorch/src/note.rs"]
+pub mod note;
+#[path = "../../rustorch/src/score.rs"]
+pub mod score;
+#[path = "../../rustorch/src/mml.rs"]
+pub mod mml;
+#[path = "../../rustorch/src/pitch_effect.rs"]
+pub mod pitch_effect;
+#[path = "../../rustorch/src/tone.rs"]
+pub mod tone;
+#[path = "../../rustorch/src/voice.rs"]
+pub mod voice;
+#[path = "../../rustorch/src/sound_effects.rs"]
+pub mod sound_effects;
+#[path = "../../rustorch/src/dnd.rs"]
+pub mod dnd;
+#[path = "../../rustorch/src/rtttl.rs"]
+pub mod rtttl;
+#[path = "../../rustorch/src/midi.rs"]
+pub mod midi;
+#[path = "../../rustorch/src/melodies.rs"]
+pub mod melodies;
+#[path = "../../rustorch/src/frame_buffer.rs"]
+pub mod frame_buffer;
+#[path = "../../rustorch/src/bitmap_font.rs"]
+pub mod bitmap_font;
+#[path = "../../rustorch/src/text.rs"]
+pub mod text;
+#[path = "../../rustorch/src/screenshot.rs"]
+pub mod screenshot;
+#[path = "../../rustorch/src/display_command.rs"]
+pub mod display_command;
+#[path = "../../rustorch/src/button.rs"]
+pub mod button;
+#[path = "../../rustorch/src/widget.rs"]
+pub mod widget;
+#[path = "../../rustorch/src/sprite.rs"]
+pub mod sprite;
+#[path = "../../rustorch/src/image_asset.rs"]
+pub mod image_asset;
+#[path = "../../rustorch/src/grayscale.rs"]
+pub mod grayscale;
+
 pub const NUMBER_SEGMENT_TABLE: [u8; 10] = [
     0xFC,   // 0
     0x60,   // 1
@@ -0,0 +1,152 @@
+use embedded_graphics::{prelude::*, primitives::Rectangle};
+use rustorch_test::bitmap_font::*;
+use rustorch_test::display_command::*;
+use rustorch_test::frame_buffer::*;
+use rustorch_test::text::*;
+
+// 8x8 (ベースライン下 1 ピクセル) の小さな BDF
+// - U+3042 は全面、U+FF21 は BBX で右下に寄せた 2x2、-1 は文字コードなし
+const SAMPLE_BDF: &str = "\
+STARTFONT 2.1
+FONT -Test-Sample-Medium-R-Normal--8-80-75-75-C-80-ISO10646-1
+SIZE 8 75 75
+FONTBOUNDINGBOX 8 8 0 -1
+STARTPROPERTIES 2
+CHARSET_REGISTRY \"ISO10646\"
+CHARSET_ENCODING \"1\"
+ENDPROPERTIES
+CHARS 3
+STARTCHAR uni3042
+ENCODING 12354
+SWIDTH 1000 0
+DWIDTH 8 0
+BBX 8 8 0 -1
+BITMAP
+FF
+81
+81
+81
+81
+81
+81
+FF
+ENDCHAR
+STARTCHAR uniFF21
+ENCODING 65313
+SWIDTH 1000 0
+DWIDTH 8 0
+BBX 2 2 5 -1
+BITMAP
+C0
+40
+ENDCHAR
+STARTCHAR unused
+ENCODING -1
+BBX 8 8 0 -1
+BITMAP
+FF
+FF
+FF
+FF
+FF
+FF
+FF
+FF
+ENDCHAR
+ENDFONT
+";
+
+fn sample_font() -> &'static BitmapFont {
+    let bdf = parse_bdf(SAMPLE_BDF).unwrap();
+    let mut codepoints = Vec::new();
+    let mut bitmaps = Vec::new();
+    for (encoding, bitmap) in bdf.glyphs {
+        codepoints.push(encoding);
+        bitmaps.extend(bitmap);
+    }
+    Box::leak(Box::new(BitmapFont {
+        width: bdf.width,
+        height: bdf.height,
+        codepoints: codepoints.leak(),
+        bitmaps: bitmaps.leak(),
+    }))
+}
+
+#[test]
+fn test_parse_bdf() {
+    let bdf = parse_bdf(SAMPLE_BDF).unwrap();
+    assert_eq!(bdf.charset, "ISO10646");
+    assert_eq!((bdf.width, bdf.height), (8, 8));
+    // 文字コードなしのグリフは読み飛ばす
+    assert_eq!(bdf.glyphs.len(), 2);
+    assert_eq!(bdf.glyphs[0], (0x3042, vec![0xFF, 0x81, 0x81, 0x81, 0x81, 0x81, 0x81, 0xFF]));
+    // 右下 (x = 5, y = 6) に寄せて配置する
+    assert_eq!(bdf.glyphs[1], (0xFF21, vec![0, 0, 0, 0, 0, 0, 0x06, 0x02]));
+
+    assert_eq!(parse_bdf("STARTFONT 2.1\nENDFONT\n"), Err(BdfError::MissingBoundingBox));
+    assert_eq!(parse_bdf("FONTBOUNDINGBOX 8 x 0 0\n"), Err(BdfError::InvalidLine(1)));
+}
+
+#[test]
+fn test_glyph_lookup() {
+    let font = sample_font();
+    assert_eq!(font.len(), 2);
+    assert_eq!(font.bytes_per_glyph(), 8);
+    assert!(font.contains('あ'));
+    assert!(!font.contains('い'));
+
+    let glyph = font.glyph('あ').unwrap();
+    assert!(glyph.pixel(0, 0) && glyph.pixel(7, 7));
+    assert!(!glyph.pixel(3, 3));
+    // 範囲外は消灯
+    assert!(!glyph.pixel(8, 0));
+
+    // 12x12 は 1 文字 18 バイトに詰める
+    let font = BitmapFont { width: 12, height: 12, codepoints: &[], bitmaps: &[] };
+    assert_eq!(font.bytes_per_glyph(), 18);
+    assert!(font.is_empty());
+}
+
+#[test]
+fn test_string_literal_chars() {
+    let source = r##"
+        // コメントは対象外
+        /* ブロック /* 入れ子 */ コメント */
+        let a = "あいう";
+        let b = '"'; let c = "か\"き";
+        let d: &'static str = r#"生"文字"#;
+        let e = '字';
+    "##;
+    let chars: String = string_literal_chars(source).into_iter().collect();
+    assert_eq!(chars, "あいうかき字文生");
+}
+
+#[test]
+fn test_to_rust_source() {
+    let source = to_rust_source("FONT", 8, 8, &[('あ', vec![0xFF, 0, 0, 0, 0, 0, 0, 0x01])]);
+    assert!(source.starts_with("pub static FONT: crate::bitmap_font::BitmapFont"));
+    assert!(source.contains("        0x3042, // あ\n"));
+    assert!(source.contains("        0xFF, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // あ\n"));
+}
+
+#[test]
+fn test_draw_japanese_text() {
+    let font = sample_font();
+    let area = Rectangle::new(Point::new(0, 0), Size::new(128, 8));
+    let style = TextStyle::new().font(Font::Bitmap(font));
+    assert_eq!(Font::Bitmap(font).advance(), 8);
+    assert_eq!(Font::Bitmap(font).columns_in(128), 16);
+
+    let mut frame = FrameBuffer::new();
+    // あ: グリフ、A: 全角で代用、い: 未収録なので四角、空白: 何も描かない
+    render(&DisplayCommand::DrawTextBox { text: "あA い".to_string(), area, style }, &mut frame).unwrap();
+    assert_eq!(frame.count_on(), 28 + 3 + 20);
+    assert!(frame.get_pixel(Point::new(0, 0)).is_on());
+    assert!(frame.get_pixel(Point::new(8 + 5, 6)).is_on());
+    assert!(frame.get_pixel(Point::new(24 + 1, 1)).is_on());
+    assert!(!frame.get_pixel(Point::new(24, 0)).is_on());
+
+    // 日本語は空白がなくても文字単位で折り返す
+    let lines = layout_lines("あいうえおかきくけこ", 4, 3, true);
+    assert_eq!(lines, vec!["あいうえ", "おかきく", "けこ"]);
+}
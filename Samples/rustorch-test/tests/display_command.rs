@@ -0,0 +1,231 @@
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+use rustorch_test::display_command::*;
+use rustorch_test::frame_buffer::*;
+
+fn draw(commands: &[DisplayCommand]) -> FrameBuffer {
+    let mut frame = FrameBuffer::new();
+    for command in commands {
+        render(command, &mut frame).unwrap();
+    }
+    frame
+}
+
+fn on(frame: &FrameBuffer, x: i32, y: i32) -> bool {
+    frame.get_pixel(Point::new(x, y)).is_on()
+}
+
+#[test]
+fn test_frame_buffer_layout() {
+    let mut frame = FrameBuffer::new();
+    frame.set_pixel(Point::new(0, 0), BinaryColor::On);
+    frame.set_pixel(Point::new(1, 9), BinaryColor::On);
+    frame.set_pixel(Point::new(127, 63), BinaryColor::On);
+    // 画面外は無視される
+    frame.set_pixel(Point::new(128, 0), BinaryColor::On);
+    frame.set_pixel(Point::new(0, -1), BinaryColor::On);
+
+    // SSD1306 の GDDRAM と同じ並び
+    let bytes = frame.as_bytes();
+    assert_eq!(bytes[0], 0x01);
+    assert_eq!(bytes[128 + 1], 0x02);
+    assert_eq!(bytes[BUFFER_SIZE - 1], 0x80);
+    assert_eq!(frame.count_on(), 3);
+    assert_eq!(frame.get_pixel(Point::new(200, 200)), BinaryColor::Off);
+}
+
+#[test]
+fn test_pixel_and_clear() {
+    let frame = draw(&[
+        DisplayCommand::Clear,
+        DisplayCommand::DrawPixel { point: Point::new(5, 6), color: BinaryColor::On },
+    ]);
+    assert!(on(&frame, 5, 6));
+    assert_eq!(frame.count_on(), 1);
+
+    let frame = draw(&[
+        DisplayCommand::DrawRectangle { area: Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT)), style: fill() },
+        DisplayCommand::DrawPixel { point: Point::new(5, 6), color: BinaryColor::Off },
+    ]);
+    assert!(!on(&frame, 5, 6));
+    assert_eq!(frame.count_on(), WIDTH * HEIGHT - 1);
+    assert_eq!(draw(&[DisplayCommand::Clear]).count_on(), 0);
+}
+
+#[test]
+fn test_line() {
+    let frame = draw(&[DisplayCommand::DrawLine { start: Point::new(0, 0), end: Point::new(9, 0), style: stroke(1) }]);
+    assert_eq!(frame.count_on(), 10);
+    assert!(on(&frame, 0, 0) && on(&frame, 9, 0));
+
+    let frame = draw(&[DisplayCommand::DrawLine { start: Point::new(0, 0), end: Point::new(7, 7), style: stroke(1) }]);
+    assert_eq!(frame.count_on(), 8);
+    for i in 0..8 {
+        assert!(on(&frame, i, i));
+    }
+}
+
+#[test]
+fn test_rectangle() {
+    let area = Rectangle::new(Point::new(10, 10), Size::new(20, 10));
+
+    // 枠線のみ: 周囲 (20 + 10) * 2 - 4
+    let frame = draw(&[DisplayCommand::DrawRectangle { area, style: stroke(1) }]);
+    assert_eq!(frame.count_on(), 56);
+    assert!(on(&frame, 10, 10) && on(&frame, 29, 19));
+    assert!(!on(&frame, 15, 15));
+
+    let frame = draw(&[DisplayCommand::DrawRectangle { area, style: fill() }]);
+    assert_eq!(frame.count_on(), 200);
+
+    // 塗りつぶしの上に縁取り付きで描くと内側は消える
+    let frame = draw(&[
+        DisplayCommand::DrawRectangle { area, style: fill() },
+        DisplayCommand::DrawRectangle { area, style: outlined_fill(1) },
+    ]);
+    assert_eq!(frame.count_on(), 56);
+}
+
+#[test]
+fn test_rounded_rectangle() {
+    let area = Rectangle::new(Point::new(0, 0), Size::new(20, 20));
+    let square = draw(&[DisplayCommand::DrawRectangle { area, style: fill() }]);
+    let rounded = draw(&[DisplayCommand::DrawRoundedRectangle { area, corner_radius: 5, style: fill() }]);
+    // 角だけ欠ける
+    assert!(!on(&rounded, 0, 0) && !on(&rounded, 19, 19));
+    assert!(on(&rounded, 10, 0) && on(&rounded, 10, 10));
+    assert!(rounded.count_on() < square.count_on());
+}
+
+#[test]
+fn test_circle() {
+    let frame = draw(&[DisplayCommand::DrawCircle { top_left: Point::new(0, 0), diameter: 11, style: fill() }]);
+    assert!(on(&frame, 5, 5));
+    assert!(on(&frame, 5, 0) && on(&frame, 0, 5) && on(&frame, 10, 5) && on(&frame, 5, 10));
+    assert!(!on(&frame, 0, 0) && !on(&frame, 10, 10));
+
+    let outline = draw(&[DisplayCommand::DrawCircle { top_left: Point::new(0, 0), diameter: 11, style: stroke(1) }]);
+    assert!(!on(&outline, 5, 5));
+    assert!(on(&outline, 5, 0));
+    assert!(outline.count_on() < frame.count_on());
+}
+
+#[test]
+fn test_polyline() {
+    let points = vec![Point::new(0, 0), Point::new(4, 0), Point::new(4, 4)];
+    let frame = draw(&[DisplayCommand::DrawPolyline { points, style: stroke(1) }]);
+    // 折れ曲がり点は重複しない
+    assert_eq!(frame.count_on(), 9);
+    assert!(on(&frame, 4, 0) && on(&frame, 4, 4));
+    assert!(!on(&frame, 0, 4));
+}
+
+#[test]
+fn test_invert_and_clear_region() {
+    let area = Rectangle::new(Point::new(0, 0), Size::new(8, 8));
+    let frame = draw(&[
+        DisplayCommand::DrawPixel { point: Point::new(1, 1), color: BinaryColor::On },
+        DisplayCommand::InvertRegion { area },
+    ]);
+    assert_eq!(frame.count_on(), 63);
+    assert!(!on(&frame, 1, 1));
+
+    // 2 回反転すると元に戻る
+    let twice = draw(&[
+        DisplayCommand::DrawPixel { point: Point::new(1, 1), color: BinaryColor::On },
+        DisplayCommand::InvertRegion { area },
+        DisplayCommand::InvertRegion { area },
+    ]);
+    assert_eq!(twice.count_on(), 1);
+
+    // 画面からはみ出した部分は無視される
+    let frame = draw(&[DisplayCommand::InvertRegion { area: Rectangle::new(Point::new(120, 60), Size::new(16, 16)) }]);
+    assert_eq!(frame.count_on(), 8 * 4);
+
+    let frame = draw(&[
+        DisplayCommand::DrawRectangle { area: Rectangle::new(Point::new(0, 0), Size::new(16, 16)), style: fill() },
+        DisplayCommand::ClearRegion { area: Rectangle::new(Point::new(4, 4), Size::new(8, 8)) },
+    ]);
+    assert_eq!(frame.count_on(), 256 - 64);
+    assert!(!on(&frame, 4, 4) && on(&frame, 3, 3));
+}
+
+#[test]
+fn test_text_and_image() {
+    let frame = draw(&[DisplayCommand::DrawText { text: "A".to_string(), point: Point::new(0, 0) }]);
+    // FONT_6X10 の 1 文字分に収まる
+    assert!(frame.count_on() > 0);
+    for y in 0..HEIGHT as i32 {
+        for x in 0..WIDTH as i32 {
+            if on(&frame, x, y) {
+                assert!(x < 6 && y < 10);
+            }
+        }
+    }
+
+    let image = include_bytes!("../../rustorch/asserts/images/pomodoro_working.bmp");
+    let frame = draw(&[DisplayCommand::DrawImage { image, point: Point::zero() }]);
+    assert!(frame.count_on() > 0);
+
+    let mut frame = FrameBuffer::new();
+    let result = render(&DisplayCommand::DrawImage { image: b"not a bmp", point: Point::zero() }, &mut frame);
+    assert_eq!(result, Err(RenderError::InvalidImage));
+}
+
+#[test]
+fn test_update_does_not_draw() {
+    assert_eq!(draw(&[DisplayCommand::Update]).count_on(), 0);
+}
+
+#[test]
+fn test_frame_is_rendered_atomically() {
+    let mut frame = FrameBuffer::new();
+    let mut display_frame = DisplayFrame::new();
+    display_frame
+        .draw_rectangle(Rectangle::new(Point::zero(), Size::new(8, 8)), fill())
+        .invert_region(Rectangle::new(Point::zero(), Size::new(4, 8)));
+    assert_eq!(display_frame.commands().len(), 2);
+    render_frame(&display_frame, &mut frame).unwrap();
+    assert_eq!(frame.count_on(), 32);
+
+    // 途中で失敗したら前の内容のまま
+    let mut broken = DisplayFrame::new();
+    broken
+        .clear()
+        .draw_line(Point::new(0, 63), Point::new(127, 63), stroke(1))
+        .draw_image(b"not a bmp", Point::zero());
+    assert_eq!(render_frame(&broken, &mut frame), Err(RenderError::InvalidImage));
+    assert_eq!(frame.count_on(), 32);
+    assert!(on(&frame, 4, 0) && !on(&frame, 0, 0));
+
+    // DisplayCommand としても同じ
+    let result = render(&DisplayCommand::Frame(broken), &mut frame);
+    assert_eq!(result, Err(RenderError::InvalidImage));
+    assert_eq!(frame.count_on(), 32);
+
+    // 描き始めは前の内容
+    let mut next = DisplayFrame::new();
+    next.draw_pixel(Point::new(100, 0), BinaryColor::On);
+    render(&DisplayCommand::Frame(next), &mut frame).unwrap();
+    assert_eq!(frame.count_on(), 33);
+    assert!(DisplayFrame::new().is_empty());
+}
+
+#[test]
+fn test_pending_frames() {
+    let pending = PendingFrames::new();
+    assert!(pending.try_acquire(SubmitPolicy::DropIfBusy));
+    // 処理中は捨てる
+    assert!(!pending.try_acquire(SubmitPolicy::DropIfBusy));
+    assert_eq!(pending.count(), 1);
+    // 待つ場合は常に送る
+    assert!(pending.try_acquire(SubmitPolicy::Wait));
+    assert_eq!(pending.count(), 2);
+
+    // 表示スレッド側と共有する
+    let thread_side = pending.clone();
+    thread_side.release();
+    assert!(!pending.try_acquire(SubmitPolicy::DropIfBusy));
+    thread_side.release();
+    assert_eq!(pending.count(), 0);
+    assert!(pending.try_acquire(SubmitPolicy::DropIfBusy));
+}
@@ -0,0 +1,85 @@
+use rustorch_test::dnd::*;
+
+// 2024-06-01 00:00:00 UTC
+const JUNE_1_UTC: i64 = 1_717_200_000;
+
+#[test]
+fn test_quiet_hours() {
+    let daytime = QuietHours::new(9 * 60, 17 * 60).unwrap();
+    assert!(!daytime.contains(9 * 60 - 1));
+    assert!(daytime.contains(9 * 60));
+    assert!(daytime.contains(17 * 60 - 1));
+    assert!(!daytime.contains(17 * 60));
+
+    // 日付をまたぐ
+    let night = QuietHours::new(22 * 60, 7 * 60).unwrap();
+    assert_eq!(night.to_string(), "22:00 ~ 7:00");
+    assert!(night.contains(23 * 60));
+    assert!(night.contains(0));
+    assert!(night.contains(7 * 60 - 1));
+    assert!(!night.contains(7 * 60));
+    assert!(!night.contains(12 * 60));
+
+    assert_eq!(QuietHours::new(0, MINUTES_PER_DAY), Err(DndError::InvalidTime(MINUTES_PER_DAY)));
+    assert_eq!(QuietHours::new(60, 60), Err(DndError::EmptyQuietHours));
+}
+
+#[test]
+fn test_is_active() {
+    let settings = DndSettings::new().quiet_hours(Some(QuietHours::new(22 * 60, 7 * 60).unwrap()));
+    assert_eq!(settings.utc_offset_minutes, 9 * 60);
+    // UTC 0:00 は日本時間 9:00
+    assert_eq!(settings.local_minute_of_day(JUNE_1_UTC), 9 * 60);
+    assert!(!settings.is_active(Some(JUNE_1_UTC)));
+    // UTC 13:30 は日本時間 22:30
+    assert!(settings.is_active(Some(JUNE_1_UTC + 13 * 3600 + 30 * 60)));
+    assert!(!settings.utc_offset(0).is_active(Some(JUNE_1_UTC + 13 * 3600 + 30 * 60)));
+    // 負のずれ (UTC-5 の 23:00)
+    assert_eq!(settings.utc_offset(-5 * 60).local_minute_of_day(JUNE_1_UTC + 4 * 3600), 23 * 60);
+
+    // 時刻が未設定ならスケジュールは無視し、手動設定だけで判定する
+    assert!(!settings.is_active(None));
+    assert!(!settings.is_active(Some(13 * 3600 + 30 * 60)));
+    assert!(settings.manual(true).is_active(None));
+    assert!(settings.manual(true).is_active(Some(JUNE_1_UTC)));
+    assert!(!DndSettings::new().is_active(Some(JUNE_1_UTC)));
+}
+
+#[test]
+fn test_unix_time_at() {
+    let settings = DndSettings::new().quiet_hours(Some(QuietHours::new(22 * 60, 7 * 60).unwrap()));
+    // 設定アプリで時計を 23:15 に合わせる
+    let unix_time_s = settings.unix_time_at(23 * 60 + 15).unwrap();
+    assert!(unix_time_s >= CLOCK_VALID_AFTER_UNIX_S);
+    assert!(unix_time_s < CLOCK_VALID_AFTER_UNIX_S + 24 * 3600);
+    assert_eq!(settings.local_minute_of_day(unix_time_s), 23 * 60 + 15);
+    assert!(settings.is_active(Some(unix_time_s)));
+    assert!(!settings.is_active(Some(settings.unix_time_at(12 * 60).unwrap())));
+
+    for offset in [ -5 * 60, 0, 9 * 60, 14 * 60 ] {
+        let settings = settings.utc_offset(offset);
+        for minute in [ 0, 1, 9 * 60, MINUTES_PER_DAY - 1 ] {
+            assert_eq!(settings.local_minute_of_day(settings.unix_time_at(minute).unwrap()), minute);
+        }
+    }
+    assert_eq!(settings.unix_time_at(MINUTES_PER_DAY), Err(DndError::InvalidTime(MINUTES_PER_DAY)));
+}
+
+#[test]
+fn test_bytes() {
+    let settings = DndSettings::new()
+        .manual(true)
+        .quiet_hours(Some(QuietHours::new(22 * 60 + 30, 6 * 60).unwrap()))
+        .utc_offset(-300);
+    assert_eq!(DndSettings::from_bytes(&settings.to_bytes()), Ok(settings));
+    assert_eq!(DndSettings::from_bytes(&DndSettings::new().to_bytes()), Ok(DndSettings::new()));
+
+    let mut bytes = settings.to_bytes();
+    assert_eq!(DndSettings::from_bytes(&bytes[..8]), Err(DndError::InvalidData));
+    bytes[0] = 0;
+    assert_eq!(DndSettings::from_bytes(&bytes), Err(DndError::InvalidData));
+    // 範囲外の時刻
+    let mut bytes = settings.to_bytes();
+    bytes[3..5].copy_from_slice(&MINUTES_PER_DAY.to_le_bytes());
+    assert_eq!(DndSettings::from_bytes(&bytes), Err(DndError::InvalidData));
+}
@@ -0,0 +1,69 @@
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+use rustorch_test::display_command::*;
+use rustorch_test::frame_buffer::*;
+
+#[test]
+fn test_dirty_regions() {
+    let shown = FrameBuffer::new();
+    let mut frame = shown.clone();
+    assert!(frame.dirty_regions(&shown).is_empty());
+
+    // 同じページ内の変化は最初の列から最後の列までまとめる
+    frame.set_pixel(Point::new(10, 0), BinaryColor::On);
+    frame.set_pixel(Point::new(20, 7), BinaryColor::On);
+    frame.set_pixel(Point::new(127, 63), BinaryColor::On);
+    let regions = frame.dirty_regions(&shown);
+    assert_eq!(regions, vec![
+        DirtyRegion { page: 0, start_column: 10, end_column: 21 },
+        DirtyRegion { page: 7, start_column: 127, end_column: 128 },
+    ]);
+    assert_eq!(regions[0].len(), 11);
+    assert_eq!(frame.region_bytes(&regions[0])[0], 0x01);
+    assert_eq!(frame.region_bytes(&regions[0])[10], 0x80);
+    assert_eq!(frame.region_bytes(&regions[1]), &[0x80]);
+
+    // 描き直しても内容が同じなら送らない
+    let mut redrawn = frame.clone();
+    render(&DisplayCommand::Clear, &mut redrawn).unwrap();
+    redrawn.set_pixel(Point::new(10, 0), BinaryColor::On);
+    redrawn.set_pixel(Point::new(20, 7), BinaryColor::On);
+    redrawn.set_pixel(Point::new(127, 63), BinaryColor::On);
+    assert!(redrawn.dirty_regions(&frame).is_empty());
+}
+
+#[test]
+fn test_small_update_is_small() {
+    let mut shown = FrameBuffer::new();
+    render(&DisplayCommand::DrawText { text: "Count: 0".to_string(), point: Point::new(0, 20) }, &mut shown).unwrap();
+
+    // 数字 1 文字分 (6x10) の書き換えは 2 ページ x 6 列以内
+    let mut frame = shown.clone();
+    let area = Rectangle::new(Point::new(42, 20), Size::new(6, 10));
+    render(&DisplayCommand::ClearRegion { area }, &mut frame).unwrap();
+    render(&DisplayCommand::DrawText { text: "1".to_string(), point: Point::new(42, 20) }, &mut frame).unwrap();
+    let regions = frame.dirty_regions(&shown);
+    assert_eq!(regions.iter().map(|region| region.page).collect::<Vec<_>>(), vec![2, 3]);
+    assert!(regions.iter().all(|region| region.start_column >= 42 && region.end_column <= 48));
+
+    // 全体が変われば 1KiB
+    let mut inverted = shown.clone();
+    render(&DisplayCommand::InvertRegion { area: Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT)) }, &mut inverted).unwrap();
+    let bytes: usize = inverted.dirty_regions(&shown).iter().map(|region| region.len()).sum();
+    assert_eq!(bytes, BUFFER_SIZE);
+}
+
+#[test]
+fn test_flush_stats() {
+    let mut stats = FlushStats::new();
+    assert_eq!(stats.mean_us(), 0);
+    assert_eq!(stats.estimate_us(10), None);
+
+    stats.record(25_000, 1024);
+    stats.record(1_000, 16);
+    assert_eq!(stats.count, 2);
+    assert_eq!((stats.last_us, stats.last_bytes), (1_000, 16));
+    assert_eq!(stats.max_us, 25_000);
+    assert_eq!(stats.mean_us(), 13_000);
+    // 26ms で 1040 バイト = 25us/バイト
+    assert_eq!(stats.estimate_us(12), Some(300));
+}
@@ -0,0 +1,157 @@
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use rustorch_test::display_command::*;
+use rustorch_test::frame_buffer::*;
+use rustorch_test::grayscale::*;
+
+const STARTUP: &[u8] = include_bytes!("../../rustorch/asserts/images/pomodoro_startup.gray.bmp");
+
+// 各画素が点灯しているサブフレームの数
+fn on_counts(subframes: &[Subframe], image: &GrayscaleImage, top_left: Point) -> Vec<usize> {
+    let size = image.size();
+    (0..size.height).flat_map(|y| (0..size.width).map(move |x| (x, y))).map(|(x, y)| {
+        let point = top_left + Point::new(x as i32, y as i32);
+        subframes.iter().filter(|subframe| subframe.frame.get_pixel(point).is_on()).count()
+    }).collect()
+}
+
+#[test]
+fn test_image() {
+    let image = GrayscaleImage::from_luminance(4, 1, &[0, 80, 170, 255], 4).unwrap();
+    assert_eq!((0..4).map(|x| image.get(x, 0)).collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+    assert_eq!(image.size(), Size::new(4, 1));
+    assert_eq!(image.levels(), 4);
+
+    assert_eq!(GrayscaleImage::new(2, 1, 1, vec![0, 0]), Err(GrayscaleError::InvalidLevels(1)));
+    assert_eq!(GrayscaleImage::new(2, 1, 9, vec![0, 0]), Err(GrayscaleError::InvalidLevels(9)));
+    assert_eq!(GrayscaleImage::new(2, 1, 4, vec![0]), Err(GrayscaleError::InvalidPixels));
+    assert_eq!(GrayscaleImage::new(2, 1, 4, vec![0, 4]), Err(GrayscaleError::InvalidPixels));
+    assert_eq!(GrayscaleImage::from_bmp(b"not a bmp", 4), Err(GrayscaleError::InvalidImage));
+
+    let startup = GrayscaleImage::from_bmp(STARTUP, 8).unwrap();
+    assert_eq!(startup.size(), Size::new(WIDTH, HEIGHT));
+    // 中間の階調を含む
+    let values: Vec<u8> = (0..HEIGHT).flat_map(|y| (0..WIDTH).map(move |x| (x, y))).map(|(x, y)| startup.get(x, y)).collect();
+    assert!(values.iter().any(|value| *value > 0 && *value < 7));
+}
+
+#[test]
+fn test_pwm() {
+    let image = GrayscaleImage::new(8, 1, 8, (0..8).collect()).unwrap();
+    assert_eq!(subframe_count(8, GrayscaleMethod::Pwm), 7);
+    let planes = subframes(&FrameBuffer::new(), &image, Point::new(10, 20), GrayscaleMethod::Pwm);
+    assert_eq!(planes.len(), 7);
+    assert!(planes.iter().all(|subframe| subframe.contrast == MAX_CONTRAST));
+    // 値の数だけ点灯する
+    assert_eq!(on_counts(&planes, &image, Point::new(10, 20)), vec![0, 1, 2, 3, 4, 5, 6, 7]);
+    // 点灯するサブフレームを散らすので、1 枚に偏らない
+    let per_subframe: Vec<u32> = planes.iter().map(|subframe| subframe.frame.count_on()).collect();
+    assert!(per_subframe.iter().all(|count| *count == 4), "{:?}", per_subframe);
+}
+
+#[test]
+fn test_weighted_planes() {
+    assert_eq!(subframe_count(2, GrayscaleMethod::WeightedPlanes), 1);
+    assert_eq!(subframe_count(4, GrayscaleMethod::WeightedPlanes), 2);
+    assert_eq!(subframe_count(5, GrayscaleMethod::WeightedPlanes), 3);
+    assert_eq!(subframe_count(8, GrayscaleMethod::WeightedPlanes), 3);
+
+    let image = GrayscaleImage::new(4, 1, 4, vec![0, 1, 2, 3]).unwrap();
+    let planes = subframes(&FrameBuffer::new(), &image, Point::zero(), GrayscaleMethod::WeightedPlanes);
+    // 下位ビットのプレーンは半分のコントラスト
+    assert_eq!(planes.iter().map(|subframe| subframe.contrast).collect::<Vec<_>>(), vec![MAX_CONTRAST >> 1, MAX_CONTRAST]);
+    let bits = |subframe: &Subframe| (0..4).map(|x| subframe.frame.get_pixel(Point::new(x, 0)).is_on()).collect::<Vec<_>>();
+    assert_eq!(bits(&planes[0]), vec![false, true, false, true]);
+    assert_eq!(bits(&planes[1]), vec![false, false, true, true]);
+
+    // 2^bits 段階でない場合は引き伸ばす (5 段階の 2 は 8 段階の 4 なので最上位ビットだけ、最大値は全部のプレーンで点灯)
+    let image = GrayscaleImage::new(3, 1, 5, vec![0, 2, 4]).unwrap();
+    let stretched = subframes(&FrameBuffer::new(), &image, Point::zero(), GrayscaleMethod::WeightedPlanes);
+    assert_eq!(on_counts(&stretched, &image, Point::zero()), vec![0, 1, 3]);
+    assert!(stretched[2].frame.get_pixel(Point::new(1, 0)).is_on());
+}
+
+#[test]
+fn test_player() {
+    let mut base = FrameBuffer::new();
+    base.set_pixel(Point::new(100, 0), BinaryColor::On);
+    base.set_pixel(Point::new(0, 0), BinaryColor::On);
+    let image = GrayscaleImage::new(2, 1, 3, vec![0, 1]).unwrap();
+    let mut player = GrayscalePlayer::new(&base, image, Point::zero(), GrayscaleMethod::Pwm);
+
+    // 画像の外は背景のまま、画像の中は背景を上書きする
+    let first = player.next_subframe().frame.clone();
+    let second = player.next_subframe().frame.clone();
+    assert_ne!(first.get_pixel(Point::new(1, 0)), second.get_pixel(Point::new(1, 0)));
+    for frame in [&first, &second] {
+        assert_eq!(frame.get_pixel(Point::new(0, 0)), BinaryColor::Off);
+        assert_eq!(frame.get_pixel(Point::new(100, 0)), BinaryColor::On);
+    }
+    // 一巡したら最初に戻る
+    assert_eq!(player.next_subframe().frame.count_on(), first.count_on());
+
+    // 背景の差し替え
+    base.set_pixel(Point::new(100, 0), BinaryColor::Off);
+    player.set_base(&base);
+    assert_eq!(player.next_subframe().frame.get_pixel(Point::new(100, 0)), BinaryColor::Off);
+}
+
+#[test]
+fn test_flush_timing() {
+    assert_eq!(I2cTiming::STANDARD_400K.flush_us(&[]), 0);
+    // 範囲の指定 6 バイト + データ 16 バイト + ヘッダ 2 バイト x 2 トランザクション = 26 バイト (234 クロック)
+    let region = DirtyRegion { page: 0, start_column: 0, end_column: 16 };
+    assert_eq!(I2cTiming::STANDARD_400K.flush_us(&[region]), 585 + 80);
+    assert_eq!(I2cTiming::FAST_1M.flush_us(&[region]), 234 + 80);
+    // 17 バイトはデータが 2 トランザクションになる
+    let region = DirtyRegion { page: 0, start_column: 0, end_column: 17 };
+    assert_eq!(I2cTiming::FAST_1M.flush_us(&[region]), (6 + 17 + 3 * 2) * 9 + 3 * 40);
+
+    // 全画面は 400kHz で 25〜30ms ほど
+    let mut on = FrameBuffer::new();
+    on.clear(BinaryColor::On).unwrap();
+    let full = I2cTiming::STANDARD_400K.flush_us(&on.dirty_regions(&FrameBuffer::new()));
+    assert!((25_000..32_000).contains(&full), "{}", full);
+}
+
+#[test]
+fn test_estimate() {
+    // 全画面が毎回変わる場合
+    let mut on = FrameBuffer::new();
+    on.clear(BinaryColor::On).unwrap();
+    let frames = vec![Subframe { frame: on, contrast: MAX_CONTRAST }, Subframe { frame: FrameBuffer::new(), contrast: MAX_CONTRAST }];
+    let standard = GrayscaleEstimate::estimate(&frames, &I2cTiming::STANDARD_400K);
+    let fast = GrayscaleEstimate::estimate(&frames, &I2cTiming::FAST_1M);
+    assert_eq!(standard.subframes, 2);
+    assert_eq!(standard.bytes_per_cycle, 2 * BUFFER_SIZE);
+    assert!(fast.frame_rate() > 2.0 * standard.frame_rate());
+    assert_eq!(standard.subframe_rate(), standard.frame_rate() * 2.0);
+
+    // 同じサブフレームなら何も送らない
+    let same = vec![frames[1].clone(), frames[1].clone()];
+    let estimate = GrayscaleEstimate::estimate(&same, &I2cTiming::STANDARD_400K);
+    assert_eq!(estimate.bytes_per_cycle, 0);
+    assert_eq!(estimate.frame_rate(), f32::INFINITY);
+
+    // 準備中の画面 (4 階調) は変わった範囲だけを送るので、一巡の転送量は全画面 2 枚分より少ない
+    // (フレームレートは I2cTiming の仮の値に左右されるのでここでは確かめず、実機の Gray benchmark で計測する)
+    let startup = GrayscaleImage::from_bmp(STARTUP, 4).unwrap();
+    let planes = subframes(&FrameBuffer::new(), &startup, Point::zero(), GrayscaleMethod::WeightedPlanes);
+    let estimate = GrayscaleEstimate::estimate(&planes, &I2cTiming::STANDARD_400K);
+    assert!(estimate.bytes_per_cycle < 2 * BUFFER_SIZE);
+}
+
+#[test]
+fn test_display_commands() {
+    let image = GrayscaleImage::new(2, 1, 4, vec![3, 3]).unwrap();
+    let mut frame = DisplayFrame::new();
+    frame.clear()
+        .show_grayscale(image.clone(), Point::zero(), GrayscaleMethod::Pwm)
+        .hide_grayscale();
+
+    // 描いた画面には入らない
+    let mut buffer = FrameBuffer::new();
+    render_frame(&frame, &mut buffer).unwrap();
+    assert_eq!(buffer.count_on(), 0);
+    assert!(matches!(frame.commands()[1], DisplayCommand::ShowGrayscale { method: GrayscaleMethod::Pwm, .. }));
+    assert!(matches!(frame.commands()[2], DisplayCommand::HideGrayscale));
+}
@@ -0,0 +1,158 @@
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use rustorch_test::display_command::*;
+use rustorch_test::frame_buffer::*;
+use rustorch_test::image_asset::*;
+
+// 横方向のグラデーション (0..=255)
+fn gradient(width: u32, height: u32) -> GrayImage {
+    let pixels = (0..height).flat_map(|_| (0..width).map(move |x| (x * 255 / (width - 1)) as u8)).collect();
+    GrayImage::new(width, height, pixels).unwrap()
+}
+
+fn count_on(image: &PackedImage) -> usize {
+    (0..image.height).map(|y| (0..image.width).filter(|x| image.get(*x, y)).count()).sum()
+}
+
+#[test]
+fn test_gray_image() {
+    assert_eq!(GrayImage::new(2, 2, vec![0; 3]), Err(ImageAssetError::InvalidPixels));
+
+    // 白、黒、半透明の白、透明な白
+    let rgba = [255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 128, 255, 255, 255, 0];
+    let image = GrayImage::from_rgba(4, 1, &rgba).unwrap();
+    assert_eq!(image.pixels, vec![255, 0, 128, 0]);
+    // 緑が一番明るい
+    let image = GrayImage::from_rgba(3, 1, &[255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255]).unwrap();
+    assert!(image.get(1, 0) > image.get(0, 0) && image.get(0, 0) > image.get(2, 0));
+    assert_eq!(GrayImage::from_rgba(2, 1, &rgba), Err(ImageAssetError::InvalidPixels));
+}
+
+#[test]
+fn test_packed_layout() {
+    // ページ形式: 1 バイトが縦 8 ピクセル (最下位ビットが上)
+    let mut image = PackedImage::new(3, 10);
+    assert_eq!(image.pages.len(), 6);
+    image.set(0, 0, true);
+    image.set(2, 9, true);
+    assert_eq!(image.pages, vec![0x01, 0, 0, 0, 0, 0x02]);
+    image.set(0, 0, false);
+    assert!(!image.get(0, 0));
+    assert!(image.get(2, 9));
+}
+
+#[test]
+fn test_dither() {
+    let image = gradient(64, 16);
+
+    let threshold = dither(&image, Dither::Threshold(128));
+    assert!(!threshold.get(31, 0));
+    assert!(threshold.get(32, 0));
+    assert_eq!(count_on(&threshold), 32 * 16);
+
+    // 階調に応じた密度になる (左端は全部消灯、右端は全部点灯)
+    for method in [Dither::FloydSteinberg, Dither::Ordered] {
+        let dithered = dither(&image, method);
+        let column_on = |x: u32| (0..16).filter(|y| dithered.get(x, *y)).count();
+        assert_eq!(column_on(0), 0, "{:?}", method);
+        assert_eq!(column_on(63), 16, "{:?}", method);
+        let left: usize = (0..16).map(column_on).sum();
+        let middle: usize = (24..40).map(column_on).sum();
+        let right: usize = (48..64).map(column_on).sum();
+        assert!(left < middle && middle < right, "{:?}", method);
+        // 全体でおよそ半分
+        let on = count_on(&dithered) as i32;
+        assert!((on - 512).abs() < 64, "{:?}: {}", method, on);
+    }
+
+    // 組織的ディザは 50% の灰色を市松模様にする
+    let gray = GrayImage::new(4, 4, vec![128; 16]).unwrap();
+    let ordered = dither(&gray, Dither::Ordered);
+    assert_eq!(count_on(&ordered), 8);
+    assert_ne!(ordered.get(0, 0), ordered.get(1, 0));
+    assert_ne!(ordered.get(0, 0), ordered.get(0, 1));
+}
+
+#[test]
+fn test_rle() {
+    let cases: Vec<Vec<u8>> = vec![
+        vec![],
+        vec![1],
+        vec![1, 2, 3],
+        vec![7; 2],
+        vec![7; 129],
+        vec![7; 130],
+        vec![0; 1024],
+        (0..=255).collect(),
+        [vec![1, 2], vec![0; 100], vec![3], vec![0xFF; 3]].concat(),
+    ];
+    for data in cases.iter() {
+        let encoded = rle_encode(data);
+        assert_eq!(&rle_decode(&encoded, data.len()).unwrap(), data);
+    }
+    assert_eq!(rle_encode(&[7; 129]), vec![0xFF, 7]);
+    assert_eq!(rle_encode(&[1, 2, 3, 3]), vec![0x01, 1, 2, 0x80, 3]);
+    // 真っ暗な画面は 16 バイト
+    assert_eq!(rle_encode(&[0; BUFFER_SIZE]).len(), 16);
+
+    assert_eq!(rle_decode(&[0x02, 1, 2], 3), Err(ImageAssetError::TruncatedData));
+    assert_eq!(rle_decode(&[0x85], 7), Err(ImageAssetError::TruncatedData));
+    assert_eq!(rle_decode(&[0x80, 1], 3), Err(ImageAssetError::SizeMismatch(3, 2)));
+}
+
+#[test]
+fn test_convert() {
+    let image = gradient(16, 8);
+    let (encoding, data) = ImageConvert::new().convert(&image);
+    assert_eq!(encoding, Encoding::Raw);
+    assert_eq!(data, [vec![0x00; 8], vec![0xFF; 8]].concat());
+
+    // 反転
+    let (_, data) = ImageConvert::new().invert(true).convert(&image);
+    assert_eq!(data, [vec![0xFF; 8], vec![0x00; 8]].concat());
+
+    // 圧縮して小さくなる場合だけ RLE にする
+    let (encoding, data) = ImageConvert::new().compress(true).convert(&image);
+    assert_eq!(encoding, Encoding::Rle);
+    assert_eq!(data, vec![0x86, 0x00, 0x86, 0xFF]);
+    let noise = GrayImage::new(16, 8, (0..128).map(|i| if i % 3 == 0 { 255 } else { 0 }).collect()).unwrap();
+    let (encoding, _) = ImageConvert::new().compress(true).convert(&noise);
+    assert_eq!(encoding, Encoding::Raw);
+}
+
+#[test]
+fn test_asset() {
+    static RLE: [u8; 4] = [0x86, 0x00, 0x86, 0xFF];
+    let asset = ImageAsset { width: 16, height: 8, encoding: Encoding::Rle, data: &RLE };
+    let image = asset.decode().unwrap();
+    assert!(!image.get(7, 3));
+    assert!(image.get(8, 3));
+
+    let truncated = ImageAsset { width: 16, height: 16, ..asset };
+    assert_eq!(truncated.decode(), Err(ImageAssetError::SizeMismatch(32, 16)));
+    let raw = ImageAsset { encoding: Encoding::Raw, ..asset };
+    assert_eq!(raw.decode(), Err(ImageAssetError::SizeMismatch(16, 4)));
+
+    // 描いた範囲だけ上書きする
+    let mut frame = FrameBuffer::new();
+    frame.set_pixel(Point::new(0, 20), BinaryColor::On);
+    frame.set_pixel(Point::new(100, 0), BinaryColor::On);
+    render(&DisplayCommand::DrawAsset { asset, point: Point::new(0, 20) }, &mut frame).unwrap();
+    assert_eq!(frame.get_pixel(Point::new(0, 20)), BinaryColor::Off);
+    assert_eq!(frame.get_pixel(Point::new(8, 20)), BinaryColor::On);
+    assert_eq!(frame.get_pixel(Point::new(15, 27)), BinaryColor::On);
+    assert_eq!(frame.get_pixel(Point::new(100, 0)), BinaryColor::On);
+    assert_eq!(frame.count_on(), 8 * 8 + 1);
+
+    let result = render(&DisplayCommand::DrawAsset { asset: truncated, point: Point::zero() }, &mut frame);
+    assert_eq!(result.err(), Some(RenderError::Asset(ImageAssetError::SizeMismatch(32, 16))));
+}
+
+#[test]
+fn test_rust_source() {
+    let source = to_rust_source("pomodoro_startup", &gradient(16, 8), &ImageConvert::new().compress(true));
+    assert!(source.starts_with("// 16x8, Rle, 4 bytes\n"));
+    assert!(source.contains("pub const POMODORO_STARTUP: crate::image_asset::ImageAsset"));
+    assert!(source.contains("    width: 16,\n    height: 8,\n"));
+    assert!(source.contains("encoding: crate::image_asset::Encoding::Rle,"));
+    assert!(source.contains("        0x86, 0x00, 0x86, 0xFF,\n"));
+}
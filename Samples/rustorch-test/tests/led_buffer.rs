@@ -0,0 +1,99 @@
+use rustorch_test::led_buffer::{LedFrame, LedFrameBuffer};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+#[test]
+fn test_commit_is_applied_at_digit0() {
+    let mut frame_buffer = LedFrameBuffer::new();
+    let mut scanner = frame_buffer.scanner();
+
+    assert_eq!(scanner.digit(0), (0x00, 100));
+
+    frame_buffer.back_mut().segments = [ 0x11, 0x22, 0x33, 0x44 ];
+    frame_buffer.back_mut().brightness = [ 10, 20, 30, 40 ];
+    // コミット前は反映されない
+    assert_eq!(scanner.digit(1), (0x00, 100));
+    frame_buffer.commit();
+    // 走査の途中でコミットされても 0 桁目に戻るまでは反映されない
+    assert_eq!(scanner.digit(2), (0x00, 100));
+    assert_eq!(scanner.digit(3), (0x00, 100));
+    assert_eq!(scanner.digit(0), (0x11, 10));
+    assert_eq!(scanner.digit(1), (0x22, 20));
+    assert_eq!(scanner.digit(2), (0x33, 30));
+    assert_eq!(scanner.digit(3), (0x44, 40));
+}
+
+#[test]
+fn test_commit_is_never_seen_partially() {
+    let mut frame_buffer = LedFrameBuffer::new();
+    let mut scanner = frame_buffer.scanner();
+    let finished = Arc::new(AtomicBool::new(false));
+    let finished_clone = Arc::clone(&finished);
+
+    // 表示内容と輝度が常に全桁同じ値になるフレームを書き込み続ける
+    let writer = thread::spawn(move || {
+        for n in 0..20000u32 {
+            let value = (n % 251) as u8;
+            frame_buffer.back_mut().segments = [ value; 4 ];
+            frame_buffer.commit();
+            frame_buffer.back_mut().brightness = [ value; 4 ];
+            frame_buffer.commit();
+        }
+        finished_clone.store(true, Ordering::Release);
+    });
+
+    // 1 回の走査で見える内容が混ざっていないことを確認する
+    let mut scan_count = 0;
+    while !finished.load(Ordering::Acquire) || scan_count < 1000 {
+        let mut scanned = LedFrame::default();
+        for index in 0..4 {
+            let (segments, brightness) = scanner.digit(index);
+            scanned.segments[index] = segments;
+            scanned.brightness[index] = brightness;
+        }
+        assert!(scanned.segments.iter().all(|&v| v == scanned.segments[0]), "{:?}", scanned);
+        assert!(scanned.brightness.iter().all(|&v| v == scanned.brightness[0]), "{:?}", scanned);
+        scan_count += 1;
+    }
+    writer.join().unwrap();
+}
+
+#[test]
+fn test_frame_visibility() {
+    assert!(!LedFrame::default().is_visible());
+    assert!(LedFrame { segments: [ 0, 0, 0x60, 0 ], brightness: [ 100; 4 ] }.is_visible());
+    // 輝度 0% の桁は点灯しない
+    assert!(!LedFrame { segments: [ 0xFC; 4 ], brightness: [ 0; 4 ] }.is_visible());
+    assert!(!LedFrame { segments: [ 0xFC, 0, 0, 0 ], brightness: [ 0, 100, 100, 100 ] }.is_visible());
+    assert!(LedFrame { segments: [ 0xFC, 0, 0, 0 ], brightness: [ 1, 0, 0, 0 ] }.is_visible());
+}
+
+#[test]
+fn test_scanner_waits_while_nothing_is_visible() {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let mut frame_buffer = LedFrameBuffer::new();
+    let mut scanner = frame_buffer.scanner();
+    scanner.digit(0);
+    assert!(!scanner.is_visible());
+
+    let (tx, rx) = mpsc::channel();
+    let waiter = thread::spawn(move || {
+        scanner.wait_until_visible();
+        tx.send(scanner.digit(0)).unwrap();
+    });
+
+    // 何も点灯しない内容のコミットでは起きない
+    frame_buffer.back_mut().brightness = [ 0; 4 ];
+    frame_buffer.back_mut().segments = [ 0xFC; 4 ];
+    frame_buffer.commit();
+    assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+
+    // 点灯する内容のコミットで再開する
+    frame_buffer.back_mut().brightness = [ 50; 4 ];
+    frame_buffer.commit();
+    assert_eq!(rx.recv_timeout(Duration::from_secs(5)), Ok((0xFC, 50)));
+    waiter.join().unwrap();
+}
@@ -0,0 +1,79 @@
+use rustorch_test::led_config::{LedConfig, LedConfigError, TimingStats, MIN_DEAD_TIME_US};
+
+#[test]
+#[cfg(not(feature = "led-pwm"))]
+fn test_default_config() {
+    let config = LedConfig::new();
+    assert_eq!(config.validate(), Ok(()));
+    // 従来の固定値 (1 桁 4ms)
+    assert_eq!(config.digit_period_ticks(), 4);
+    assert_eq!(config.digit_period_us(), 4000);
+    assert_eq!(config.actual_refresh_rate_mhz(), 62500);
+    assert_eq!(config.on_time_us(100), 4000);
+    assert_eq!(config.on_time_us(50), 2000);
+    assert_eq!(config.on_time_us(0), 0);
+}
+
+#[test]
+fn test_refresh_rate() {
+    assert_eq!(LedConfig::new().refresh_rate(250).digit_period_ticks(), 1);
+    assert_eq!(LedConfig::new().refresh_rate(125).digit_period_ticks(), 2);
+    // tick 単位に丸められる
+    assert_eq!(LedConfig::new().refresh_rate(100).digit_period_ticks(), 3);
+    assert_eq!(LedConfig::new().refresh_rate(100).actual_refresh_rate_mhz(), 83333);
+    assert_eq!(LedConfig::new().refresh_rate(1).digit_period_ticks(), 250);
+
+    assert_eq!(LedConfig::new().refresh_rate(0).validate(), Err(LedConfigError::RefreshRateOutOfRange(0)));
+    assert_eq!(LedConfig::new().refresh_rate(251).validate(), Err(LedConfigError::RefreshRateOutOfRange(251)));
+}
+
+#[test]
+fn test_duty_cycle_and_dead_time() {
+    let config = LedConfig::new().refresh_rate(125).duty_cycle(90).dead_time(100);
+    assert_eq!(config.validate(), Ok(()));
+    assert_eq!(config.max_on_time_us(), 1800);
+    assert_eq!(config.on_time_us(50), 900);
+    // 輝度 100% 超過は 100% 扱い
+    assert_eq!(config.on_time_us(200), 1800);
+
+    assert_eq!(LedConfig::new().duty_cycle(0).validate(), Err(LedConfigError::DutyCycleOutOfRange(0)));
+    assert_eq!(LedConfig::new().duty_cycle(101).validate(), Err(LedConfigError::DutyCycleOutOfRange(101)));
+    // デッドタイム + 最大点灯時間が周期を超える
+    assert_eq!(LedConfig::new().refresh_rate(125).duty_cycle(90).dead_time(201).validate(), Err(LedConfigError::DeadTimeTooLong(201)));
+    assert_eq!(LedConfig::new().refresh_rate(125).duty_cycle(90).dead_time(200).validate(), Ok(()));
+}
+
+#[test]
+#[cfg(feature = "led-pwm")]
+fn test_pwm_dead_time() {
+    // 消灯が反映されるまで PWM の 1 周期 (100us) かかる
+    assert_eq!(MIN_DEAD_TIME_US, 100);
+    let config = LedConfig::new();
+    assert_eq!(config.validate(), Ok(()));
+    assert_eq!(config.dead_time_us, 100);
+    assert!(config.dead_time_us + config.max_on_time_us() <= config.digit_period_us());
+
+    assert_eq!(LedConfig::new().refresh_rate(125).duty_cycle(90).dead_time(99).validate(), Err(LedConfigError::DeadTimeTooShort(99)));
+    assert_eq!(LedConfig::new().refresh_rate(125).duty_cycle(90).dead_time(0).validate(), Err(LedConfigError::DeadTimeTooShort(0)));
+    assert_eq!(LedConfig::new().refresh_rate(125).duty_cycle(90).dead_time(100).validate(), Ok(()));
+}
+
+#[test]
+#[cfg(not(feature = "led-pwm"))]
+fn test_timer_dead_time() {
+    assert_eq!(MIN_DEAD_TIME_US, 0);
+    assert_eq!(LedConfig::new().dead_time(0).validate(), Ok(()));
+}
+
+#[test]
+fn test_timing_stats() {
+    let mut stats = TimingStats::new();
+    assert_eq!(stats.mean_abs_jitter_us(), 0);
+    stats.record(4000, 4000);
+    stats.record(4030, 4000);
+    stats.record(3980, 4000);
+    assert_eq!(stats.count, 3);
+    assert_eq!(stats.min_jitter_us, -20);
+    assert_eq!(stats.max_jitter_us, 30);
+    assert_eq!(stats.mean_abs_jitter_us(), 16);
+}
@@ -0,0 +1,69 @@
+use rustorch_test::led_format::*;
+
+const N: [u8; 10] = NUMBER_SEGMENT_TABLE;
+const DOT: u8 = SEGMENT_DOT;
+const MINUS: u8 = SEGMENT_MINUS;
+
+#[test]
+fn test_format_integer() {
+    assert_eq!(format_integer(0, Padding::Space), [ 0x00, 0x00, 0x00, N[0] ]);
+    assert_eq!(format_integer(0, Padding::Zero), [ N[0], N[0], N[0], N[0] ]);
+    assert_eq!(format_integer(42, Padding::Space), [ 0x00, 0x00, N[4], N[2] ]);
+    assert_eq!(format_integer(42, Padding::Zero), [ N[0], N[0], N[4], N[2] ]);
+    assert_eq!(format_integer(9999, Padding::Space), [ N[9], N[9], N[9], N[9] ]);
+    assert_eq!(format_integer(-5, Padding::Space), [ 0x00, 0x00, MINUS, N[5] ]);
+    assert_eq!(format_integer(-5, Padding::Zero), [ MINUS, N[0], N[0], N[5] ]);
+    assert_eq!(format_integer(-999, Padding::Space), [ MINUS, N[9], N[9], N[9] ]);
+    // 範囲外
+    assert_eq!(format_integer(10000, Padding::Space), OVERFLOW_PATTERN);
+    assert_eq!(format_integer(-1000, Padding::Space), UNDERFLOW_PATTERN);
+    assert_eq!(format_integer(i32::MIN, Padding::Zero), UNDERFLOW_PATTERN);
+}
+
+#[test]
+fn test_format_fixed() {
+    assert_eq!(format_fixed(1234, 1, Padding::Space), [ N[1], N[2], N[3] | DOT, N[4] ]);
+    assert_eq!(format_fixed(5, 1, Padding::Space), [ 0x00, 0x00, N[0] | DOT, N[5] ]);
+    assert_eq!(format_fixed(5, 2, Padding::Space), [ 0x00, N[0] | DOT, N[0], N[5] ]);
+    assert_eq!(format_fixed(5, 3, Padding::Space), [ N[0] | DOT, N[0], N[0], N[5] ]);
+    assert_eq!(format_fixed(5, 1, Padding::Zero), [ N[0], N[0], N[0] | DOT, N[5] ]);
+    assert_eq!(format_fixed(-5, 1, Padding::Space), [ 0x00, MINUS, N[0] | DOT, N[5] ]);
+    assert_eq!(format_fixed(-125, 2, Padding::Space), [ MINUS, N[1] | DOT, N[2], N[5] ]);
+    // 範囲外
+    assert_eq!(format_fixed(-5, 3, Padding::Space), UNDERFLOW_PATTERN);
+    assert_eq!(format_fixed(10000, 1, Padding::Space), OVERFLOW_PATTERN);
+    assert_eq!(format_fixed(1, 4, Padding::Space), OVERFLOW_PATTERN);
+    // 小数桁 0 は整数と同じ
+    assert_eq!(format_fixed(42, 0, Padding::Space), format_integer(42, Padding::Space));
+}
+
+#[test]
+fn test_format_time() {
+    assert_eq!(format_minutes_seconds(25 * 60, true, Padding::Space), [ N[2], N[5] | DOT, N[0], N[0] ]);
+    assert_eq!(format_minutes_seconds(5 * 60 + 7, false, Padding::Space), [ 0x00, N[5], N[0], N[7] ]);
+    assert_eq!(format_minutes_seconds(5 * 60 + 7, true, Padding::Zero), [ N[0], N[5] | DOT, N[0], N[7] ]);
+    assert_eq!(format_minutes_seconds(0, true, Padding::Space), [ 0x00, N[0] | DOT, N[0], N[0] ]);
+    assert_eq!(format_minutes_seconds(99 * 60 + 59, true, Padding::Space), [ N[9], N[9] | DOT, N[5], N[9] ]);
+    assert_eq!(format_minutes_seconds(100 * 60, true, Padding::Space), OVERFLOW_PATTERN);
+
+    assert_eq!(format_hours_minutes(13 * 60 + 45, true, Padding::Zero), [ N[1], N[3] | DOT, N[4], N[5] ]);
+    assert_eq!(format_hours_minutes(9 * 60 + 5, true, Padding::Zero), [ N[0], N[9] | DOT, N[0], N[5] ]);
+    assert_eq!(format_hours_minutes(100 * 60, true, Padding::Zero), OVERFLOW_PATTERN);
+}
+
+#[test]
+fn test_format_hex() {
+    assert_eq!(format_hex(0xBEEF, Padding::Space), [ HEX_SEGMENT_TABLE[0xB], HEX_SEGMENT_TABLE[0xE], HEX_SEGMENT_TABLE[0xE], HEX_SEGMENT_TABLE[0xF] ]);
+    assert_eq!(format_hex(0x1A, Padding::Space), [ 0x00, 0x00, N[1], HEX_SEGMENT_TABLE[0xA] ]);
+    assert_eq!(format_hex(0x1A, Padding::Zero), [ N[0], N[0], N[1], HEX_SEGMENT_TABLE[0xA] ]);
+    assert_eq!(format_hex(0x10000, Padding::Zero), OVERFLOW_PATTERN);
+}
+
+#[test]
+fn test_format_percent() {
+    assert_eq!(format_percent(0, Padding::Space), [ 0x00, 0x00, N[0], SEGMENT_PERCENT ]);
+    assert_eq!(format_percent(75, Padding::Space), [ 0x00, N[7], N[5], SEGMENT_PERCENT ]);
+    assert_eq!(format_percent(75, Padding::Zero), [ N[0], N[7], N[5], SEGMENT_PERCENT ]);
+    assert_eq!(format_percent(100, Padding::Space), [ N[1], N[0], N[0], SEGMENT_PERCENT ]);
+    assert_eq!(format_percent(1000, Padding::Space), OVERFLOW_PATTERN);
+}
@@ -0,0 +1,162 @@
+use rustorch_test::midi::{to_rust_source, MidiError, MidiImport, VoicePolicy};
+use rustorch_test::score::*;
+
+const QUARTER: u32 = TICKS_PER_QUARTER;
+// テストデータの時間単位 (4 分音符あたりの tick 数)
+const DIVISION: u32 = 96;
+
+fn note(midi: u8, ticks: u32) -> ScoreEvent {
+    ScoreEvent::Note { frequency: midi_note_to_frequency(midi), ticks }
+}
+
+fn variable_length(mut value: u32) -> Vec<u8> {
+    let mut bytes = vec![ (value & 0x7F) as u8 ];
+    value >>= 7;
+    while value > 0 {
+        bytes.insert(0, (value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes
+}
+
+// (デルタタイム, イベントのバイト列) の並びからトラックチャンクを作る
+fn track(events: &[(u32, &[u8])]) -> Vec<u8> {
+    let mut body = Vec::new();
+    for (delta, bytes) in events {
+        body.extend(variable_length(*delta));
+        body.extend_from_slice(bytes);
+    }
+    body.extend([ 0x00, 0xFF, 0x2F, 0x00 ]);
+    let mut chunk = b"MTrk".to_vec();
+    chunk.extend((body.len() as u32).to_be_bytes());
+    chunk.extend(body);
+    chunk
+}
+
+fn smf(format: u16, tracks: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = b"MThd".to_vec();
+    data.extend(6_u32.to_be_bytes());
+    data.extend(format.to_be_bytes());
+    data.extend((tracks.len() as u16).to_be_bytes());
+    data.extend((DIVISION as u16).to_be_bytes());
+    for track in tracks {
+        data.extend(track);
+    }
+    data
+}
+
+// C4 を 4 分音符で鳴らしている間に E4 を 8 分音符で重ねる (チャンネル 0)
+fn overlapping_notes() -> Vec<u8> {
+    smf(0, &[ track(&[
+        (0, &[ 0x90, 60, 100 ]),
+        (DIVISION / 4, &[ 0x90, 64, 100 ]),
+        (DIVISION / 2, &[ 0x80, 64, 0 ]),
+        (DIVISION / 4, &[ 0x80, 60, 0 ]),
+    ]) ])
+}
+
+#[test]
+fn test_voice_policy() {
+    let data = overlapping_notes();
+    let score = MidiImport::new().policy(VoicePolicy::Highest).convert(&data).unwrap();
+    assert_eq!(score.tempo_bpm, 120);
+    assert_eq!(score.gate_percent, 100);
+    assert_eq!(score.events, vec![ note(60, QUARTER / 4), note(64, QUARTER / 2), note(60, QUARTER / 4) ]);
+
+    let score = MidiImport::new().policy(VoicePolicy::Lowest).convert(&data).unwrap();
+    assert_eq!(score.events, vec![ note(60, QUARTER) ]);
+
+    // 後から押された E4 が離された後は、押されたままの C4 に戻る
+    let score = MidiImport::new().policy(VoicePolicy::Last).convert(&data).unwrap();
+    assert_eq!(score.events, vec![ note(60, QUARTER / 4), note(64, QUARTER / 2), note(60, QUARTER / 4) ]);
+}
+
+#[test]
+fn test_track_channel_and_tempo() {
+    // トラック 0: テンポ、トラック 1: チャンネル 0 のメロディとチャンネル 1 のベース
+    // ランニングステータス、ベロシティ 0 のノートオン、先頭の無音、プログラムチェンジも含める
+    let data = smf(1, &[
+        track(&[
+            (0, &[ 0xFF, 0x51, 0x03, 0x09, 0x27, 0xC0 ]),            // 100 bpm
+            (DIVISION * 2, &[ 0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20 ]), // 120 bpm
+        ]),
+        track(&[
+            (0, &[ 0xC0, 0x05 ]),
+            (0, &[ 0x91, 36, 80 ]),
+            (DIVISION, &[ 0x90, 72, 100 ]),
+            (DIVISION, &[ 74, 100 ]),
+            (0, &[ 72, 0 ]),
+            (DIVISION, &[ 0x80, 74, 0 ]),
+            (0, &[ 0x81, 36, 0 ]),
+        ]),
+    ]);
+
+    let score = MidiImport::new().track(1).channel(0).convert(&data).unwrap();
+    assert_eq!(score.tempo_bpm, 100);
+    assert_eq!(score.events, vec![
+        note(72, QUARTER),
+        ScoreEvent::Tempo { bpm: 120 },
+        note(74, QUARTER),
+    ]);
+
+    let score = MidiImport::new().channel(1).convert(&data).unwrap();
+    assert_eq!(score.events, vec![ note(36, QUARTER * 2), ScoreEvent::Tempo { bpm: 120 }, note(36, QUARTER) ]);
+
+    let score = MidiImport::new().track(1).policy(VoicePolicy::Lowest).convert(&data).unwrap();
+    assert_eq!(score.events, vec![ note(36, QUARTER * 2), ScoreEvent::Tempo { bpm: 120 }, note(36, QUARTER) ]);
+
+    assert_eq!(MidiImport::new().track(0).convert(&data), Err(MidiError::NoNotes));
+    assert_eq!(MidiImport::new().track(2).convert(&data), Err(MidiError::TrackNotFound(2)));
+}
+
+#[test]
+fn test_invalid_data() {
+    assert_eq!(MidiImport::new().convert(b"RIFF"), Err(MidiError::InvalidHeader));
+
+    let mut data = overlapping_notes();
+    data[9] = 2;
+    assert_eq!(MidiImport::new().convert(&data), Err(MidiError::UnsupportedFormat(2)));
+
+    let mut data = overlapping_notes();
+    data[12] = 0xE7;
+    assert_eq!(MidiImport::new().convert(&data), Err(MidiError::UnsupportedDivision(0xE760)));
+
+    // ステータスバイトなしで始まるトラック
+    let data = smf(0, &[ track(&[ (0, &[ 60, 100 ]) ]) ]);
+    assert_eq!(MidiImport::new().convert(&data), Err(MidiError::InvalidStatus(0)));
+}
+
+#[test]
+fn test_huge_lengths() {
+    // 32 ビット環境で足すと桁あふれする長さでもパニックせずエラーにする
+    let mut data = overlapping_notes();
+    data[18..22].copy_from_slice(&0xFFFF_FFFF_u32.to_be_bytes());
+    assert_eq!(MidiImport::new().convert(&data), Err(MidiError::InvalidHeader));
+
+    let mut data = overlapping_notes();
+    data[4..8].copy_from_slice(&0xFFFF_FFFF_u32.to_be_bytes());
+    assert_eq!(MidiImport::new().convert(&data), Err(MidiError::InvalidHeader));
+
+    // 可変長数値の最大値 (0x0FFFFFFF) の長さのメタイベント
+    let data = smf(0, &[ track(&[ (0, &[ 0xFF, 0x01, 0xFF, 0xFF, 0xFF, 0x7F ]) ]) ]);
+    assert_eq!(MidiImport::new().convert(&data), Err(MidiError::UnexpectedEnd(0)));
+}
+
+#[test]
+fn test_sample_file() {
+    let data = include_bytes!("../../rustorch/asserts/sounds/frere_jacques.mid");
+    let score = MidiImport::new().track(1).channel(0).convert(data).unwrap();
+    assert_eq!(score.tempo_bpm, 100);
+    assert!(score.validate().is_ok());
+    assert_eq!(score.events[0..2], [ note(60, QUARTER * 88 / 96), ScoreEvent::Rest { ticks: QUARTER * 8 / 96 } ]);
+    assert_eq!(score.total_ticks(), QUARTER * 32 - QUARTER * 8 / 96);
+
+    // チャンネルを絞らないと、メロディの切れ目でベースが鳴る
+    let score = MidiImport::new().convert(data).unwrap();
+    assert_eq!(score.events[0..2], [ note(60, QUARTER * 88 / 96), note(48, QUARTER * 8 / 96) ]);
+
+    let source = to_rust_source("frere_jacques", &score);
+    assert!(source.starts_with("pub fn frere_jacques() -> crate::score::Score {\n"));
+    assert!(source.contains("crate::score::ScoreEvent::Note { frequency: 262, ticks: 440 },\n"));
+    assert!(source.contains("tempo_bpm: 100,\n"));
+}
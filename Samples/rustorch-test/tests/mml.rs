@@ -0,0 +1,102 @@
+use rustorch_test::mml::{parse, MmlError, MmlErrorKind};
+use rustorch_test::score::*;
+
+const QUARTER: u32 = TICKS_PER_QUARTER;
+
+fn note(midi: u8, ticks: u32) -> ScoreEvent {
+    ScoreEvent::Note { frequency: midi_note_to_frequency(midi), ticks }
+}
+
+fn error(line: usize, column: usize, kind: MmlErrorKind) -> Result<Score, MmlError> {
+    Err(MmlError { line, column, kind })
+}
+
+#[test]
+fn test_midi_note_to_frequency() {
+    assert_eq!(midi_note_to_frequency(69), 440);
+    assert_eq!(midi_note_to_frequency(57), 220);
+    assert_eq!(midi_note_to_frequency(81), 880);
+    assert_eq!(midi_note_to_frequency(60), 262);
+    assert_eq!(midi_note_to_frequency(72), 523);
+    assert_eq!(midi_note_to_frequency(21), 28);
+    assert_eq!(midi_note_to_frequency(127), 12544);
+}
+
+#[test]
+fn test_parse_scale() {
+    let score = parse("T120 O4 L8 CDEFGAB>C").unwrap();
+    assert_eq!(score.tempo_bpm, 120);
+    assert_eq!(score.events, vec![
+        note(60, QUARTER / 2), note(62, QUARTER / 2), note(64, QUARTER / 2), note(65, QUARTER / 2),
+        note(67, QUARTER / 2), note(69, QUARTER / 2), note(71, QUARTER / 2), note(72, QUARTER / 2),
+    ]);
+    assert_eq!(score.loop_points, None);
+    assert_eq!(score.validate(), Ok(()));
+}
+
+#[test]
+fn test_parse_lengths_and_accidentals() {
+    // 音域の両端
+    assert_eq!(parse("O0 C- O8 B+").unwrap().events, vec![ note(11, QUARTER), note(120, QUARTER) ]);
+    let score = parse("c4 d+2 e-8. f#16 g1 a4.. r8 p2 n69 <c >>c").unwrap();
+    assert_eq!(score.events, vec![
+        note(60, QUARTER),
+        note(63, QUARTER * 2),
+        note(63, QUARTER / 2 + QUARTER / 4),
+        note(66, QUARTER / 4),
+        note(67, QUARTER * 4),
+        note(69, QUARTER + QUARTER / 2 + QUARTER / 4),
+        ScoreEvent::Rest { ticks: QUARTER / 2 },
+        ScoreEvent::Rest { ticks: QUARTER * 2 },
+        note(69, QUARTER),
+        note(48, QUARTER),
+        note(72, QUARTER),
+    ]);
+    // L の付点
+    let score = parse("L4. C").unwrap();
+    assert_eq!(score.events, vec![ note(60, QUARTER + QUARTER / 2) ]);
+    let score = parse("L4 C.").unwrap();
+    assert_eq!(score.events, vec![ note(60, QUARTER + QUARTER / 2) ]);
+}
+
+#[test]
+fn test_parse_tempo_tie_gate_loop() {
+    let score = parse("T90 Q6 C4&C8 $ D T150 E").unwrap();
+    assert_eq!(score.tempo_bpm, 90);
+    assert_eq!(score.gate_percent, 75);
+    assert_eq!(score.events, vec![
+        note(60, QUARTER + QUARTER / 2),
+        note(62, QUARTER),
+        ScoreEvent::Tempo { bpm: 150 },
+        note(64, QUARTER),
+    ]);
+    assert_eq!(score.loop_points, Some(LoopPoints { start: 1, end: 4, repeat: None }));
+    assert_eq!(score.validate(), Ok(()));
+    // 音符のないループ区間は無視する
+    assert_eq!(parse("C $ T100").unwrap().loop_points, None);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse("CDX"), error(1, 3, MmlErrorKind::UnexpectedCharacter('X')));
+    assert_eq!(parse("C D\n  E\n Z"), error(3, 2, MmlErrorKind::UnexpectedCharacter('Z')));
+    assert_eq!(parse("T"), error(1, 1, MmlErrorKind::MissingNumber('T')));
+    assert_eq!(parse("T0"), error(1, 1, MmlErrorKind::OutOfRange('T', 0)));
+    assert_eq!(parse("O9"), error(1, 1, MmlErrorKind::OutOfRange('O', 9)));
+    assert_eq!(parse("O8 >C"), error(1, 4, MmlErrorKind::OutOfRange('O', 9)));
+    assert_eq!(parse("O0 <"), error(1, 4, MmlErrorKind::OutOfRange('O', 0)));
+    assert_eq!(parse("Q9"), error(1, 1, MmlErrorKind::OutOfRange('Q', 9)));
+    assert_eq!(parse("N128"), error(1, 1, MmlErrorKind::OutOfRange('N', 128)));
+    assert_eq!(parse("C7"), error(1, 1, MmlErrorKind::InvalidLength(7)));
+    assert_eq!(parse("L0"), error(1, 1, MmlErrorKind::InvalidLength(0)));
+    assert_eq!(parse("C&D"), error(1, 2, MmlErrorKind::InvalidTie));
+    assert_eq!(parse("C&"), error(1, 2, MmlErrorKind::InvalidTie));
+    assert_eq!(parse("R&R"), error(1, 2, MmlErrorKind::InvalidTie));
+    assert_eq!(parse("C& T100 C"), error(1, 2, MmlErrorKind::InvalidTie));
+}
+
+#[test]
+fn test_error_display() {
+    let error = parse("CD\n  X").unwrap_err();
+    assert_eq!(error.to_string(), "2:3: unexpected character 'X'");
+}
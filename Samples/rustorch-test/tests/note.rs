@@ -0,0 +1,61 @@
+use rustorch_test::note::*;
+
+#[test]
+fn test_note_number() {
+    let note = Note::new(PitchClass::C, 4).unwrap();
+    assert_eq!(note.midi(), 60);
+    assert_eq!(note.pitch_class(), PitchClass::C);
+    assert_eq!(note.octave(), 4);
+
+    let note = Note::from_midi(70).unwrap();
+    assert_eq!(note.pitch_class(), PitchClass::ASharp);
+    assert_eq!(note.octave(), 4);
+    assert_eq!(note.to_string(), "A#4");
+
+    assert_eq!(Note::new(PitchClass::C, -1).unwrap().midi(), 0);
+    assert_eq!(Note::new(PitchClass::G, 9).unwrap().midi(), 127);
+    assert_eq!(Note::new(PitchClass::GSharp, 9), Err(NoteError::OutOfRange(128)));
+    assert_eq!(Note::new(PitchClass::B, -2), Err(NoteError::OutOfRange(-1)));
+    assert_eq!(Note::from_midi(128), Err(NoteError::OutOfRange(128)));
+}
+
+#[test]
+fn test_transpose_and_detune() {
+    let note = Note::new(PitchClass::A, 4).unwrap().detune(-30).unwrap();
+    assert_eq!(note.cents(), -30);
+    assert_eq!(note.to_string(), "A4-30c");
+
+    let transposed = note.transpose(3).unwrap();
+    assert_eq!(transposed.pitch_class(), PitchClass::C);
+    assert_eq!(transposed.octave(), 5);
+    assert_eq!(transposed.cents(), -30);
+    assert_eq!(note.transpose(-12).unwrap().midi(), 57);
+    assert_eq!(note.transpose(59), Err(NoteError::OutOfRange(128)));
+
+    assert_eq!(note.detune(101), Err(NoteError::CentsOutOfRange(101)));
+    assert_eq!(note.detune(-101), Err(NoteError::CentsOutOfRange(-101)));
+}
+
+#[test]
+fn test_frequency() {
+    let a4 = Note::new(PitchClass::A, 4).unwrap();
+    assert_eq!(a4.frequency_mhz(STANDARD_A4_HZ), 440_000);
+    assert_eq!(a4.frequency(), 440);
+    assert_eq!(Note::new(PitchClass::C, 4).unwrap().frequency_mhz(STANDARD_A4_HZ), 261_626);
+    assert_eq!(Note::new(PitchClass::A, 0).unwrap().frequency_mhz(STANDARD_A4_HZ), 27_500);
+    assert_eq!(Note::new(PitchClass::G, 9).unwrap().frequency(), 12_544);
+    assert_eq!(Note::from_midi(0).unwrap().frequency_mhz(STANDARD_A4_HZ), 8_176);
+
+    // 基準ピッチの変更
+    assert_eq!(a4.frequency_with_a4(442), 442);
+    assert_eq!(Note::new(PitchClass::A, 5).unwrap().frequency_with_a4(442), 884);
+    assert_eq!(Note::new(PitchClass::E, 5).unwrap().frequency_mhz(442), 662_252);
+
+    // ±50 セントは半音の中間、100 セントは半音上と同じ
+    assert_eq!(a4.detune(50).unwrap().frequency_mhz(STANDARD_A4_HZ), 452_893);
+    assert_eq!(a4.detune(-50).unwrap().frequency_mhz(STANDARD_A4_HZ), 427_474);
+    assert_eq!(a4.detune(100).unwrap().frequency_mhz(STANDARD_A4_HZ),
+               a4.transpose(1).unwrap().frequency_mhz(STANDARD_A4_HZ));
+    assert_eq!(a4.detune(-100).unwrap().frequency_mhz(STANDARD_A4_HZ),
+               a4.transpose(-1).unwrap().frequency_mhz(STANDARD_A4_HZ));
+}
@@ -0,0 +1,102 @@
+use rustorch_test::pitch_effect::*;
+
+#[test]
+fn test_arpeggio() {
+    let mut generator = PitchGenerator::new();
+    let effect = PitchEffect::NONE;
+    assert_eq!(generator.update(&effect, 0), None);
+    assert_eq!(generator.next_update_us(&effect, 0), None);
+
+    // 単音は高さが変化しないので更新不要
+    generator.start(vec![440], 0, &effect, 1_000);
+    assert_eq!(generator.update(&effect, 5_000), Some(440));
+    assert_eq!(generator.next_update_us(&effect, 5_000), None);
+
+    // 和音は構成音を順番に切り替え、次の切り替え時刻まで待つ
+    generator.start(vec![262, 330, 392], 30_000, &effect, 1_000);
+    assert_eq!(generator.update(&effect, 1_000), Some(262));
+    assert_eq!(generator.next_update_us(&effect, 1_000), Some(31_000));
+    assert_eq!(generator.update(&effect, 30_999), Some(262));
+    assert_eq!(generator.update(&effect, 31_000), Some(330));
+    assert_eq!(generator.next_update_us(&effect, 45_000), Some(61_000));
+    assert_eq!(generator.update(&effect, 61_000), Some(392));
+    assert_eq!(generator.update(&effect, 91_000), Some(262));
+
+    generator.stop();
+    assert!(!generator.is_active());
+    assert_eq!(generator.update(&effect, 100_000), None);
+}
+
+#[test]
+fn test_glide() {
+    let mut generator = PitchGenerator::new();
+    let effect = PitchEffect { glide_ms: 100, ..PitchEffect::NONE };
+
+    // 直前の音がなければポルタメントしない
+    generator.start(vec![400], 0, &effect, 0);
+    assert_eq!(generator.update(&effect, 0), Some(400));
+    assert_eq!(generator.next_update_us(&effect, 0), None);
+
+    generator.start(vec![800], 0, &effect, 1_000_000);
+    assert_eq!(generator.update(&effect, 1_000_000), Some(400));
+    assert_eq!(generator.next_update_us(&effect, 1_000_000), Some(1_000_000 + PITCH_STEP_US));
+    assert_eq!(generator.update(&effect, 1_050_000), Some(600));
+    assert_eq!(generator.update(&effect, 1_100_000), Some(800));
+    assert_eq!(generator.next_update_us(&effect, 1_100_000), None);
+
+    // 止めた後は直前の音がないものとして扱う
+    generator.stop();
+    generator.start(vec![400], 0, &effect, 2_000_000);
+    assert_eq!(generator.update(&effect, 2_000_000), Some(400));
+    assert_eq!(generator.next_update_us(&effect, 2_000_000), None);
+}
+
+#[test]
+fn test_sweep() {
+    let sweep = |mode| PitchEffect {
+        sweep: Some(Sweep { target_percent: 50, duration_ms: 100, mode }),
+        ..PitchEffect::NONE
+    };
+    let mut generator = PitchGenerator::new();
+
+    let once = sweep(SweepMode::Once);
+    generator.start(vec![1000], 0, &once, 0);
+    assert_eq!(generator.update(&once, 0), Some(1000));
+    assert_eq!(generator.update(&once, 50_000), Some(750));
+    assert_eq!(generator.update(&once, 100_000), Some(500));
+    assert_eq!(generator.update(&once, 300_000), Some(500));
+    assert_eq!(generator.next_update_us(&once, 0), Some(PITCH_STEP_US));
+
+    let repeat = sweep(SweepMode::Repeat);
+    generator.start(vec![1000], 0, &repeat, 0);
+    assert_eq!(generator.update(&repeat, 50_000), Some(750));
+    assert_eq!(generator.update(&repeat, 100_000), Some(1000));
+    assert_eq!(generator.update(&repeat, 150_000), Some(750));
+
+    let ping_pong = sweep(SweepMode::PingPong);
+    generator.start(vec![1000], 0, &ping_pong, 0);
+    assert_eq!(generator.update(&ping_pong, 50_000), Some(750));
+    assert_eq!(generator.update(&ping_pong, 100_000), Some(500));
+    assert_eq!(generator.update(&ping_pong, 150_000), Some(750));
+    assert_eq!(generator.update(&ping_pong, 200_000), Some(1000));
+}
+
+#[test]
+fn test_vibrato() {
+    let effect = PitchEffect {
+        vibrato: Some(Vibrato { depth_cents: 100, period_ms: 200 }),
+        ..PitchEffect::NONE
+    };
+    let mut generator = PitchGenerator::new();
+    generator.start(vec![1000], 0, &effect, 0);
+
+    // 中心から始まり、1/4 周期で +100 セント、3/4 周期で -100 セント (約 ±5.8%)
+    assert_eq!(generator.update(&effect, 0), Some(1000));
+    assert_eq!(generator.update(&effect, 50_000), Some(1057));
+    assert_eq!(generator.update(&effect, 100_000), Some(1000));
+    assert_eq!(generator.update(&effect, 150_000), Some(943));
+    for now in (0..400_000).step_by(1_000) {
+        let frequency = generator.update(&effect, now).unwrap();
+        assert!((943..=1057).contains(&frequency), "{} us: {} Hz", now, frequency);
+    }
+}
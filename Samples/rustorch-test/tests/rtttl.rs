@@ -0,0 +1,85 @@
+use rustorch_test::melodies::SAMPLE_TUNES;
+use rustorch_test::rtttl::{parse, RtttlError, RtttlErrorKind, RtttlMode};
+use rustorch_test::score::*;
+
+const QUARTER: u32 = TICKS_PER_QUARTER;
+
+fn note(midi: u8, ticks: u32) -> ScoreEvent {
+    ScoreEvent::Note { frequency: midi_note_to_frequency(midi), ticks }
+}
+
+fn error(column: usize, kind: RtttlErrorKind) -> RtttlError {
+    RtttlError { column, kind }
+}
+
+#[test]
+fn test_parse_strict() {
+    let ringtone = parse("test:d=4,o=5,b=100:c,8d#,2p,e6,a.,16b4.,1g", RtttlMode::Strict).unwrap();
+    assert_eq!(ringtone.name, "test");
+    assert_eq!(ringtone.score.tempo_bpm, 100);
+    assert_eq!(ringtone.score.events, vec![
+        note(72, QUARTER),
+        note(75, QUARTER / 2),
+        ScoreEvent::Rest { ticks: QUARTER * 2 },
+        note(88, QUARTER),
+        note(81, QUARTER * 3 / 2),
+        note(71, QUARTER / 4 * 3 / 2),
+        note(79, QUARTER * 4),
+    ]);
+    assert!(ringtone.score.validate().is_ok());
+}
+
+#[test]
+fn test_strict_errors() {
+    assert_eq!(parse("test:d=4,o=5,b=100", RtttlMode::Strict), Err(error(19, RtttlErrorKind::MissingSection)));
+    assert_eq!(parse("abcdefghijk:d=4,o=5,b=100:c", RtttlMode::Strict), Err(error(1, RtttlErrorKind::NameTooLong)));
+    assert_eq!(parse("test:d=4, o=5,b=100:c", RtttlMode::Strict), Err(error(10, RtttlErrorKind::UnexpectedCharacter(' '))));
+    assert_eq!(parse("Test:d=4,o=5,b=100:C", RtttlMode::Strict), Err(error(20, RtttlErrorKind::UnexpectedCharacter('C'))));
+    assert_eq!(parse("test:o=5,d=4,b=100:c", RtttlMode::Strict), Err(error(6, RtttlErrorKind::InvalidDefault)));
+    assert_eq!(parse("test:d=4,o=5:c", RtttlMode::Strict), Err(error(6, RtttlErrorKind::InvalidDefault)));
+    assert_eq!(parse("test:d=4,o=5,b=101:c", RtttlMode::Strict), Err(error(16, RtttlErrorKind::InvalidTempo(101))));
+    assert_eq!(parse("test:d=4,o=5,b=100:c,64d", RtttlMode::Strict), Err(error(22, RtttlErrorKind::InvalidDuration(64))));
+    assert_eq!(parse("test:d=4,o=5,b=100:c,d3", RtttlMode::Strict), Err(error(23, RtttlErrorKind::InvalidOctave(3))));
+    assert_eq!(parse("test:d=4,o=5,b=100:c,x", RtttlMode::Strict), Err(error(22, RtttlErrorKind::InvalidNote)));
+    assert_eq!(parse("test:d=4,o=5,b=100:c,,d", RtttlMode::Strict), Err(error(22, RtttlErrorKind::InvalidNote)));
+}
+
+#[test]
+fn test_parse_lenient() {
+    // 空白・大文字・順不同・未知のキー・省略されたデフォルト値・h・末尾のカンマ
+    let ringtone = parse(" My Song : O=5, x=1, D=8 :\n C, 4H, 64p, A3,\n", RtttlMode::Lenient).unwrap();
+    assert_eq!(ringtone.name, "My Song");
+    assert_eq!(ringtone.score.tempo_bpm, 63);
+    assert_eq!(ringtone.score.events, vec![
+        note(72, QUARTER / 2),
+        note(83, QUARTER),
+        ScoreEvent::Rest { ticks: QUARTER / 16 },
+        note(57, QUARTER / 2),
+    ]);
+
+    // 厳格モードでは受け付けない
+    assert!(parse(" My Song : O=5, x=1, D=8 :\n C, 4H, 64p, A3,\n", RtttlMode::Strict).is_err());
+    // 寛容モードでも範囲外の値はエラー
+    assert_eq!(parse("test:b=0:c", RtttlMode::Lenient), Err(error(8, RtttlErrorKind::InvalidTempo(0))));
+    assert_eq!(parse("test::c9", RtttlMode::Lenient), Err(error(8, RtttlErrorKind::InvalidOctave(9))));
+    assert_eq!(parse("test:b=1301:c", RtttlMode::Lenient), Err(error(8, RtttlErrorKind::InvalidTempo(1301))));
+}
+
+#[test]
+fn test_lenient_accepts_strict_tempos() {
+    // 仕様にない 1125 は寛容モードだけ、仕様の 1300 は両方で受け付ける
+    assert_eq!(parse("test:d=4,o=5,b=1125:c", RtttlMode::Strict), Err(error(16, RtttlErrorKind::InvalidTempo(1125))));
+    assert_eq!(parse("test:d=4,o=5,b=1125:c", RtttlMode::Lenient).unwrap().score.tempo_bpm, 1125);
+    for mode in [RtttlMode::Strict, RtttlMode::Lenient] {
+        assert_eq!(parse("test:d=4,o=5,b=1300:c", mode).unwrap().score.tempo_bpm, 1300);
+    }
+}
+
+#[test]
+fn test_sample_tunes() {
+    for source in SAMPLE_TUNES {
+        let ringtone = parse(source, RtttlMode::Strict).unwrap();
+        assert!(!ringtone.score.events.is_empty());
+        assert!(ringtone.score.validate().is_ok());
+    }
+}
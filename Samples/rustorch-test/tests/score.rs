@@ -0,0 +1,160 @@
+use rustorch_test::score::*;
+
+const QUARTER: u32 = TICKS_PER_QUARTER;
+
+#[test]
+fn test_ticks_to_us() {
+    // 120bpm の 4 分音符は 0.5 秒
+    assert_eq!(ticks_to_us(QUARTER, 120), 500_000);
+    assert_eq!(ticks_to_us(QUARTER / 2, 120), 250_000);
+    assert_eq!(ticks_to_us(QUARTER * 4, 60), 4_000_000);
+    // 1 tick が 1us 未満になる速さでも 0us にはしない
+    assert_eq!(ticks_to_us(1, 200_000), 1);
+    assert_eq!(ticks_to_us(0, 200_000), 0);
+}
+
+#[test]
+fn test_player_very_fast_tempo() {
+    // 1 tick が 0.6us に丸められるテンポでも音符を 1 つずつ進める
+    let score = Score::new(200_000).gate(100)
+        .note(440, 1)
+        .note(550, 1);
+    let mut player = ScorePlayer::new();
+    player.start(score, 0).unwrap();
+    player.update(0);
+    assert_eq!(player.output(), Some(440));
+    assert_eq!(player.next_deadline_us(), Some(1));
+    player.update(1);
+    assert_eq!(player.output(), Some(550));
+    assert_eq!(player.status().position, 1);
+}
+
+#[test]
+fn test_validate() {
+    assert_eq!(Score::new(120).note(440, QUARTER).validate(), Ok(()));
+    assert_eq!(Score::new(0).validate(), Err(ScoreError::InvalidTempo(0)));
+    assert_eq!(Score::new(120).tempo(0).validate(), Err(ScoreError::InvalidTempo(0)));
+    assert_eq!(Score::new(120).gate(0).validate(), Err(ScoreError::InvalidGate(0)));
+    assert_eq!(Score::new(120).gate(101).validate(), Err(ScoreError::InvalidGate(101)));
+    // 長さ 0 のループ区間は NG
+    let score = Score::new(120).tempo(100).note(440, QUARTER).with_loop(0, 1, None);
+    assert!(matches!(score.validate(), Err(ScoreError::InvalidLoop(_))));
+    let score = Score::new(120).note(440, QUARTER).with_loop(0, 2, None);
+    assert!(matches!(score.validate(), Err(ScoreError::InvalidLoop(_))));
+    let score = Score::new(120).note(440, QUARTER).with_loop(1, 1, None);
+    assert!(matches!(score.validate(), Err(ScoreError::InvalidLoop(_))));
+}
+
+#[test]
+fn test_player_timing() {
+    let score = Score::new(120).gate(50)
+        .note(440, QUARTER)
+        .rest(QUARTER)
+        .note(880, QUARTER / 2);
+    let mut player = ScorePlayer::new();
+    assert!(!player.is_playing());
+    assert_eq!(player.next_deadline_us(), None);
+
+    let t0 = 1_000_000;
+    player.start(score, t0).unwrap();
+    assert!(player.is_playing());
+    assert_eq!(player.output(), Some(440));
+    assert_eq!(player.next_deadline_us(), Some(t0 + 250_000));
+
+    // ゲート 50% なので 4 分音符の半分で消音
+    player.update(t0 + 249_999);
+    assert_eq!(player.output(), Some(440));
+    player.update(t0 + 250_000);
+    assert_eq!(player.output(), None);
+    assert_eq!(player.status().position, 0);
+    assert_eq!(player.next_deadline_us(), Some(t0 + 500_000));
+
+    // 休符
+    player.update(t0 + 500_000);
+    assert_eq!(player.output(), None);
+    assert_eq!(player.status().position, 1);
+
+    // 起床が遅れても次のイベントの開始時刻は楽譜通り
+    player.update(t0 + 1_000_100);
+    assert_eq!(player.output(), Some(880));
+    assert_eq!(player.status().position, 2);
+    assert_eq!(player.status().elapsed_us, 1_000_100);
+    assert_eq!(player.next_deadline_us(), Some(t0 + 1_125_000));
+
+    player.update(t0 + 1_250_000);
+    assert!(!player.is_playing());
+    assert_eq!(player.output(), None);
+    assert_eq!(player.status(), PlayerStatus::default());
+}
+
+#[test]
+fn test_player_skips_multiple_events_at_once() {
+    let score = Score::new(120).gate(100)
+        .note(440, QUARTER)
+        .note(550, QUARTER)
+        .note(660, QUARTER);
+    let mut player = ScorePlayer::new();
+    player.start(score, 0).unwrap();
+    player.update(1_200_000);
+    assert_eq!(player.output(), Some(660));
+    assert_eq!(player.status().position, 2);
+}
+
+#[test]
+fn test_player_tempo_change() {
+    let score = Score::new(120).gate(100)
+        .note(440, QUARTER)
+        .tempo(60)
+        .note(880, QUARTER);
+    let mut player = ScorePlayer::new();
+    player.start(score, 0).unwrap();
+    player.update(500_000);
+    assert_eq!(player.output(), Some(880));
+    // 60bpm の 4 分音符は 1 秒
+    assert_eq!(player.next_deadline_us(), Some(1_500_000));
+    player.update(1_500_000);
+    assert!(!player.is_playing());
+}
+
+#[test]
+fn test_player_loop() {
+    // イントロ + 2 音のループを追加で 2 回
+    let score = Score::new(120).gate(100)
+        .note(100, QUARTER)
+        .note(200, QUARTER)
+        .note(300, QUARTER)
+        .with_loop(1, 3, Some(2));
+    let mut player = ScorePlayer::new();
+    player.start(score, 0).unwrap();
+
+    let mut outputs = Vec::new();
+    while let Some(deadline) = player.next_deadline_us() {
+        outputs.push(player.output());
+        player.update(deadline);
+    }
+    assert_eq!(outputs, vec![ Some(100), Some(200), Some(300), Some(200), Some(300), Some(200), Some(300) ]);
+    assert_eq!(player.status().loop_iteration, 2);
+}
+
+#[test]
+fn test_player_infinite_loop_and_cancel() {
+    let score = Score::new(120).gate(100)
+        .note(100, QUARTER)
+        .with_loop(0, 1, None);
+    let mut player = ScorePlayer::new();
+    player.start(score, 0).unwrap();
+    player.update(500_000 * 100 + 1);
+    assert!(player.is_playing());
+    assert_eq!(player.status().loop_iteration, 100);
+
+    player.cancel();
+    assert!(!player.is_playing());
+    assert_eq!(player.output(), None);
+}
+
+#[test]
+fn test_player_rejects_invalid_score() {
+    let mut player = ScorePlayer::new();
+    assert_eq!(player.start(Score::new(0), 0), Err(ScoreError::InvalidTempo(0)));
+    assert!(!player.is_playing());
+}
@@ -0,0 +1,74 @@
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+use rustorch_test::display_command::*;
+use rustorch_test::frame_buffer::*;
+use rustorch_test::screenshot::*;
+
+fn sample_frame() -> FrameBuffer {
+    let mut frame = FrameBuffer::new();
+    render(&DisplayCommand::DrawText { text: "Hello".to_string(), point: Point::new(3, 5) }, &mut frame).unwrap();
+    render(&DisplayCommand::DrawRectangle { area: Rectangle::new(Point::new(100, 40), Size::new(20, 20)), style: fill() }, &mut frame).unwrap();
+    frame
+}
+
+#[test]
+fn test_base64() {
+    assert_eq!(base64_encode(b""), "");
+    assert_eq!(base64_encode(b"f"), "Zg==");
+    assert_eq!(base64_encode(b"fo"), "Zm8=");
+    assert_eq!(base64_encode(b"foo"), "Zm9v");
+    assert_eq!(base64_encode(b"foobar"), "Zm9vYmFy");
+    assert_eq!(base64_encode(&[0xFB, 0xFF]), "+/8=");
+
+    assert_eq!(base64_decode("Zg==").unwrap(), b"f");
+    assert_eq!(base64_decode("Zm8=").unwrap(), b"fo");
+    assert_eq!(base64_decode("Zm9vYmFy").unwrap(), b"foobar");
+    assert_eq!(base64_decode("+/8=").unwrap(), [0xFB, 0xFF]);
+    assert_eq!(base64_decode("Zm9v!"), Err(ScreenshotError::InvalidBase64));
+    // 1 文字余るのは不正
+    assert_eq!(base64_decode("Zm9vY"), Err(ScreenshotError::InvalidBase64));
+}
+
+#[test]
+fn test_pbm() {
+    let mut frame = FrameBuffer::new();
+    frame.set_pixel(Point::new(0, 0), BinaryColor::On);
+    let pbm = to_pbm(&frame);
+    let header = b"P4\n128 64\n";
+    assert_eq!(&pbm[..header.len()], header);
+    assert_eq!(pbm.len(), header.len() + 16 * 64);
+    // 点灯は白 (0)、消灯は黒 (1)
+    assert_eq!(pbm[header.len()], 0x7F);
+    assert!(pbm[header.len() + 1..].iter().all(|byte| *byte == 0xFF));
+
+    let frame = sample_frame();
+    assert!(from_pbm(&to_pbm(&frame)).unwrap() == frame);
+
+    // コメント付きのヘッダも読める
+    let mut commented = b"P4\n# created by hand\n128 64\n".to_vec();
+    commented.extend_from_slice(&pbm[header.len()..]);
+    assert_eq!(from_pbm(&commented).unwrap().count_on(), 1);
+
+    assert_eq!(from_pbm(b"P1\n128 64\n").err(), Some(ScreenshotError::InvalidPbm));
+    assert_eq!(from_pbm(b"P4\n64 32\n").err(), Some(ScreenshotError::UnexpectedSize(64, 32)));
+    assert_eq!(from_pbm(b"P4\n128 64\n\x00").err(), Some(ScreenshotError::InvalidPbm));
+}
+
+#[test]
+fn test_line_round_trip() {
+    let frame = sample_frame();
+    let line = encode_line(42, &frame);
+    assert!(line.starts_with("@OLED 42 "));
+    assert!(!line.contains('\n'));
+
+    let (sequence, decoded) = decode_line(&line).unwrap().unwrap();
+    assert_eq!(sequence, 42);
+    assert!(decoded == frame);
+
+    // ログの前置きや末尾の改行コードがあってもよい
+    let (_, decoded) = decode_line(&format!("I (1234) rustorch: {}\r", line)).unwrap().unwrap();
+    assert!(decoded == frame);
+
+    assert!(decode_line("I (1234) rustorch: Hello, world!").is_none());
+    assert_eq!(decode_line("@OLED x AAAA").unwrap().err(), Some(ScreenshotError::InvalidSequence));
+    assert_eq!(decode_line("@OLED 1 UDQK").unwrap().err(), Some(ScreenshotError::InvalidPbm));
+}
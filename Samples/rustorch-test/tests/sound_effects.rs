@@ -0,0 +1,32 @@
+use rustorch_test::score::ScoreEvent;
+use rustorch_test::sound_effects::SoundEffect;
+
+#[test]
+fn test_all_effects_are_playable() {
+    for effect in SoundEffect::ALL {
+        let score = effect.score();
+        assert!(score.validate().is_ok(), "{}", effect.name());
+        assert!(score.loop_points.is_none(), "{}", effect.name());
+        // ブザーで出力できる周波数 (77Hz ~ 78125Hz) の範囲に収まること
+        for event in score.events.iter() {
+            if let ScoreEvent::Note { frequency, .. } = event {
+                assert!((77..=78_125).contains(frequency), "{}: {} Hz", effect.name(), frequency);
+            }
+        }
+        // 効果音は 2 秒以内
+        let length_us = score.total_ticks() as i64 * 60_000_000 / (score.tempo_bpm as i64 * 480);
+        assert!(length_us <= 2_000_000, "{}: {} us", effect.name(), length_us);
+    }
+}
+
+#[test]
+fn test_from_name() {
+    for effect in SoundEffect::ALL {
+        assert_eq!(SoundEffect::from_name(effect.name()), Some(effect));
+    }
+    assert_eq!(SoundEffect::from_name("timer_done"), Some(SoundEffect::TimerDone));
+    assert_eq!(SoundEffect::from_name("unknown"), None);
+
+    assert!(SoundEffect::Click.is_ui_feedback());
+    assert!(!SoundEffect::Jackpot.is_ui_feedback());
+}
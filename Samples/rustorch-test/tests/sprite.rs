@@ -0,0 +1,213 @@
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use rustorch_test::display_command::*;
+use rustorch_test::frame_buffer::*;
+use rustorch_test::sprite::*;
+
+const SPINNER: &[u8] = include_bytes!("../../rustorch/asserts/images/pomodoro_spinner.bmp");
+
+// 1 ビット BMP (下の行から、行は 4 バイト境界に揃える)
+fn bmp(rows: &[&str]) -> Vec<u8> {
+    let width = rows[0].len() as u32;
+    let height = rows.len() as u32;
+    let stride = width.div_ceil(32) * 4;
+    let mut pixels = Vec::new();
+    for row in rows.iter().rev() {
+        let mut bytes = vec![0u8; stride as usize];
+        for (x, ch) in row.chars().enumerate() {
+            if ch == '#' {
+                bytes[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        pixels.extend(bytes);
+    }
+    let offset = 14 + 40 + 8;
+    let mut data = b"BM".to_vec();
+    data.extend((offset + pixels.len() as u32).to_le_bytes());
+    data.extend([0; 4]);
+    data.extend(offset.to_le_bytes());
+    data.extend(40u32.to_le_bytes());
+    data.extend(width.to_le_bytes());
+    data.extend(height.to_le_bytes());
+    data.extend(1u16.to_le_bytes());
+    data.extend(1u16.to_le_bytes());
+    data.extend([0; 4]);
+    data.extend((pixels.len() as u32).to_le_bytes());
+    data.extend([0; 8]);
+    data.extend(2u32.to_le_bytes());
+    data.extend(2u32.to_le_bytes());
+    data.extend([0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0x00]);
+    data.extend(pixels);
+    data
+}
+
+// 2x2 のフレームが 3 つ並んだシート
+fn sheet_rows() -> Vec<&'static str> {
+    vec![
+        "#.##..",
+        "..#.##",
+    ]
+}
+
+fn pixels(frame: &FrameBuffer, top_left: Point, size: Size) -> Vec<String> {
+    (0..size.height as i32).map(|y| {
+        (0..size.width as i32).map(|x| {
+            if frame.get_pixel(top_left + Point::new(x, y)).is_on() { '#' } else { '.' }
+        }).collect()
+    }).collect()
+}
+
+#[test]
+fn test_sprite_sheet() {
+    let sheet = SpriteSheet::new(&bmp(&sheet_rows()), Size::new(2, 2)).unwrap();
+    assert_eq!(sheet.columns(), 3);
+    assert_eq!(sheet.frame_count(), 3);
+
+    let mut frame = FrameBuffer::new();
+    sheet.draw(&mut frame, 0, Point::new(10, 10), Flip::None).unwrap();
+    sheet.draw(&mut frame, 1, Point::new(12, 10), Flip::None).unwrap();
+    assert_eq!(pixels(&frame, Point::new(10, 10), Size::new(4, 2)), vec!["#.##", "..#."]);
+    assert_eq!(sheet.draw(&mut frame, 3, Point::zero(), Flip::None), Err(SpriteError::FrameOutOfRange(3)));
+
+    // 画面外にはみ出してもよい
+    sheet.draw(&mut frame, 1, Point::new(127, 63), Flip::None).unwrap();
+    assert_eq!(frame.get_pixel(Point::new(127, 63)), BinaryColor::On);
+
+    assert_eq!(SpriteSheet::new(&bmp(&sheet_rows()), Size::new(3, 3)).err(), Some(SpriteError::InvalidFrameSize));
+    assert_eq!(SpriteSheet::new(&bmp(&sheet_rows()), Size::new(0, 2)).err(), Some(SpriteError::InvalidFrameSize));
+    assert_eq!(SpriteSheet::new(b"not a bmp", Size::new(2, 2)).err(), Some(SpriteError::InvalidImage));
+
+    let spinner = SpriteSheet::new(SPINNER, Size::new(12, 12)).unwrap();
+    assert_eq!(spinner.frame_count(), 8);
+}
+
+#[test]
+fn test_flip() {
+    let sheet = SpriteSheet::new(&bmp(&["##.", "#.."]), Size::new(3, 2)).unwrap();
+    let draw = |flip| {
+        let mut frame = FrameBuffer::new();
+        sheet.draw(&mut frame, 0, Point::zero(), flip).unwrap();
+        pixels(&frame, Point::zero(), Size::new(3, 2))
+    };
+    assert_eq!(draw(Flip::None), vec!["##.", "#.."]);
+    assert_eq!(draw(Flip::Horizontal), vec![".##", "..#"]);
+    assert_eq!(draw(Flip::Vertical), vec!["#..", "##."]);
+    assert_eq!(draw(Flip::Both), vec!["..#", ".##"]);
+}
+
+#[test]
+fn test_transparency() {
+    let mut background = FrameBuffer::new();
+    background.fill_region(&embedded_graphics::primitives::Rectangle::new(Point::zero(), Size::new(3, 1)), BinaryColor::On);
+
+    // 既定では消灯も描く
+    let sheet = SpriteSheet::new(&bmp(&[".#."]), Size::new(3, 1)).unwrap();
+    let mut frame = background.clone();
+    sheet.draw(&mut frame, 0, Point::zero(), Flip::None).unwrap();
+    assert_eq!(pixels(&frame, Point::zero(), Size::new(3, 1)), vec![".#."]);
+
+    // 透過色
+    let mut frame = FrameBuffer::new();
+    sheet.clone().transparent(BinaryColor::On).draw(&mut frame, 0, Point::zero(), Flip::None).unwrap();
+    assert_eq!(frame.count_on(), 0);
+    let mut frame = background.clone();
+    sheet.clone().transparent(BinaryColor::Off).draw(&mut frame, 0, Point::zero(), Flip::None).unwrap();
+    assert_eq!(pixels(&frame, Point::zero(), Size::new(3, 1)), vec!["###"]);
+
+    // マスクの消灯は描かない
+    let masked = sheet.clone().mask(&bmp(&["##."])).unwrap();
+    let mut frame = background.clone();
+    masked.draw(&mut frame, 0, Point::zero(), Flip::Horizontal).unwrap();
+    // マスクも一緒に反転するので、描くのは右 2 ピクセル
+    assert_eq!(pixels(&frame, Point::zero(), Size::new(3, 1)), vec!["##."]);
+
+    assert_eq!(sheet.mask(&bmp(&["##"])).err(), Some(SpriteError::MaskSizeMismatch));
+}
+
+#[test]
+fn test_animator() {
+    let sheet = SpriteSheet::new(&bmp(&sheet_rows()), Size::new(2, 2)).unwrap();
+    let animation = Animation::new(sheet.clone()).frame(0, 100).frame(2, 50).frame(1, 100);
+    assert_eq!(animation.duration_ms(), 250);
+
+    let mut animator = Animator::new();
+    assert_eq!(animator.next_deadline_ms(), None);
+    animator.start(1, Sprite::new(animation, Point::new(4, 4)), 1000).unwrap();
+    assert_eq!(animator.current_frame(1), Some(0));
+    assert_eq!(animator.next_deadline_ms(), Some(1100));
+
+    assert!(!animator.advance(1099));
+    assert!(animator.advance(1100));
+    assert_eq!(animator.current_frame(1), Some(2));
+    assert_eq!(animator.next_deadline_ms(), Some(1150));
+
+    // 遅れた場合は飛ばして、時刻はずらさない
+    assert!(animator.advance(1260));
+    assert_eq!(animator.current_frame(1), Some(0));
+    assert_eq!(animator.next_deadline_ms(), Some(1350));
+
+    // 繰り返さない場合は最後のフレームで止まる
+    let once = Animation::new(sheet.clone()).frames(0..3, 10).looping(false);
+    animator.start(2, Sprite::new(once, Point::zero()), 0).unwrap();
+    animator.advance(1000);
+    assert_eq!(animator.current_frame(2), Some(2));
+    animator.advance(10_000);
+    assert_eq!(animator.current_frame(2), Some(2));
+
+    assert!(animator.stop(1));
+    assert!(!animator.stop(1));
+    assert_eq!(animator.next_deadline_ms(), None);
+    assert!(!animator.is_empty());
+    animator.stop_all();
+    assert!(animator.is_empty());
+
+    assert_eq!(animator.start(3, Sprite::new(Animation::new(sheet.clone()), Point::zero()), 0), Err(SpriteError::EmptyAnimation));
+    let invalid = Animation::new(sheet).frame(0, 10).frame(5, 10);
+    assert_eq!(animator.start(3, Sprite::new(invalid, Point::zero()), 0), Err(SpriteError::FrameOutOfRange(5)));
+    assert!(!animator.contains(3));
+}
+
+#[test]
+fn test_compose() {
+    let sheet = SpriteSheet::new(&bmp(&sheet_rows()), Size::new(2, 2)).unwrap().transparent(BinaryColor::Off);
+    let mut animator = Animator::new();
+    animator.start(0, Sprite::new(Animation::new(sheet).frames(0..3, 100), Point::new(0, 0)), 0).unwrap();
+
+    let mut base = FrameBuffer::new();
+    base.set_pixel(Point::new(1, 1), BinaryColor::On);
+    let composed = animator.compose(&base);
+    assert_eq!(pixels(&composed, Point::zero(), Size::new(2, 2)), vec!["#.", ".#"]);
+    // 描いた画面には残らない
+    assert_eq!(base.count_on(), 1);
+
+    assert!(animator.move_to(0, Point::new(10, 0), Flip::None));
+    assert!(!animator.move_to(9, Point::zero(), Flip::None));
+    let composed = animator.compose(&base);
+    assert_eq!(composed.get_pixel(Point::new(0, 0)), BinaryColor::Off);
+    assert_eq!(composed.get_pixel(Point::new(10, 0)), BinaryColor::On);
+}
+
+#[test]
+fn test_display_commands() {
+    let sheet = SpriteSheet::new(&bmp(&sheet_rows()), Size::new(2, 2)).unwrap();
+    let mut frame = DisplayFrame::new();
+    frame.clear()
+        .draw_sprite(sheet.clone(), 1, Point::new(0, 0), Flip::None)
+        .start_animation(5, Sprite::new(Animation::new(sheet.clone()).frames(0..3, 100), Point::new(20, 0)));
+
+    // アニメーションは描いた画面には入らない
+    let mut buffer = FrameBuffer::new();
+    render_frame(&frame, &mut buffer).unwrap();
+    assert_eq!(pixels(&buffer, Point::zero(), Size::new(2, 2)), vec!["##", "#."]);
+    assert_eq!(buffer.count_on(), 3);
+
+    let mut animator = Animator::new();
+    animate(&DisplayCommand::Frame(frame), &mut animator, 0).unwrap();
+    assert!(animator.contains(5));
+    animate(&DisplayCommand::MoveSprite { id: 5, point: Point::new(30, 0), flip: Flip::Both }, &mut animator, 0).unwrap();
+    assert_eq!(animator.compose(&FrameBuffer::new()).get_pixel(Point::new(31, 1)), BinaryColor::On);
+    animate(&DisplayCommand::StopAnimation { id: 5 }, &mut animator, 0).unwrap();
+    assert!(animator.is_empty());
+
+    let command = DisplayCommand::DrawSprite { sheet, index: 7, point: Point::zero(), flip: Flip::None };
+    assert_eq!(render(&command, &mut buffer).err(), Some(RenderError::Sprite(SpriteError::FrameOutOfRange(7))));
+}
@@ -0,0 +1,135 @@
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+use rustorch_test::display_command::*;
+use rustorch_test::frame_buffer::*;
+use rustorch_test::text::*;
+
+fn draw_box(text: &str, area: Rectangle, style: TextStyle) -> FrameBuffer {
+    let mut frame = FrameBuffer::new();
+    render(&DisplayCommand::DrawTextBox { text: text.to_string(), area, style }, &mut frame).unwrap();
+    frame
+}
+
+// 点灯しているピクセルを囲む矩形
+fn bounding_box(frame: &FrameBuffer) -> Option<Rectangle> {
+    let points: Vec<Point> = Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT))
+        .points()
+        .filter(|point| frame.get_pixel(*point).is_on())
+        .collect();
+    let first = *points.first()?;
+    let (min, max) = points.iter().fold((first, first), |(min, max), p| {
+        (min.component_min(*p), max.component_max(*p))
+    });
+    Some(Rectangle::with_corners(min, max))
+}
+
+#[test]
+fn test_font_metrics() {
+    assert_eq!(Font::Normal.character_size(), Size::new(6, 10));
+    assert_eq!(Font::Normal.advance(), 6);
+    assert_eq!(Font::Small.line_height(), 6);
+    assert_eq!(Font::Large.character_size(), Size::new(10, 20));
+    assert_eq!(Font::Normal.columns_in(128), 21);
+    assert_eq!(Font::Normal.text_width(21), 126);
+
+    // 文字間隔は末尾に含めない
+    assert_eq!(Font::Digits.advance(), 22);
+    assert_eq!(Font::Digits.text_width(5), 106);
+    assert_eq!(Font::Digits.columns_in(106), 5);
+    assert_eq!(Font::Digits.columns_in(105), 4);
+    assert_eq!(Font::Digits.text_width(0), 0);
+}
+
+#[test]
+fn test_layout_lines_without_wrap() {
+    assert_eq!(layout_lines("hello", 10, 1, false), vec!["hello"]);
+    assert_eq!(layout_lines("hello world", 8, 1, false), vec!["hello..."]);
+    assert_eq!(layout_lines("a\nb\nc", 10, 2, false), vec!["a", "b..."]);
+    // 枠が狭すぎる
+    assert_eq!(layout_lines("hello", 2, 1, false), vec![".."]);
+    assert!(layout_lines("hello", 0, 1, false).is_empty());
+    assert!(layout_lines("hello", 10, 0, false).is_empty());
+}
+
+#[test]
+fn test_layout_lines_with_wrap() {
+    assert_eq!(layout_lines("the quick brown fox", 10, 4, true), vec!["the quick", "brown fox"]);
+    // 1 行に収まらない単語は途中で切る
+    assert_eq!(layout_lines("abcdefghijkl xy", 5, 4, true), vec!["abcde", "fghij", "kl xy"]);
+    // 空行は残し、連続する空白はまとめる
+    assert_eq!(layout_lines("a   b\n\nc", 10, 4, true), vec!["a b", "", "c"]);
+    // 行数を超えたら最後の行を省略する
+    assert_eq!(layout_lines("one two three four", 9, 2, true), vec!["one two", "three..."]);
+    assert_eq!(layout_lines("one two three four", 7, 2, true), vec!["one two", "thre..."]);
+}
+
+#[test]
+fn test_layout_alignment() {
+    let area = Rectangle::new(Point::new(4, 8), Size::new(60, 30));
+    let style = TextStyle::new();
+    assert_eq!(layout("abc", &area, &style), vec![(Point::new(4, 8), "abc".to_string())]);
+
+    let style = style.align(HorizontalAlign::Center, VerticalAlign::Middle);
+    assert_eq!(layout("abc", &area, &style), vec![(Point::new(4 + 21, 8 + 10), "abc".to_string())]);
+
+    let style = style.align(HorizontalAlign::Right, VerticalAlign::Bottom);
+    assert_eq!(layout("abc\nde", &area, &style), vec![
+        (Point::new(4 + 42, 8 + 10), "abc".to_string()),
+        (Point::new(4 + 48, 8 + 20), "de".to_string()),
+    ]);
+}
+
+#[test]
+fn test_draw_text_box() {
+    let area = Rectangle::new(Point::new(0, 0), Size::new(128, 64));
+    let style = TextStyle::new().align(HorizontalAlign::Center, VerticalAlign::Middle);
+    let bounds = bounding_box(&draw_box("X", area, style)).unwrap();
+    assert!(bounds.top_left.x >= 61 && bounds.top_left.x + bounds.size.width as i32 <= 67);
+    assert!(bounds.top_left.y >= 27 && bounds.top_left.y + bounds.size.height as i32 <= 37);
+
+    // はみ出した分は描かれない
+    let area = Rectangle::new(Point::new(0, 0), Size::new(30, 10));
+    let long = "a very long line that does not fit";
+    let bounds = bounding_box(&draw_box(long, area, TextStyle::new().font(Font::Small).wrap(true))).unwrap();
+    assert!(bounds.top_left.x + bounds.size.width as i32 <= 30);
+    assert!(bounds.top_left.y + bounds.size.height as i32 <= 10);
+}
+
+#[test]
+fn test_inverted_and_color() {
+    let area = Rectangle::new(Point::new(0, 0), Size::new(40, 10));
+    let normal = draw_box("Hi", area, TextStyle::new());
+    let inverted = draw_box("Hi", area, TextStyle::new().inverted(true));
+    // 枠内だけが反転する
+    assert_eq!(inverted.count_on(), 400 - normal.count_on());
+    assert!(inverted.get_pixel(Point::new(39, 9)).is_on());
+    assert!(!inverted.get_pixel(Point::new(40, 0)).is_on());
+
+    // 消灯色の文字は塗りつぶしの上から抜ける
+    let mut frame = FrameBuffer::new();
+    render(&DisplayCommand::DrawRectangle { area, style: fill() }, &mut frame).unwrap();
+    let style = TextStyle::new().color(BinaryColor::Off);
+    render(&DisplayCommand::DrawTextBox { text: "Hi".to_string(), area, style }, &mut frame).unwrap();
+    assert!(frame == inverted);
+}
+
+#[test]
+fn test_digits_font() {
+    let area = Rectangle::new(Point::new(0, 0), Size::new(128, 64));
+    let style = TextStyle::new().font(Font::Digits);
+    let eight = draw_box("8", area, style);
+    let one = draw_box("1", area, style);
+    // 8 は全セグメント、1 は b と c のみ
+    assert_eq!(bounding_box(&eight), Some(Rectangle::new(Point::zero(), Size::new(18, 31))));
+    assert!(one.count_on() < eight.count_on());
+    assert!(!one.get_pixel(Point::new(1, 10)).is_on());
+    assert!(one.get_pixel(Point::new(16, 10)).is_on());
+
+    // 2 文字目は文字送り分ずれる
+    let two = draw_box("88", area, style);
+    assert_eq!(two.count_on(), eight.count_on() * 2);
+    assert_eq!(bounding_box(&two), Some(Rectangle::new(Point::zero(), Size::new(40, 31))));
+
+    // 対応しない文字は空白
+    assert_eq!(draw_box("x", area, style).count_on(), 0);
+    assert!(draw_box(":", area, style).count_on() > 0);
+}
@@ -0,0 +1,36 @@
+use rustorch_test::tone::*;
+
+#[test]
+fn test_envelope() {
+    let envelope = Envelope::new(10, 20, 40);
+    assert_eq!(envelope.level_permille(0), 0);
+    assert_eq!(envelope.level_permille(5_000), 500);
+    assert_eq!(envelope.level_permille(10_000), 1000);
+    assert_eq!(envelope.level_permille(20_000), 700);
+    assert_eq!(envelope.level_permille(30_000), 400);
+    assert_eq!(envelope.level_permille(1_000_000), 400);
+    assert_eq!(envelope.settle_us(), 30_000);
+
+    assert_eq!(Envelope::FLAT.level_permille(0), 1000);
+    assert_eq!(Envelope::FLAT.settle_us(), 0);
+    assert_eq!(Envelope::new(0, 0, 150).sustain_percent, 100);
+}
+
+#[test]
+fn test_output_duty() {
+    let master = MasterVolume::new();
+    assert_eq!(output_duty_permille(&Instrument::new(), &master, 0), 500);
+    assert_eq!(output_duty_permille(&Instrument::new().timbre(Timbre::Pulse25), &master, 0), 250);
+    assert_eq!(output_duty_permille(&Instrument::new().volume(50), &master, 0), 250);
+    assert_eq!(Instrument::new().volume(200).volume_percent, 100);
+
+    // エンベロープ・音量・全体の音量は掛け合わせる
+    let instrument = Instrument::new().volume(50).envelope(Envelope::new(10, 0, 100));
+    let master = MasterVolume { volume_percent: 50, muted: false };
+    assert_eq!(output_duty_permille(&instrument, &master, 5_000), 62);
+    assert_eq!(output_duty_permille(&instrument, &master, 10_000), 125);
+
+    // ミュート中は常に 0
+    let master = MasterVolume { volume_percent: 100, muted: true };
+    assert_eq!(output_duty_permille(&Instrument::new(), &master, 0), 0);
+}
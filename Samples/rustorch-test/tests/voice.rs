@@ -0,0 +1,91 @@
+use rustorch_test::score::{Score, ScoreEvent, TICKS_PER_QUARTER};
+use rustorch_test::voice::*;
+
+// 120bpm の 4 分音符 (500ms) を並べた楽譜 (ゲート 100%)
+fn quarters(frequencies: &[u32]) -> Score {
+    let mut score = Score::new(120);
+    score.gate_percent = 100;
+    for frequency in frequencies {
+        score.events.push(ScoreEvent::Note { frequency: *frequency, ticks: TICKS_PER_QUARTER });
+    }
+    score
+}
+
+#[test]
+fn test_interrupt_and_resume() {
+    let mut mixer = Mixer::new();
+    mixer.play(VoicePriority::Music, quarters(&[100, 200]), None, 0).unwrap();
+    let output = mixer.update(0);
+    assert_eq!(output, MixerOutput { frequency: Some(100), note_started: true });
+    assert_eq!(mixer.update(100_000), MixerOutput { frequency: Some(100), note_started: false });
+
+    // 操作音が音楽を中断する
+    mixer.play(VoicePriority::Ui, quarters(&[1000]), None, 200_000).unwrap();
+    assert_eq!(mixer.update(200_000), MixerOutput { frequency: Some(1000), note_started: true });
+    assert_eq!(mixer.current(), Some(VoicePriority::Ui));
+    assert_eq!(mixer.next_deadline_us(200_000), Some(700_000));
+    assert_eq!(mixer.status(VoicePriority::Music, 600_000).position, 0);
+
+    // 操作音が鳴り終わると中断した位置 (1 音目の残り 300ms) から再開する
+    assert_eq!(mixer.update(700_000), MixerOutput { frequency: Some(100), note_started: true });
+    assert_eq!(mixer.current(), Some(VoicePriority::Music));
+    assert_eq!(mixer.next_deadline_us(700_000), Some(1_000_000));
+    assert_eq!(mixer.update(999_999).frequency, Some(100));
+    assert_eq!(mixer.update(1_000_000), MixerOutput { frequency: Some(200), note_started: true });
+    assert_eq!(mixer.update(1_500_000), MixerOutput { frequency: None, note_started: true });
+    assert_eq!(mixer.current(), None);
+    assert_eq!(mixer.next_deadline_us(1_500_000), None);
+}
+
+#[test]
+fn test_priority() {
+    let mut mixer = Mixer::new();
+    mixer.start_tone(VoicePriority::Ui, vec![440], 0);
+    assert_eq!(mixer.update(0).frequency, Some(440));
+
+    // 低い優先度の楽譜は高い優先度のボイスが鳴り終わるまで待ち、最初から演奏する
+    mixer.play(VoicePriority::Music, quarters(&[100, 200]), None, 0).unwrap();
+    assert_eq!(mixer.update(800_000).frequency, Some(440));
+    assert!(mixer.is_active(VoicePriority::Music));
+
+    // 通知は操作音より優先される
+    mixer.play(VoicePriority::Alert, quarters(&[2000]), None, 1_000_000).unwrap();
+    assert_eq!(mixer.update(1_000_000).frequency, Some(2000));
+    assert_eq!(mixer.update(1_500_000).frequency, Some(440));
+
+    mixer.stop(VoicePriority::Ui);
+    assert_eq!(mixer.update(2_000_000), MixerOutput { frequency: Some(100), note_started: true });
+    assert_eq!(mixer.update(2_500_000).frequency, Some(200));
+
+    // 同じボイスへの発音は置き換え
+    mixer.start_tone(VoicePriority::Music, vec![300, 400], 10_000);
+    assert!(mixer.update(2_600_000).note_started);
+    assert_eq!(mixer.update(2_610_000).frequency, Some(400));
+
+    mixer.stop_all();
+    assert_eq!(mixer.update(2_700_000).frequency, None);
+    for voice in VoicePriority::ALL {
+        assert!(!mixer.is_active(voice));
+    }
+}
+
+#[test]
+fn test_voice_instrument() {
+    use rustorch_test::tone::Instrument;
+
+    let mut mixer = Mixer::new();
+    mixer.set_instrument(Instrument::pluck());
+    mixer.start_tone(VoicePriority::Music, vec![440], 0);
+    mixer.update(0);
+    assert_eq!(mixer.instrument(), Instrument::pluck());
+
+    mixer.play(VoicePriority::Alert, quarters(&[880]), Some(Instrument::beep()), 0).unwrap();
+    mixer.update(0);
+    assert_eq!(mixer.instrument(), Instrument::beep());
+    mixer.update(500_000);
+    assert_eq!(mixer.instrument(), Instrument::pluck());
+
+    assert!(VoicePriority::Alert > VoicePriority::Ui && VoicePriority::Ui > VoicePriority::Music);
+    assert!(VoicePriority::Alert.waits_on_overflow());
+    assert!(!VoicePriority::Music.waits_on_overflow());
+}
@@ -0,0 +1,235 @@
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*, primitives::Rectangle};
+use rustorch_test::button::Button;
+use rustorch_test::display_command::*;
+use rustorch_test::frame_buffer::*;
+use rustorch_test::widget::layout::{self, Direction, Length};
+use rustorch_test::widget::*;
+
+fn render_screen(screen: &Screen) -> FrameBuffer {
+    let mut frame = FrameBuffer::new();
+    render_frame(&screen.draw(), &mut frame).unwrap();
+    frame
+}
+
+#[test]
+fn test_layout() {
+    let areas = layout::stack(layout::SCREEN, Direction::Vertical, &[Length::Fixed(10), Length::Fill, Length::Fixed(12)]);
+    assert_eq!(areas, vec![
+        Rectangle::new(Point::new(0, 0), Size::new(128, 10)),
+        Rectangle::new(Point::new(0, 10), Size::new(128, 42)),
+        Rectangle::new(Point::new(0, 52), Size::new(128, 12)),
+    ]);
+
+    // Fill の端数は先頭から配る
+    let columns = layout::stack(layout::SCREEN, Direction::Horizontal, &[Length::Fill, Length::Fill, Length::Fill]);
+    assert_eq!(columns.iter().map(|area| area.size.width).collect::<Vec<_>>(), vec![43, 43, 42]);
+    assert_eq!(columns[2].top_left, Point::new(86, 0));
+
+    // 収まらない固定長は詰める
+    let areas = layout::stack(layout::SCREEN, Direction::Vertical, &[Length::Fixed(60), Length::Fixed(10)]);
+    assert_eq!(areas[1].size.height, 4);
+
+    assert_eq!(layout::rows(layout::SCREEN).len(), 6);
+    assert_eq!(layout::inset(layout::SCREEN, 2), Rectangle::new(Point::new(2, 2), Size::new(124, 60)));
+}
+
+#[test]
+fn test_list_menu() {
+    let mut menu = ListMenu::new(&["a", "b", "c"]);
+    assert_eq!(menu.handle(Button::DOWN), WidgetEvent::Changed);
+    assert_eq!(menu.selected(), 1);
+    assert_eq!(menu.handle(Button::A), WidgetEvent::Activated);
+    assert_eq!(menu.handle(Button::B), WidgetEvent::Cancelled);
+    assert_eq!(menu.handle(Button::LEFT), WidgetEvent::Ignored);
+    // 端で折り返す
+    menu.handle(Button::DOWN);
+    menu.handle(Button::DOWN);
+    assert_eq!(menu.selected(), 0);
+    menu.handle(Button::UP);
+    assert_eq!(menu.selected(), 2);
+
+    // 折り返さない場合は端から先の入力を使わない
+    let mut menu = ListMenu::new(&["a", "b"]).wrap_around(false);
+    assert_eq!(menu.handle(Button::UP), WidgetEvent::Ignored);
+    assert_eq!(menu.selected(), 0);
+}
+
+#[test]
+fn test_list_menu_scroll() {
+    let items: Vec<String> = (0..10).map(|i| format!("Item {}", i)).collect();
+    let mut menu = ListMenu::new(&items);
+    let area = Rectangle::new(Point::zero(), Size::new(128, 30));
+    assert_eq!(menu.visible_range(area), 0..3);
+    menu.select(4);
+    assert_eq!(menu.visible_range(area), 2..5);
+    // 戻るときは選択中の項目が先頭になるまでスクロールしない
+    menu.select(3);
+    assert_eq!(menu.visible_range(area), 2..5);
+    menu.select(0);
+    assert_eq!(menu.visible_range(area), 0..3);
+    menu.select(100);
+    assert_eq!(menu.selected(), 9);
+    assert_eq!(menu.visible_range(area), 7..10);
+
+    // スクロールバーは右端に描く
+    let mut screen = Screen::new();
+    screen.add(area, ListMenu::new(&items));
+    let frame = render_screen(&screen);
+    assert_eq!(frame.get_pixel(Point::new(127, 0)), BinaryColor::On);
+    assert_eq!(frame.get_pixel(Point::new(127, 29)), BinaryColor::Off);
+}
+
+#[test]
+fn test_spinner() {
+    let mut spinner = Spinner::new("Volume", 50, 0, 100).step(30);
+    assert_eq!(spinner.handle(Button::RIGHT), WidgetEvent::Changed);
+    assert_eq!(spinner.value(), 80);
+    spinner.handle(Button::RIGHT);
+    assert_eq!(spinner.value(), 100);
+    assert_eq!(spinner.handle(Button::RIGHT), WidgetEvent::Consumed);
+    spinner.handle(Button::LEFT);
+    assert_eq!(spinner.value(), 70);
+    // ↑↓ はフォーカスの移動に使う
+    assert_eq!(spinner.handle(Button::UP), WidgetEvent::Ignored);
+    spinner.set_value(-10);
+    assert_eq!(spinner.value(), 0);
+}
+
+#[test]
+fn test_progress_bar_and_gauge() {
+    let mut bar = ProgressBar::new(200);
+    let area = Rectangle::new(Point::new(0, 0), Size::new(104, 8));
+    bar.set_value(50);
+    assert_eq!(bar.filled_width(area), 25);
+    bar.set_value(1000);
+    assert_eq!(bar.value(), 200);
+    assert_eq!(bar.filled_width(area), 100);
+    assert!(!bar.is_focusable());
+
+    let mut gauge = Gauge::new(0, 100);
+    assert_eq!(gauge.needle_angle(), 180.0);
+    gauge.set_value(50);
+    assert_eq!(gauge.needle_angle(), 270.0);
+    gauge.set_value(200);
+    assert_eq!(gauge.needle_angle(), 360.0);
+
+    let mut screen = Screen::new();
+    let id = screen.add(Rectangle::new(Point::zero(), Size::new(64, 32)), Gauge::new(0, 100));
+    assert_eq!(screen.focus(), None);
+    screen.get_mut::<Gauge>(id).unwrap().set_value(50);
+    let frame = render_screen(&screen);
+    // 針は真上を指す
+    assert_eq!(frame.get_pixel(Point::new(32, 10)), BinaryColor::On);
+    assert_eq!(frame.get_pixel(Point::new(20, 10)), BinaryColor::Off);
+}
+
+#[test]
+fn test_dialog() {
+    let mut dialog = Dialog::new("Delete?");
+    assert!(!dialog.answer());
+    assert_eq!(dialog.handle(Button::LEFT), WidgetEvent::Changed);
+    assert!(dialog.answer());
+    assert_eq!(dialog.handle(Button::A), WidgetEvent::Activated);
+    assert!(dialog.answer());
+    assert_eq!(dialog.handle(Button::B), WidgetEvent::Cancelled);
+    assert!(!dialog.answer());
+}
+
+#[test]
+fn test_toast() {
+    let mut toast = Toast::new();
+    assert!(!toast.is_visible());
+    assert!(!toast.tick());
+    toast.show("Saved", 2);
+    assert!(toast.is_visible());
+    assert!(!toast.tick());
+    assert!(toast.tick());
+    assert!(!toast.is_visible());
+
+    let mut screen = Screen::new();
+    let area = Rectangle::new(Point::new(0, 54), Size::new(128, 10));
+    let id = screen.add(area, Toast::new());
+    assert_eq!(render_screen(&screen).count_on(), 0);
+    screen.get_mut::<Toast>(id).unwrap().show("Saved", 10);
+    assert!(render_screen(&screen).count_on() > 0);
+}
+
+#[test]
+fn test_number_entry() {
+    let mut entry = NumberEntry::new("PIN", 4, 12345);
+    assert_eq!(entry.value(), 2345);
+    assert_eq!(entry.handle(Button::UP), WidgetEvent::Changed);
+    assert_eq!(entry.value(), 3345);
+    entry.handle(Button::RIGHT);
+    entry.handle(Button::RIGHT);
+    assert_eq!(entry.cursor(), 2);
+    entry.handle(Button::DOWN);
+    entry.handle(Button::DOWN);
+    entry.handle(Button::DOWN);
+    entry.handle(Button::DOWN);
+    entry.handle(Button::DOWN);
+    assert_eq!(entry.value(), 3395);
+    for _ in 0..10 {
+        entry.handle(Button::RIGHT);
+    }
+    assert_eq!(entry.cursor(), 3);
+    assert_eq!(entry.handle(Button::A), WidgetEvent::Activated);
+}
+
+#[test]
+fn test_screen_focus() {
+    let rows = layout::rows(layout::SCREEN);
+    let mut screen = Screen::new();
+    let progress = screen.add(rows[0], ProgressBar::new(10));
+    let volume = screen.add(rows[1], Spinner::new("Volume", 5, 0, 10));
+    let confirm = screen.add(rows[2], ListMenu::new(&["OK", "Cancel"]).wrap_around(false));
+    // 表示だけのウィジェットは飛ばす
+    assert_eq!(screen.focus(), Some(volume));
+
+    assert_eq!(screen.handle(Button::RIGHT), vec![(volume, WidgetEvent::Changed)]);
+    assert_eq!(screen.get::<Spinner>(volume).unwrap().value(), 6);
+
+    // Spinner は ↓ を使わないのでフォーカスが移る
+    assert!(screen.handle(Button::DOWN).is_empty());
+    assert_eq!(screen.focus(), Some(confirm));
+    // ListMenu は ↓ を使う
+    assert_eq!(screen.handle(Button::DOWN), vec![(confirm, WidgetEvent::Changed)]);
+    assert_eq!(screen.focus(), Some(confirm));
+    // 最後のウィジェットの端ではフォーカスも移らない
+    assert!(screen.handle(Button::DOWN).is_empty());
+    assert_eq!(screen.focus(), Some(confirm));
+
+    // 複数のボタンは UP, LEFT, DOWN, RIGHT, A, B の順に処理する
+    assert_eq!(screen.handle(Button::UP | Button::A), vec![
+        (confirm, WidgetEvent::Changed),
+        (confirm, WidgetEvent::Activated),
+    ]);
+    assert_eq!(screen.get::<ListMenu>(confirm).unwrap().selected(), 0);
+
+    // 先頭の ↑ は ProgressBar を飛ばして止まる
+    screen.handle(Button::UP);
+    assert_eq!(screen.focus(), Some(volume));
+    screen.handle(Button::UP);
+    assert_eq!(screen.focus(), Some(volume));
+
+    screen.set_focus(progress);
+    assert_eq!(screen.focus(), Some(volume));
+    assert!(screen.get::<Spinner>(progress).is_none());
+}
+
+#[test]
+fn test_screen_draw() {
+    let rows = layout::rows(layout::SCREEN);
+    let mut screen = Screen::new();
+    screen.add(rows[0], Spinner::new("Volume", 5, 0, 10));
+    screen.add(rows[1], Spinner::new("Speed", 5, 0, 10));
+
+    let frame = render_screen(&screen);
+    // フォーカス中の行は反転する
+    assert_eq!(frame.get_pixel(Point::new(127, 0)), BinaryColor::On);
+    assert_eq!(frame.get_pixel(Point::new(127, 10)), BinaryColor::Off);
+
+    // 先頭で画面を消す
+    let draw = screen.draw();
+    assert!(matches!(draw.commands()[0], DisplayCommand::Clear));
+}
@@ -1,3 +1,219 @@
+// 楽譜の変換処理はアプリと共有する
+#[allow(dead_code)]
+#[path = "src/note.rs"]
+mod note;
+#[allow(dead_code)]
+#[path = "src/score.rs"]
+mod score;
+#[allow(dead_code)]
+#[path = "src/midi.rs"]
+mod midi;
+// フォントのサブセット化もアプリと共有する
+#[allow(dead_code)]
+#[path = "src/bitmap_font.rs"]
+mod bitmap_font;
+// 画像の 2 値化もアプリと共有する
+#[allow(dead_code)]
+#[path = "src/image_asset.rs"]
+mod image_asset;
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use image_asset::{Dither, GrayImage, ImageConvert};
+use midi::{MidiImport, VoicePolicy};
+
+// 楽譜に変換する MIDI ファイル (asserts/sounds/<名前>.mid) と変換設定
+fn midi_assets() -> Vec<(&'static str, MidiImport)> {
+    vec![
+        // トラック 1 のチャンネル 0 がメロディ (チャンネル 1 はベース)
+        ("frere_jacques", MidiImport::new().track(1).channel(0).policy(VoicePolicy::Highest)),
+    ]
+}
+
+// 名前を関数名とするソースを生成する
+// (アプリ側からは midi_tunes::<名前>() または midi_tunes::MIDI_TUNES で楽譜を取得する)
+fn convert_midi_files(out_dir: &Path) {
+    let assets = midi_assets();
+    let mut source = String::new();
+    for (name, import) in assets.iter() {
+        let path = format!("asserts/sounds/{}.mid", name);
+        println!("cargo:rerun-if-changed={}", path);
+        let data = std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        let score = import.convert(&data).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        source.push_str(&midi::to_rust_source(name, &score));
+    }
+    source.push_str(&format!("pub const MIDI_TUNES: [(&str, fn() -> crate::score::Score); {}] = [\n", assets.len()));
+    for (name, _) in assets.iter() {
+        source.push_str(&format!("    (\"{}\", {}),\n", name, name));
+    }
+    source.push_str("];\n");
+
+    std::fs::write(out_dir.join("midi_tunes.rs"), source).unwrap();
+}
+
+// 日本語フォント (japanese-font フィーチャを有効にした場合だけ変換する)
+// - 美咲フォント (8x8) や k8x12 (8x12) などの BDF を置く (ライセンスファイルも一緒に置くこと)
+// - CHARSET_REGISTRY が ISO10646 (Unicode) か JISX0208 (配布されている BDF のまま) のものに対応
+const JAPANESE_FONT_PATH: &str = "asserts/fonts/japanese.bdf";
+// 収録する漢字 (かなと記号は常に収録する)
+const KANJI_LIST_PATH: &str = "asserts/fonts/kanji.txt";
+
+// かな、句読点などの記号、全角英数字
+fn japanese_base_chars() -> BTreeSet<char> {
+    let ranges = [
+        '\u{3000}'..='\u{3003}',  // 全角空白、、。〃
+        '\u{300C}'..='\u{300F}',  // 「」『』
+        '\u{3041}'..='\u{3096}',  // ひらがな
+        '\u{309B}'..='\u{309E}',  // ゛゜ゝゞ
+        '\u{30A1}'..='\u{30FA}',  // カタカナ
+        '\u{30FB}'..='\u{30FE}',  // ・ーヽヾ
+        '\u{FF01}'..='\u{FF5E}',  // 全角英数字・記号
+    ];
+    ranges.into_iter().flatten().collect()
+}
+
+// '#' で始まる行はコメント、空白と改行は無視する
+fn read_kanji_list() -> BTreeSet<char> {
+    println!("cargo:rerun-if-changed={}", KANJI_LIST_PATH);
+    let text = std::fs::read_to_string(KANJI_LIST_PATH).unwrap_or_default();
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(|line| line.chars())
+        .filter(|ch| !ch.is_whitespace())
+        .collect()
+}
+
+// アプリのソースの文字列リテラルで使っている文字
+fn used_chars() -> BTreeSet<char> {
+    println!("cargo:rerun-if-changed=src");
+    let mut chars = BTreeSet::new();
+    for entry in std::fs::read_dir("src").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|extension| extension == "rs") {
+            let source = std::fs::read_to_string(&path).unwrap();
+            chars.extend(bitmap_font::string_literal_chars(&source));
+        }
+    }
+    chars
+}
+
+// BDF の文字コードを Unicode に変換する
+fn decode(charset: &str, encoding: u32) -> Option<char> {
+    if charset.starts_with("ISO10646") {
+        char::from_u32(encoding)
+    } else if charset.starts_with("JISX0208") {
+        // 区点を EUC-JP に直して変換する
+        let bytes = [(encoding >> 8) as u8 | 0x80, encoding as u8 | 0x80];
+        let text = encoding_rs::EUC_JP.decode_without_bom_handling_and_without_replacement(&bytes)?;
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Some(ch),
+            _ => None,
+        }
+    } else {
+        panic!("{}: unsupported charset \"{}\"", JAPANESE_FONT_PATH, charset);
+    }
+}
+
+// 使う文字だけを取り出したフォントを生成する
+// (アプリ側からは japanese_font::JAPANESE_FONT で参照する)
+fn convert_japanese_font(out_dir: &Path) {
+    println!("cargo:rerun-if-changed={}", JAPANESE_FONT_PATH);
+    let mut wanted = japanese_base_chars();
+    wanted.extend(read_kanji_list());
+    wanted.extend(used_chars());
+
+    // 空のフォントで日本語が全て四角になるのに気付かないより、ビルドを止める
+    let bdf = std::fs::read_to_string(JAPANESE_FONT_PATH).unwrap_or_else(|e| panic!(
+        "{}: {} (the japanese-font feature needs a BDF font, e.g. misaki or k8x12)", JAPANESE_FONT_PATH, e));
+    let font = bitmap_font::parse_bdf(&bdf).unwrap_or_else(|e| panic!("{}: {}", JAPANESE_FONT_PATH, e));
+    let mut glyphs: Vec<(char, Vec<u8>)> = font.glyphs.into_iter()
+        .filter_map(|(encoding, bitmap)| Some((decode(&font.charset, encoding)?, bitmap)))
+        .filter(|(ch, _)| wanted.contains(ch))
+        .collect();
+    glyphs.sort_by_key(|(ch, _)| *ch);
+    glyphs.dedup_by_key(|(ch, _)| *ch);
+    let source = bitmap_font::to_rust_source("JAPANESE_FONT", font.width, font.height, &glyphs);
+    std::fs::write(out_dir.join("japanese_font.rs"), source).unwrap();
+}
+
+// 2 値化する画像 (asserts/images/<名前>.png または .gif) と変換設定
+fn image_assets() -> Vec<(&'static str, ImageConvert)> {
+    vec![
+        // 階調のある起動画面は誤差拡散で粒の密度にする
+        ("pomodoro_startup", ImageConvert::new().dither(Dither::FloydSteinberg).compress(true)),
+    ]
+}
+
+type ImageLoader = fn(&[u8]) -> Result<GrayImage, Box<dyn std::error::Error>>;
+
+fn load_png(data: &[u8]) -> Result<GrayImage, Box<dyn std::error::Error>> {
+    let mut decoder = png::Decoder::new(data);
+    // パレットは RGB に、16 ビットは 8 ビットにする
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let pixels = &buffer[..info.buffer_size()];
+    let rgba: Vec<u8> = match info.color_type {
+        png::ColorType::Grayscale => pixels.iter().flat_map(|v| [*v, *v, *v, 0xFF]).collect(),
+        png::ColorType::GrayscaleAlpha => pixels.chunks(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Rgb => pixels.chunks(3).flat_map(|p| [p[0], p[1], p[2], 0xFF]).collect(),
+        png::ColorType::Rgba => pixels.to_vec(),
+        png::ColorType::Indexed => return Err("indexed PNG was not expanded".into()),
+    };
+    Ok(GrayImage::from_rgba(info.width, info.height, &rgba)?)
+}
+
+// アニメーション GIF は最初のフレームだけを使う
+fn load_gif(data: &[u8]) -> Result<GrayImage, Box<dyn std::error::Error>> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(data)?;
+    let (width, height) = (decoder.width() as u32, decoder.height() as u32);
+    let frame = decoder.read_next_frame()?.ok_or("no frame")?;
+    // フレームは画面の一部のことがあるので、画面の大きさの透明な画像に置く
+    let mut rgba = vec![0u8; (width * height * 4) as usize];
+    for y in 0..frame.height as u32 {
+        for x in 0..frame.width as u32 {
+            let (screen_x, screen_y) = (frame.left as u32 + x, frame.top as u32 + y);
+            if screen_x < width && screen_y < height {
+                let source = ((y * frame.width as u32 + x) * 4) as usize;
+                let target = ((screen_y * width + screen_x) * 4) as usize;
+                rgba[target..target + 4].copy_from_slice(&frame.buffer[source..source + 4]);
+            }
+        }
+    }
+    Ok(GrayImage::from_rgba(width, height, &rgba)?)
+}
+
+// 名前を大文字にした定数を生成する
+// (アプリ側からは images::<名前> で ImageAsset を参照する)
+fn convert_images(out_dir: &Path) {
+    let mut source = String::new();
+    for (name, convert) in image_assets().iter() {
+        let png_path = format!("asserts/images/{}.png", name);
+        let (path, load): (String, ImageLoader) = if Path::new(&png_path).exists() {
+            (png_path, load_png)
+        } else {
+            (format!("asserts/images/{}.gif", name), load_gif)
+        };
+        println!("cargo:rerun-if-changed={}", path);
+        let data = std::fs::read(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        let image = load(&data).unwrap_or_else(|e| panic!("{}: {}", path, e));
+        source.push_str(&image_asset::to_rust_source(name, &image, convert));
+    }
+    std::fs::write(out_dir.join("images.rs"), source).unwrap();
+}
+
 fn main() {
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    convert_midi_files(Path::new(&out_dir));
+    if std::env::var_os("CARGO_FEATURE_JAPANESE_FONT").is_some() {
+        convert_japanese_font(Path::new(&out_dir));
+    }
+    convert_images(Path::new(&out_dir));
+
     embuild::espidf::sysenv::output();
 }
@@ -0,0 +1,165 @@
+use crate::app_context::AppContext;
+use crate::app_context::AppFramework;
+use crate::display_driver::I2C_BAUDRATE_KHZ;
+
+use crate::Button;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use rustorch::display_command::{DisplayFrame, SubmitPolicy};
+use rustorch::frame_buffer::FrameBuffer;
+use rustorch::grayscale::{subframes, GrayscaleEstimate, GrayscaleImage, GrayscaleMethod, I2cTiming};
+use rustorch::text::{HorizontalAlign, TextStyle, VerticalAlign};
+use rustorch::widget::layout;
+
+// グレースケール表示の実機でのベンチマーク
+// - ポモドーロタイマーの準備中の画面 (4 階調) を DisplayDriver でサブフレームを切り替えながら表示し、
+//   get_flush_stats() からフレームレート (サブフレームを一巡する回数) と 1 回の転送時間を計測する
+// - 方式毎に計測し、結果を画面とログに出す (ログにはホストでの見積もりも並べる)
+// - I2C のクロックは oled-1mhz フィーチャで変わるので、両方のビルドで実行して比べる
+// B でいつでもメニューに戻る
+
+const IMAGE: &[u8] = include_bytes!("../asserts/images/pomodoro_startup.gray.bmp");
+const LEVELS: u8 = 4;
+const METHODS: [GrayscaleMethod; 2] = [ GrayscaleMethod::WeightedPlanes, GrayscaleMethod::Pwm ];
+
+// 表示を切り替えた直後の転送は計測から外す
+const WARMUP_FRAMES: u64 = 30;
+const MEASURE_FRAMES: u64 = 60 * 5;
+
+fn now_us() -> i64 {
+    unsafe { esp_idf_sys::esp_timer_get_time() }
+}
+
+pub struct GrayscaleBenchmark {
+    finished: bool,
+    image: Option<GrayscaleImage>,
+    // 計測中の METHODS の番号 (全て終わったら METHODS.len())
+    method_index: usize,
+    // 計測中の方式を表示し始めてからのフレーム数
+    method_frames: u64,
+    start_us: i64,
+    // 画面に出す結果 (1 方式 2 行)
+    results: Vec<String>,
+}
+
+impl GrayscaleBenchmark {
+    pub fn new() -> Self {
+        GrayscaleBenchmark {
+            finished: false,
+            image: None,
+            method_index: 0,
+            method_frames: 0,
+            start_us: 0,
+            results: Vec::new(),
+        }
+    }
+
+    fn draw(&self, context: &AppContext, status: &str) -> anyhow::Result<()> {
+        let mut frame = DisplayFrame::new();
+        frame.clear();
+        let title_style = TextStyle::new()
+            .inverted(true)
+            .align(HorizontalAlign::Center, VerticalAlign::Middle);
+        frame.draw_text_box(format!("Gray bench {}kHz", I2C_BAUDRATE_KHZ), Rectangle::new(Point::zero(), Size::new(128, 10)), title_style);
+        let rows = layout::rows(Rectangle::new(Point::new(0, 10), Size::new(128, 54)));
+        for (row, text) in rows.iter().zip(self.results.iter().map(String::as_str).chain([status])) {
+            frame.draw_text_box(text.to_string(), *row, TextStyle::new());
+        }
+        context.display.lock().unwrap().submit(frame, SubmitPolicy::Wait)?;
+        Ok(())
+    }
+
+    // 次の方式の計測を始める (全て終わったら結果を表示する)
+    fn start_method(&mut self, context: &AppContext) -> anyhow::Result<()> {
+        self.method_frames = 0;
+        let Some(image) = self.image.clone() else {
+            return self.draw(context, "No grayscale image");
+        };
+        match METHODS.get(self.method_index) {
+            Some(method) => {
+                let mut frame = DisplayFrame::new();
+                frame.clear().show_grayscale(image, Point::zero(), *method);
+                context.display.lock().unwrap().submit(frame, SubmitPolicy::Wait)?;
+            },
+            None => {
+                context.display.lock().unwrap().hide_grayscale()?;
+                self.draw(context, "B: Back")?;
+            },
+        }
+        Ok(())
+    }
+
+    // 計測中の方式の結果をまとめる
+    fn finish_method(&mut self, context: &AppContext) {
+        let elapsed_us = (now_us() - self.start_us).max(1) as f32;
+        let stats = context.display.lock().unwrap().get_flush_stats();
+        let method = METHODS[self.method_index];
+        let image = self.image.as_ref().unwrap();
+        let planes = subframes(&FrameBuffer::new(), image, Point::zero(), method);
+        let fps = stats.count as f32 * 1_000_000.0 / elapsed_us / planes.len() as f32;
+        let timing = if I2C_BAUDRATE_KHZ >= 1000 { I2cTiming::FAST_1M } else { I2cTiming::STANDARD_400K };
+        let estimate = GrayscaleEstimate::estimate(&planes, &timing);
+        log::info!("[bench] {:?} {} levels, I2C {}kHz: {:.1} fps (estimate {:.1} fps), flush mean {} us, max {} us, {} bytes/flush",
+            method, LEVELS, I2C_BAUDRATE_KHZ, fps, estimate.frame_rate(), stats.mean_us(), stats.max_us,
+            stats.sum_bytes / (stats.count.max(1) as u64));
+        // 1 行 (21 文字) に収める
+        let name = match method {
+            GrayscaleMethod::WeightedPlanes => "Planes",
+            GrayscaleMethod::Pwm => "PWM",
+        };
+        self.results.push(format!("{} {:.1} fps", name, fps));
+        self.results.push(format!(" mean {:.1} max {:.1} ms", stats.mean_us() as f32 / 1000.0, stats.max_us as f32 / 1000.0));
+    }
+}
+
+impl AppFramework for GrayscaleBenchmark {
+    fn get_name(&self) -> &str {
+        "Gray benchmark"
+    }
+
+    fn initialize(&mut self, context: &AppContext) -> anyhow::Result<()> {
+        self.finished = false;
+        self.method_index = 0;
+        self.results.clear();
+        self.image = match GrayscaleImage::from_bmp(IMAGE, LEVELS) {
+            Ok(image) => Some(image),
+            Err(e) => {
+                log::warn!("[bench] {}", e);
+                None
+            },
+        };
+        self.start_method(context)
+    }
+
+    fn update(&mut self, context: &AppContext, _frame_count: u64) -> anyhow::Result<()> {
+        let released_button = context.button.lock().unwrap().was_released(Button::MASK);
+        if released_button & Button::B != 0 {
+            self.finished = true;
+            return Ok(());
+        }
+        if self.image.is_none() || self.method_index >= METHODS.len() {
+            return Ok(());
+        }
+
+        self.method_frames += 1;
+        if self.method_frames == WARMUP_FRAMES {
+            context.display.lock().unwrap().reset_flush_stats();
+            self.start_us = now_us();
+        } else if self.method_frames == WARMUP_FRAMES + MEASURE_FRAMES {
+            self.finish_method(context);
+            self.method_index += 1;
+            self.start_method(context)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(&mut self, context: &AppContext) -> anyhow::Result<()> {
+        // グレースケール表示中は表示スレッドが I2C を使い続けるので止める
+        context.display.lock().unwrap().hide_grayscale()?;
+        Ok(())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
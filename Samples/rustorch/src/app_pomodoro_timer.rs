@@ -2,10 +2,32 @@ use crate::app_context::AppContext;
 use crate::app_context::AppFramework;
 
 use crate::Button;
+use crate::Padding;
 use crate::Volume;
+use crate::SoundEffect;
+use rustorch::display_command::{DisplayFrame, SubmitPolicy};
+use rustorch::grayscale::{GrayscaleImage, GrayscaleMethod};
+use rustorch::images;
+use rustorch::sprite::{Animation, Flip, Sprite, SpriteId, SpriteSheet};
 
+use embedded_graphics::pixelcolor::BinaryColor;
 use embedded_graphics::prelude::*;
 
+// 作業中/休憩中に画面下で回すスピナー (12x12 が 8 フレーム)
+const SPINNER_ID: SpriteId = 0;
+const SPINNER_POSITION: Point = Point::new(58, 50);
+
+// 休憩中は左右反転して逆回りにする
+fn spinner(flip: Flip) -> anyhow::Result<Sprite> {
+    let sheet = SpriteSheet::new(include_bytes!("../asserts/images/pomodoro_spinner.bmp"), Size::new(12, 12))?
+        .transparent(BinaryColor::Off);
+    Ok(Sprite::new(Animation::new(sheet).frames(0..8, 125), SPINNER_POSITION).flip(flip))
+}
+
+// 準備中の画面は 4 階調で表示する
+// (ちらつかない程度のフレームレートが出るかはメニューの Gray benchmark で実機計測する)
+const STARTUP_LEVELS: u8 = 4;
+
 enum State {
     // 準備中
     Preparing,
@@ -35,17 +57,6 @@ impl PomodoroTimer {
     }
 }
 
-fn convert_to_display_format(time: u32, with_dot: bool) -> String {
-    let minutes: u32 = time / 60;
-    let seconds: u32 = time % 60;
-    // 2桁目のドットは動作中表現用
-    if with_dot {
-        return format!("{:2}.{:02}", minutes, seconds);
-    } else {
-        return format!("{:2}{:02}", minutes, seconds);
-    }
-}
-
 impl AppFramework for PomodoroTimer {
     fn get_name(&self) -> &str {
         "Pomodoro timer"
@@ -56,12 +67,20 @@ impl AppFramework for PomodoroTimer {
         self.state = State::Preparing;
         self.finished = false;
 
-        context.led.lock().unwrap().write_format(&"25.00".to_string());
+        context.led.lock().unwrap().write_minutes_seconds(self.remaining_time, true, Padding::Space);
         {
-            let mut locked = context.display.lock().unwrap();
-            locked.clear()?;
-            locked.draw_image(include_bytes!("../asserts/images/pomodoro_startup.bmp"), Point::new(0, 0))?;
-            locked.update()?;
+            let mut frame = DisplayFrame::new();
+            // 誤差拡散の 2 値画像はグレースケール画像を読めなかった場合の代わり
+            // (グレースケール表示中は同じ位置にサブフレームが重なるので見えない)
+            frame.clear()
+                .draw_asset(images::POMODORO_STARTUP, Point::new(0, 0));
+            match GrayscaleImage::from_bmp(include_bytes!("../asserts/images/pomodoro_startup.gray.bmp"), STARTUP_LEVELS) {
+                Ok(startup) => {
+                    frame.show_grayscale(startup, Point::new(0, 0), GrayscaleMethod::WeightedPlanes);
+                },
+                Err(e) => log::warn!("[pomodoro] startup image is shown in 2 levels: {}", e),
+            }
+            context.display.lock().unwrap().submit(frame, SubmitPolicy::Wait)?;
         }
         Ok(())
     }
@@ -94,16 +113,16 @@ impl AppFramework for PomodoroTimer {
             } else if self.remaining_time > 10 {
                 self.remaining_time -= 10;
             }
-            let display_format = convert_to_display_format(self.remaining_time, false);
-            context.led.lock().unwrap().write_format(&display_format);
+            context.led.lock().unwrap().write_minutes_seconds(self.remaining_time, false, Padding::Space);
         }
 
         // 強制リセット
         if was_reset_button_pressed {
+            context.buzzer.lock().unwrap().play_effect(SoundEffect::Cancel)?;
             self.state = State::Preparing;
             self.remaining_time = 25 * 60;
-            let display_format = convert_to_display_format(self.remaining_time, false);
-            context.led.lock().unwrap().write_format(&display_format);
+            context.led.lock().unwrap().write_minutes_seconds(self.remaining_time, false, Padding::Space);
+            context.display.lock().unwrap().stop_animation(SPINNER_ID)?;
             return Ok(());
         }
 
@@ -111,11 +130,14 @@ impl AppFramework for PomodoroTimer {
             State::Preparing => {
                 if was_start_stop_button_pressed {
                     self.state = State::Working;
+                    context.buzzer.lock().unwrap().play_effect(SoundEffect::Confirm)?;
                     {
-                        let mut locked = context.display.lock().unwrap();
-                        locked.clear()?;
-                        locked.draw_image(include_bytes!("../asserts/images/pomodoro_working.bmp"), Point::new(0, 0))?;
-                        locked.update()?;
+                        let mut frame = DisplayFrame::new();
+                        frame.hide_grayscale()
+                            .clear()
+                            .draw_image(include_bytes!("../asserts/images/pomodoro_working.bmp"), Point::new(0, 0))
+                            .start_animation(SPINNER_ID, spinner(Flip::None)?);
+                        context.display.lock().unwrap().submit(frame, SubmitPolicy::Wait)?;
                     }
                 }
             },
@@ -123,47 +145,59 @@ impl AppFramework for PomodoroTimer {
                 do_count_down(&mut self.remaining_time, &sub_frame);
                 if was_start_stop_button_pressed {
                     self.state = State::WorkingPaused;
+                    context.display.lock().unwrap().stop_animation(SPINNER_ID)?;
                 }
                 if self.remaining_time == 0 {
                     self.remaining_time = 5 * 60;
                     self.state = State::Resting;
+                    context.buzzer.lock().unwrap().play_effect(SoundEffect::PhaseChange)?;
                     {
-                        let mut locked = context.display.lock().unwrap();
-                        locked.clear()?;
-                        locked.draw_image(include_bytes!("../asserts/images/pomodoro_resting.bmp"), Point::new(0, 0))?;
-                        locked.update()?;
+                        let mut frame = DisplayFrame::new();
+                        frame.clear()
+                            .draw_image(include_bytes!("../asserts/images/pomodoro_resting.bmp"), Point::new(0, 0))
+                            .start_animation(SPINNER_ID, spinner(Flip::Horizontal)?);
+                        context.display.lock().unwrap().submit(frame, SubmitPolicy::Wait)?;
                     }
                 }
-                let display_format = convert_to_display_format(self.remaining_time, with_dot);
-                context.led.lock().unwrap().write_format(&display_format);
+                // 2桁目のドットは動作中表現用
+                context.led.lock().unwrap().write_minutes_seconds(self.remaining_time, with_dot, Padding::Space);
             },
             State::WorkingPaused => {
                 if was_start_stop_button_pressed {
                     self.state = State::Working;
+                    context.display.lock().unwrap().start_animation(SPINNER_ID, spinner(Flip::None)?)?;
                 }
             },
             State::Resting => {
                 do_count_down(&mut self.remaining_time, &sub_frame);
                 if was_start_stop_button_pressed {
                     self.state = State::RestingPaused;
+                    context.display.lock().unwrap().stop_animation(SPINNER_ID)?;
                 }
                 if self.remaining_time == 0 {
                     self.remaining_time = 25 * 60;
                     self.state = State::Preparing;
+                    context.buzzer.lock().unwrap().play_effect(SoundEffect::TimerDone)?;
+                    context.display.lock().unwrap().stop_animation(SPINNER_ID)?;
                 }
-                let display_format = convert_to_display_format(self.remaining_time, with_dot);
-                context.led.lock().unwrap().write_format(&display_format);
+                // 2桁目のドットは動作中表現用
+                context.led.lock().unwrap().write_minutes_seconds(self.remaining_time, with_dot, Padding::Space);
             },
             State::RestingPaused => {
                 if was_start_stop_button_pressed {
                     self.state = State::Resting;
+                    context.display.lock().unwrap().start_animation(SPINNER_ID, spinner(Flip::Horizontal)?)?;
                 }
             },
         }
         Ok(())
     }
 
-    fn finalize(&mut self, _context: &AppContext) -> anyhow::Result<()> {
+    fn finalize(&mut self, context: &AppContext) -> anyhow::Result<()> {
+        // アニメーションは画面を描き直しても残るので、メニューに戻る前に止める
+        context.display.lock().unwrap().stop_animation(SPINNER_ID)?;
+        // グレースケール表示中は表示スレッドが I2C を使い続けるので止める
+        context.display.lock().unwrap().hide_grayscale()?;
         Ok(())
     }
 
@@ -0,0 +1,262 @@
+use crate::app_context::AppContext;
+use crate::app_context::AppFramework;
+use crate::buzzer_driver::BuzzerDriver;
+
+use crate::Button;
+use crate::SoundEffect;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
+use rustorch::display_command::{DisplayFrame, SubmitPolicy};
+use rustorch::dnd::QuietHours;
+use rustorch::text::{HorizontalAlign, TextStyle, VerticalAlign};
+use rustorch::widget::{layout, layout::{Direction, Length}, ListMenu, NumberEntry, Screen, Widget, WidgetEvent};
+
+// 設定項目 (↑↓ で選び、A で切り替えまたは編集、B で戻る)
+// 設定は BuzzerDriver が NVS に保存するので再起動後も保持される (時計だけは電源を切ると未設定に戻る)
+const ITEM_UI_SOUNDS: usize = 0;
+const ITEM_DND: usize = 1;
+const ITEM_CLOCK: usize = 2;
+const ITEM_QUIET_HOURS: usize = 3;
+
+// 時刻の編集中の状態 (時刻は HHMM の 4 桁で入力する)
+enum Editing {
+    Clock(NumberEntry),
+    // 静音時間の開始と終了を順に入力する (同じ時刻にするとスケジュールなし)
+    QuietStart(NumberEntry),
+    QuietEnd(u16, NumberEntry),
+}
+
+impl Editing {
+    fn entry(&self) -> &NumberEntry {
+        match self {
+            Editing::Clock(entry) | Editing::QuietStart(entry) | Editing::QuietEnd(_, entry) => entry,
+        }
+    }
+
+    fn entry_mut(&mut self) -> &mut NumberEntry {
+        match self {
+            Editing::Clock(entry) | Editing::QuietStart(entry) | Editing::QuietEnd(_, entry) => entry,
+        }
+    }
+
+    fn hint(&self) -> &str {
+        match self {
+            Editing::Clock(_) => "Set the clock (HHMM)",
+            Editing::QuietStart(_) => "Quiet from (HHMM)",
+            Editing::QuietEnd(..) => "Until (same: off)",
+        }
+    }
+}
+
+// 0:00 からの分数を HHMM の 4 桁の数値にする
+fn to_hhmm(minute_of_day: u16) -> u32 {
+    (minute_of_day / 60 * 100 + minute_of_day % 60) as u32
+}
+
+// HHMM の 4 桁の数値を 0:00 からの分数にする (時刻として正しくなければ None)
+fn from_hhmm(hhmm: u32) -> Option<u16> {
+    let (hours, minutes) = (hhmm / 100, hhmm % 100);
+    (hours < 24 && minutes < 60).then_some((hours * 60 + minutes) as u16)
+}
+
+pub struct Settings {
+    finished: bool,
+    selected_index: usize,
+    editing: Option<Editing>,
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Settings {
+            finished: false,
+            selected_index: 0,
+            editing: None,
+        }
+    }
+
+    // 現在の値を含めた項目名
+    fn items(buzzer: &BuzzerDriver) -> Vec<String> {
+        let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+        let clock = match buzzer.local_minute_of_day() {
+            Some(minute) => format!("{}:{:02}", minute / 60, minute % 60),
+            None => "--:--".to_string(),
+        };
+        // 1 行 (21 文字) に収める
+        let quiet_hours = match buzzer.get_dnd_settings().quiet_hours {
+            Some(quiet_hours) => format!("Quiet {}", quiet_hours),
+            None => "Quiet hours: off".to_string(),
+        };
+        vec![
+            format!("Menu sounds: {}", on_off(buzzer.is_ui_sounds_enabled())),
+            format!("Do not disturb: {}", on_off(buzzer.get_dnd_settings().manual)),
+            format!("Clock: {}", clock),
+            quiet_hours,
+        ]
+    }
+
+    fn draw(&self, context: &AppContext) -> anyhow::Result<()> {
+        let items = Self::items(&context.buzzer.lock().unwrap());
+        let mut frame = DisplayFrame::new();
+        frame.clear();
+        let title_style = TextStyle::new()
+            .inverted(true)
+            .align(HorizontalAlign::Center, VerticalAlign::Middle);
+        frame.draw_text_box("Settings".to_string(), Rectangle::new(Point::zero(), Size::new(128, 10)), title_style);
+        let mut screen = Screen::new();
+        let list_area = layout::stack(layout::SCREEN, Direction::Vertical, &[Length::Fixed(10), Length::Fill])[1];
+        match &self.editing {
+            Some(editing) => {
+                let rows = layout::rows(list_area);
+                frame.draw_text_box(editing.hint().to_string(), rows[0], TextStyle::new());
+                editing.entry().draw(&mut frame, rows[2], true);
+                frame.draw_text_box("A: OK  B: Cancel".to_string(), rows[4], TextStyle::new());
+            },
+            None => {
+                screen.add(list_area, ListMenu::new(&items).with_selected(self.selected_index));
+            },
+        }
+        screen.draw_into(&mut frame);
+        context.display.lock().unwrap().submit(frame, SubmitPolicy::Wait)?;
+        Ok(())
+    }
+
+    fn toggle(&mut self, context: &AppContext, index: usize) -> anyhow::Result<()> {
+        let mut buzzer = context.buzzer.lock().unwrap();
+        match index {
+            ITEM_UI_SOUNDS => {
+                let enabled = !buzzer.is_ui_sounds_enabled();
+                buzzer.set_ui_sounds_enabled(enabled)?;
+            },
+            ITEM_DND => {
+                let enable = !buzzer.get_dnd_settings().manual;
+                buzzer.set_dnd(enable)?;
+            },
+            ITEM_CLOCK => {
+                let minute = buzzer.local_minute_of_day().unwrap_or(0);
+                self.editing = Some(Editing::Clock(NumberEntry::new("Clock", 4, to_hhmm(minute))));
+            },
+            ITEM_QUIET_HOURS => {
+                let start = buzzer.get_dnd_settings().quiet_hours.map_or(22 * 60, |quiet_hours| quiet_hours.start_minute());
+                self.editing = Some(Editing::QuietStart(NumberEntry::new("From", 4, to_hhmm(start))));
+            },
+            _ => {},
+        }
+        // 操作音を有効にした場合やおやすみモードを解除した場合はここで鳴る
+        buzzer.play_effect(SoundEffect::Click)?;
+        Ok(())
+    }
+
+    // 編集中の時刻を確定する (正しくない時刻なら編集を続ける)
+    fn confirm(&mut self, context: &AppContext, editing: Editing) -> anyhow::Result<()> {
+        let mut buzzer = context.buzzer.lock().unwrap();
+        let Some(minute) = from_hhmm(editing.entry().value()) else {
+            buzzer.play_effect(SoundEffect::Error)?;
+            self.editing = Some(editing);
+            return Ok(());
+        };
+        match editing {
+            Editing::Clock(_) => buzzer.set_local_time(minute)?,
+            Editing::QuietStart(_) => {
+                let end = buzzer.get_dnd_settings().quiet_hours.map_or(7 * 60, |quiet_hours| quiet_hours.end_minute());
+                self.editing = Some(Editing::QuietEnd(minute, NumberEntry::new("Until", 4, to_hhmm(end))));
+            },
+            Editing::QuietEnd(start, _) => {
+                buzzer.set_quiet_hours(QuietHours::new(start, minute).ok())?;
+            },
+        }
+        buzzer.play_effect(SoundEffect::Confirm)?;
+        Ok(())
+    }
+
+    // 時刻の編集中のボタン入力 (戻り値は再描画が必要か)
+    fn update_editing(&mut self, context: &AppContext, mut editing: Editing, released_button: u8) -> anyhow::Result<bool> {
+        let mut redraw = false;
+        for button in [Button::UP, Button::DOWN, Button::LEFT, Button::RIGHT, Button::A, Button::B] {
+            if released_button & button == 0 {
+                continue;
+            }
+            match editing.entry_mut().handle(button) {
+                WidgetEvent::Changed | WidgetEvent::Consumed => redraw = true,
+                WidgetEvent::Activated => {
+                    self.confirm(context, editing)?;
+                    return Ok(true);
+                },
+                WidgetEvent::Cancelled => {
+                    context.buzzer.lock().unwrap().play_effect(SoundEffect::Cancel)?;
+                    return Ok(true);
+                },
+                _ => {},
+            }
+        }
+        self.editing = Some(editing);
+        Ok(redraw)
+    }
+}
+
+impl AppFramework for Settings {
+    fn get_name(&self) -> &str {
+        "Settings"
+    }
+
+    fn initialize(&mut self, context: &AppContext) -> anyhow::Result<()> {
+        self.finished = false;
+        self.selected_index = 0;
+        self.editing = None;
+        self.draw(context)
+    }
+
+    fn update(&mut self, context: &AppContext, frame_count: u64) -> anyhow::Result<()> {
+        let released_button = context.button.lock().unwrap().was_released(Button::MASK);
+        if released_button == 0 {
+            // 時計の表示を進める
+            if self.editing.is_none() && frame_count % 60 == 0 {
+                self.draw(context)?;
+            }
+            return Ok(());
+        }
+
+        if let Some(editing) = self.editing.take() {
+            if self.update_editing(context, editing, released_button)? {
+                self.draw(context)?;
+            }
+            return Ok(());
+        }
+
+        let items = Self::items(&context.buzzer.lock().unwrap());
+        let mut menu = ListMenu::new(&items).with_selected(self.selected_index).wrap_around(true);
+        let mut redraw = false;
+        for button in [Button::UP, Button::DOWN, Button::A, Button::B] {
+            if released_button & button == 0 {
+                continue;
+            }
+            match menu.handle(button) {
+                WidgetEvent::Changed => {
+                    self.selected_index = menu.selected();
+                    context.buzzer.lock().unwrap().play_effect(SoundEffect::Click)?;
+                    redraw = true;
+                },
+                WidgetEvent::Activated => {
+                    self.toggle(context, menu.selected())?;
+                    redraw = true;
+                    if self.editing.is_some() {
+                        break;
+                    }
+                },
+                WidgetEvent::Cancelled => self.finished = true,
+                _ => {},
+            }
+        }
+        if redraw && !self.finished {
+            self.draw(context)?;
+        }
+        Ok(())
+    }
+
+    fn finalize(&mut self, _context: &AppContext) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
@@ -2,6 +2,7 @@ use crate::app_context::AppContext;
 use crate::app_context::AppFramework;
 
 use crate::Button;
+use crate::SoundEffect;
 
 enum State {
     // [---] 起動状態
@@ -87,6 +88,7 @@ impl AppFramework for SlotGame {
             State::Startup => {
                 if was_rolling_started {
                     self.state = State::Rolling;
+                    context.buzzer.lock().unwrap().play_effect(SoundEffect::Laser)?;
                     self.fixed_digit_count = 0;
                     self.fixed_number = Default::default();
                     self.internal_number = Default::default();
@@ -110,6 +112,7 @@ impl AppFramework for SlotGame {
                     // TODO: 内部数値から確定数値への変換処理を仕上げる (演出関連)
                     self.fixed_number[index] = (self.internal_number[index] / self.animation_delay_param / NUMBER_SEGMENT_SLOT_TABLE[0].len() as u32) as u8;
                     self.fixed_digit_count += 1;
+                    context.buzzer.lock().unwrap().play_effect(SoundEffect::Click)?;
 
                     println!("fixed_digit_count : {} -> {}", index, index + 1);
                 }
@@ -147,7 +150,10 @@ impl AppFramework for SlotGame {
             },
             State::Fixed => {
                 // TODO: 結果に対して何かしらのアニメーションさせる?
-                // TORIAEZU: 現状は NOP で遷移
+                // 3 桁揃ったら当たり
+                if self.fixed_number.iter().all(|&number| number == self.fixed_number[0]) {
+                    context.buzzer.lock().unwrap().play_effect(SoundEffect::Jackpot)?;
+                }
                 self.state = State::Startup;
                 println!("-> Startup");
             },
@@ -2,10 +2,43 @@ use crate::app_context::AppContext;
 use crate::app_context::AppFramework;
 
 use crate::Button;
+use crate::Padding;
+use rustorch::melodies;
+use rustorch::note::{Note, PitchClass};
+use rustorch::midi_tunes;
+use rustorch::rtttl::{self, RtttlMode};
+use rustorch::tone::Instrument;
+
+// ボタンと音の対応 (低い音から順に並べる)
+const NOTE_KEYS: [(u8, PitchClass); 6] = [
+    (Button::UP,    PitchClass::C),
+    (Button::LEFT,  PitchClass::D),
+    (Button::DOWN,  PitchClass::E),
+    (Button::RIGHT, PitchClass::F),
+    (Button::B,     PitchClass::G),
+    (Button::A,     PitchClass::A),
+];
+
+// 和音の構成音を切り替える間隔 [ms]
+const ARPEGGIO_STEP_MS: u32 = 30;
+
+// 押したボタンの組み合わせがこのフレーム数変わらなければ鳴らす
+// (同時押しのつもりでも押すタイミングは少しずれるので、途中の単音や和音を鳴らさない)
+const CHORD_SETTLE_FRAMES: u32 = 2;
 
 pub struct ToyPiano {
     finished: bool,
     previous_key_status: u8,
+    // 今のボタンの組み合わせが続いているフレーム数
+    stable_frames: u32,
+    // 最後に音に反映したボタンの組み合わせ
+    settled_key_status: u8,
+    // 次に演奏するサンプル曲
+    tune_index: usize,
+    // サンプル曲の演奏中はボタンを離しても止めない
+    playing_tune: bool,
+    // 同時押しを離す途中で単音が鳴らないよう、全て離すまで入力を無視する
+    waiting_release: bool,
 }
 
 impl ToyPiano {
@@ -13,6 +46,11 @@ impl ToyPiano {
         ToyPiano {
             finished: false,
             previous_key_status: 0,
+            stable_frames: 0,
+            settled_key_status: 0,
+            tune_index: 0,
+            playing_tune: false,
+            waiting_release: false,
         }
     }
 }
@@ -22,9 +60,15 @@ impl AppFramework for ToyPiano {
         "Toy piano"
     }
 
-    fn initialize(&mut self, _context: &AppContext) -> anyhow::Result<()> {
+    fn initialize(&mut self, context: &AppContext) -> anyhow::Result<()> {
+        // ピアノらしく減衰する音にする
+        context.buzzer.lock().unwrap().set_instrument(Instrument::pluck())?;
         self.finished = false;
         self.previous_key_status = 0;
+        self.stable_frames = 0;
+        self.settled_key_status = 0;
+        self.playing_tune = false;
+        self.waiting_release = false;
         Ok(())
     }
 
@@ -38,46 +82,81 @@ impl AppFramework for ToyPiano {
 
         // TODO: モード選択を実装して ToyPiano モードで↓が実行されるようにする
 
+        // ボリュームでオクターブを切り替える (O4 ~ O7)
         let octave =
-            if      raw_value < 1000 { 0.5 }
-            else if raw_value < 2000 { 1.0 }
-            else if raw_value < 3000 { 2.0 }
-            else                     { 4.0 };
-        
+            if      raw_value < 1000 { 4 }
+            else if raw_value < 2000 { 5 }
+            else if raw_value < 3000 { 6 }
+            else                     { 7 };
+
         if self.previous_key_status != key_status {
+            self.stable_frames = 0;
+        } else {
+            self.stable_frames = self.stable_frames.saturating_add(1);
+        }
+        // 離した時はすぐに止め、押した時は組み合わせが落ち着くまで待つ
+        let settled = key_status == 0 || self.stable_frames >= CHORD_SETTLE_FRAMES;
+
+        if settled && self.settled_key_status != key_status {
+            self.settled_key_status = key_status;
             if key_status == 0 {
                 // 全てのボタンを離した
-                context.buzzer.lock().unwrap().stop_tone()?;
-            } else {
+                self.waiting_release = false;
+                if !self.playing_tune {
+                    context.buzzer.lock().unwrap().stop_tone()?;
+                }
+            } else if key_status == Button::UP | Button::DOWN {
+                // ↑↓同時押しでサンプル曲 (RTTTL → MIDI の順) を順番に演奏する
+                let (name, score) = if self.tune_index < melodies::SAMPLE_TUNES.len() {
+                    let ringtone = rtttl::parse(melodies::SAMPLE_TUNES[self.tune_index], RtttlMode::Strict)?;
+                    (ringtone.name, ringtone.score)
+                } else {
+                    let (name, score) = midi_tunes::MIDI_TUNES[self.tune_index - melodies::SAMPLE_TUNES.len()];
+                    (name.to_string(), score())
+                };
+                log::info!("[piano] play: {}", name);
+                context.buzzer.lock().unwrap().play(score)?;
+                self.tune_index = (self.tune_index + 1) % (melodies::SAMPLE_TUNES.len() + midi_tunes::MIDI_TUNES.len());
+                self.playing_tune = true;
+                self.waiting_release = true;
+            } else if !self.waiting_release {
                 // ボタンが 6 個しかないのでシを AB 同時押しで表現する
-                let frequency_base = match key_status {
-                    // 同時押しなので優先的に判定
-                    _ if key_status == Button::B | Button::A => Some(988),  // B
-                    // 以降は単押し判定
-                    _ if key_status == Button::UP    => Some(523),  // C
-                    _ if key_status == Button::LEFT  => Some(587),  // D
-                    _ if key_status == Button::DOWN  => Some(659),  // E
-                    _ if key_status == Button::RIGHT => Some(698),  // F
-                    _ if key_status == Button::B     => Some(783),  // G
-                    _ if key_status == Button::A     => Some(880),  // A
-                    _ => None,
+                let pitch_classes: Vec<PitchClass> = if key_status == Button::B | Button::A {
+                    vec![PitchClass::B]
+                } else {
+                    // 複数押した場合は押した音の和音にする
+                    NOTE_KEYS.iter()
+                        .filter(|(button, _)| key_status & button != 0)
+                        .map(|(_, pitch_class)| *pitch_class)
+                        .collect()
                 };
-                if let Some(value) = frequency_base {
-                    let frequency = (value as f32 * octave) as u32;
-                    context.buzzer.lock().unwrap().start_tone(frequency)?;
+                let notes = pitch_classes.iter()
+                    .map(|pitch_class| Note::new(*pitch_class, octave))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if !notes.is_empty() {
+                    self.playing_tune = false;
+                    let mut buzzer = context.buzzer.lock().unwrap();
+                    if notes.len() == 1 {
+                        buzzer.start_note(notes[0])?;
+                    } else {
+                        buzzer.start_chord(&notes, ARPEGGIO_STEP_MS)?;
+                    }
                 }
             }
         }
 
-        let format = format!("{:4}", raw_value);
-        context.led.lock().unwrap().write_format(&format);
+        context.led.lock().unwrap().write_integer(raw_value as i32, Padding::Space);
 
         self.previous_key_status = key_status;
         Ok(())
     }
 
     fn finalize(&mut self, context: &AppContext) -> anyhow::Result<()> {
-        context.buzzer.lock().unwrap().stop_tone()?;
+        {
+            let mut locked = context.buzzer.lock().unwrap();
+            locked.stop_tone()?;
+            locked.set_instrument(Instrument::new())?;
+        }
         context.led.lock().unwrap().clear();
         Ok(())
     }
@@ -0,0 +1,280 @@
+// 日本語 (かな・漢字) 向けの等幅ビットマップフォント (ハードウェア非依存)
+//
+// - BDF 形式のフォントを読み込み、使う文字だけに絞り込んで (サブセット化) Rust のソースとして埋め込む
+// - グリフは 1 ピクセル 1 ビットで隙間なく詰め、コードポイント順に並べて二分探索で引く
+//   (8x8 なら 1 文字 8 バイト、12x12 なら 18 バイト)
+// - 変換は build.rs で行う (to_rust_source)
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct BitmapFont {
+    // 1 文字の大きさ (文字間隔を含む)
+    pub width: u32,
+    pub height: u32,
+    // 昇順
+    pub codepoints: &'static [u32],
+    // codepoints と同じ順に bytes_per_glyph() バイトずつ
+    // 各グリフは左上から行優先、1 バイトの上位ビットが左
+    pub bitmaps: &'static [u8],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Glyph<'a> {
+    width: u32,
+    height: u32,
+    bits: &'a [u8],
+}
+
+impl Glyph<'_> {
+    pub fn pixel(&self, x: u32, y: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let index = y * self.width + x;
+        self.bits[(index / 8) as usize] & (0x80 >> (index % 8)) != 0
+    }
+}
+
+impl BitmapFont {
+    pub const fn bytes_per_glyph(&self) -> usize {
+        glyph_bytes(self.width, self.height)
+    }
+
+    // 収録文字数
+    pub fn len(&self) -> usize {
+        self.codepoints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codepoints.is_empty()
+    }
+
+    pub fn glyph(&self, ch: char) -> Option<Glyph<'_>> {
+        let index = self.codepoints.binary_search(&(ch as u32)).ok()?;
+        let size = self.bytes_per_glyph();
+        Some(Glyph {
+            width: self.width,
+            height: self.height,
+            bits: &self.bitmaps[index * size..(index + 1) * size],
+        })
+    }
+
+    pub fn contains(&self, ch: char) -> bool {
+        self.glyph(ch).is_some()
+    }
+}
+
+const fn glyph_bytes(width: u32, height: u32) -> usize {
+    (width * height).div_ceil(8) as usize
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BdfError {
+    // FONTBOUNDINGBOX がない
+    MissingBoundingBox,
+    // 解釈できない行 (行番号は 1 始まり)
+    InvalidLine(usize),
+}
+
+impl fmt::Display for BdfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BdfError::MissingBoundingBox => write!(f, "FONTBOUNDINGBOX not found"),
+            BdfError::InvalidLine(line) => write!(f, "line {}: invalid BDF", line),
+        }
+    }
+}
+
+impl std::error::Error for BdfError {}
+
+// BDF を読み込んだ結果 (文字コードの解釈は呼び出し側で行う)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BdfFont {
+    // CHARSET_REGISTRY ("ISO10646", "JISX0208.1990" など、なければ空)
+    pub charset: String,
+    pub width: u32,
+    pub height: u32,
+    // (ENCODING の値, BitmapFont と同じ形式のビットマップ)
+    pub glyphs: Vec<(u32, Vec<u8>)>,
+}
+
+fn parse_numbers<const N: usize>(args: &[&str], line: usize) -> Result<[i32; N], BdfError> {
+    let mut numbers = [0; N];
+    if args.len() < N {
+        return Err(BdfError::InvalidLine(line));
+    }
+    for (number, arg) in numbers.iter_mut().zip(args) {
+        *number = arg.parse().map_err(|_| BdfError::InvalidLine(line))?;
+    }
+    Ok(numbers)
+}
+
+pub fn parse_bdf(source: &str) -> Result<BdfFont, BdfError> {
+    let mut charset = String::new();
+    // 幅, 高さ, x オフセット, y オフセット
+    let mut bounding_box: Option<[i32; 4]> = None;
+    let mut glyphs = Vec::new();
+
+    let mut encoding: Option<u32> = None;
+    let mut glyph_box = [0; 4];
+    let mut bitmap: Option<Vec<u8>> = None;
+    let mut row = 0;
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&keyword, args)) = words.split_first() else {
+            continue;
+        };
+
+        if keyword == "ENDCHAR" {
+            if let (Some(encoding), Some(bits)) = (encoding.take(), bitmap.take()) {
+                glyphs.push((encoding, bits));
+            }
+            continue;
+        }
+        if let Some(bits) = bitmap.as_mut() {
+            // 1 行分のビット (左詰め、バイト単位)
+            let [width, height, x_offset, y_offset] = glyph_box;
+            let [cell_width, cell_height, cell_x_offset, cell_y_offset] = bounding_box.unwrap();
+            let value = u64::from_str_radix(keyword, 16).map_err(|_| BdfError::InvalidLine(line_number))?;
+            let row_bits = keyword.len() as u32 * 4;
+            let y = (cell_height + cell_y_offset) - (height + y_offset) + row;
+            for column in 0..width {
+                if column as u32 >= row_bits || value & (1 << (row_bits - 1 - column as u32)) == 0 {
+                    continue;
+                }
+                let x = x_offset - cell_x_offset + column;
+                if x < 0 || y < 0 || x >= cell_width || y >= cell_height {
+                    continue;
+                }
+                let index = (y * cell_width + x) as usize;
+                bits[index / 8] |= 0x80 >> (index % 8);
+            }
+            row += 1;
+            continue;
+        }
+
+        match keyword {
+            "FONTBOUNDINGBOX" => bounding_box = Some(parse_numbers::<4>(args, line_number)?),
+            "CHARSET_REGISTRY" => charset = args.join(" ").trim_matches('"').to_string(),
+            // -1 は文字コードなし
+            "ENCODING" => encoding = u32::try_from(parse_numbers::<1>(args, line_number)?[0]).ok(),
+            "BBX" => glyph_box = parse_numbers::<4>(args, line_number)?,
+            "BITMAP" => {
+                let [width, height, ..] = bounding_box.ok_or(BdfError::MissingBoundingBox)?;
+                bitmap = Some(vec![0; glyph_bytes(width as u32, height as u32)]);
+                row = 0;
+            },
+            _ => (),
+        }
+    }
+
+    let [width, height, ..] = bounding_box.ok_or(BdfError::MissingBoundingBox)?;
+    Ok(BdfFont { charset, width: width as u32, height: height as u32, glyphs })
+}
+
+// ソース中の文字列リテラルに含まれる ASCII 以外の文字
+// (コメントや文字リテラルは対象外、エスケープは展開しない)
+pub fn string_literal_chars(source: &str) -> BTreeSet<char> {
+    let mut result = BTreeSet::new();
+    let chars: Vec<char> = source.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            },
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                let mut depth = 0;
+                while i < chars.len() {
+                    if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                        depth += 1;
+                        i += 1;
+                    } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                        depth -= 1;
+                        i += 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    i += 1;
+                }
+            },
+            // 文字リテラル ('"' や '\'' を文字列の開始と誤認しないため) とライフタイム
+            '\'' => {
+                if chars.get(i + 1) == Some(&'\\') {
+                    i += 2;
+                    while i < chars.len() && chars[i] != '\'' {
+                        i += 1;
+                    }
+                } else if chars.get(i + 2) == Some(&'\'') {
+                    i += 2;
+                }
+            },
+            // 生文字列 r"..." / r#"..."#
+            'r' if matches!(chars.get(i + 1), Some('"') | Some('#')) && (i == 0 || !chars[i - 1].is_alphanumeric()) => {
+                let mut hashes = 0;
+                let mut j = i + 1;
+                while chars.get(j) == Some(&'#') {
+                    hashes += 1;
+                    j += 1;
+                }
+                if chars.get(j) == Some(&'"') {
+                    j += 1;
+                    while j < chars.len() {
+                        if chars[j] == '"' && (1..=hashes).all(|k| chars.get(j + k) == Some(&'#')) {
+                            j += hashes;
+                            break;
+                        }
+                        if !chars[j].is_ascii() {
+                            result.insert(chars[j]);
+                        }
+                        j += 1;
+                    }
+                    i = j;
+                }
+            },
+            '"' => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    } else if !chars[i].is_ascii() {
+                        result.insert(chars[i]);
+                    }
+                    i += 1;
+                }
+            },
+            _ => (),
+        }
+        i += 1;
+    }
+    result
+}
+
+// build.rs で生成するソース
+// glyphs は (文字, ビットマップ) をコードポイント順に並べたもの
+pub fn to_rust_source(static_name: &str, width: u32, height: u32, glyphs: &[(char, Vec<u8>)]) -> String {
+    let mut source = String::new();
+    source.push_str(&format!("pub static {}: crate::bitmap_font::BitmapFont = crate::bitmap_font::BitmapFont {{\n", static_name));
+    source.push_str(&format!("    width: {},\n", width));
+    source.push_str(&format!("    height: {},\n", height));
+    source.push_str("    codepoints: &[\n");
+    for (ch, _) in glyphs.iter() {
+        source.push_str(&format!("        0x{:04X}, // {}\n", *ch as u32, ch.escape_debug()));
+    }
+    source.push_str("    ],\n");
+    source.push_str("    bitmaps: &[\n");
+    for (ch, bitmap) in glyphs.iter() {
+        let bytes: Vec<String> = bitmap.iter().map(|byte| format!("0x{:02X}", byte)).collect();
+        source.push_str(&format!("        {}, // {}\n", bytes.join(", "), ch.escape_debug()));
+    }
+    source.push_str("    ],\n");
+    source.push_str("};\n");
+    source
+}
@@ -0,0 +1,12 @@
+// ボタンのビット配置 (KeyMatrix が返す値、ハードウェア非依存)
+
+pub struct Button;
+impl Button {
+    pub const UP:    u8 = 0x01;
+    pub const LEFT:  u8 = 0x02;
+    pub const DOWN:  u8 = 0x04;
+    pub const RIGHT: u8 = 0x08;
+    pub const A:     u8 = 0x10;
+    pub const B:     u8 = 0x20;
+    pub const MASK:  u8 = 0x3F; // マスク操作用
+}
@@ -1,70 +1,569 @@
 use esp_idf_hal::prelude::*;
+use esp_idf_hal::delay::Ets;
 use esp_idf_hal::gpio::Gpio4;
 use esp_idf_hal::ledc::*;
 use esp_idf_hal::ledc::config::TimerConfig;
+use esp_idf_svc::nvs::{EspNvs, NvsDefault};
 
+use std::collections::VecDeque;
+use std::fmt;
 use std::sync::mpsc;
-use std::sync::mpsc::SendError;
+use std::sync::mpsc::{RecvTimeoutError, SendError, TrySendError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use rustorch::dnd::{DndSettings, QuietHours, CLOCK_VALID_AFTER_UNIX_S};
+use rustorch::note::Note;
+use rustorch::score::{PlayerStatus, Score, ScoreEvent};
+use rustorch::sound_effects::SoundEffect;
+use rustorch::tone::{output_duty_permille, Instrument, MasterVolume, ENVELOPE_STEP_US};
+use rustorch::voice::{Mixer, VoicePriority};
+
+// LEDC タイマの設定 (start_thread() の TimerConfig と揃えること)
+// クロック源は既定の APB (80MHz)、分周比は 10.8 固定小数点で 1024 未満まで
+const LEDC_CLOCK_HZ: u32 = 80_000_000;
+const LEDC_RESOLUTION: Resolution = Resolution::Bits10;
+const LEDC_RESOLUTION_BITS: u32 = 10;
+const LEDC_MAX_DIVIDER: u32 = 1024;
+
+// LEDC タイマで出力できる周波数の範囲 [Hz]
+// - 上限: 分周しない場合 (80MHz / 2^10 = 78125Hz)
+// - 下限: 最大の分周比の場合 (80MHz / 1024 / 2^10 = 約 76.3Hz) を切り上げた値
+pub const MIN_FREQUENCY_HZ: u32 = LEDC_CLOCK_HZ / (LEDC_MAX_DIVIDER << LEDC_RESOLUTION_BITS) + 1;
+pub const MAX_FREQUENCY_HZ: u32 = LEDC_CLOCK_HZ >> LEDC_RESOLUTION_BITS;
+
+// コマンドキューの長さ
+const COMMAND_QUEUE_LENGTH: usize = 16;
+
+// おやすみモードの設定を保存する NVS のキー
+const NVS_KEY_DND: &str = "dnd";
+// 操作音の有効/無効を保存する NVS のキー
+const NVS_KEY_UI_SOUNDS: &str = "ui_sounds";
+// 未処理の視覚通知の上限 (超えたら古いものから捨てる)
+const MAX_PENDING_VISUAL_ALERTS: usize = 4;
+
+// 発音に関するコマンドは voice で指定したボイスに対して行う
+// (優先度の高いボイスが鳴っている間、低いボイスは中断され、鳴り終わると再開する)
 pub enum BuzzerCommand {
-    StartTone { frequency: u32 },
-    StopTone,
+    StartTone { voice: VoicePriority, frequency: u32 },
+    // 和音をアルペジオで鳴らす (構成音を step_us 間隔で切り替える)
+    StartChord { voice: VoicePriority, frequencies: Vec<u32>, step_us: i64 },
+    StopTone { voice: VoicePriority },
+    // 楽譜を非同期に演奏する (同じボイスで演奏中の楽譜や単音は中断される)
+    // instrument を指定するとこの楽譜だけその音色で鳴らす
+    Play { voice: VoicePriority, score: Score, instrument: Option<Instrument> },
+    // 演奏の中断 (None は全てのボイス)
+    Cancel { voice: Option<VoicePriority> },
+    // 演奏状態の問い合わせ (結果は reply に返す)
+    QueryStatus { voice: VoicePriority, reply: mpsc::Sender<PlayerStatus> },
+    // 以降に鳴らす音の音量・音色・エンベロープ
+    SetInstrument { instrument: Instrument },
+    // 全アプリ共通の音量とミュート (鳴っている音にも即座に反映する)
+    SetMasterVolume { master: MasterVolume },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuzzerError {
+    FrequencyOutOfRange(u32),
+    // ブザースレッドが動いていない
+    Disconnected,
+}
+
+impl fmt::Display for BuzzerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuzzerError::FrequencyOutOfRange(frequency) =>
+                write!(f, "frequency {} Hz is out of range ({} ~ {} Hz)", frequency, MIN_FREQUENCY_HZ, MAX_FREQUENCY_HZ),
+            BuzzerError::Disconnected => write!(f, "buzzer thread is not running"),
+        }
+    }
+}
+
+impl std::error::Error for BuzzerError {}
+
+impl From<SendError<BuzzerCommand>> for BuzzerError {
+    fn from(_: SendError<BuzzerCommand>) -> Self {
+        BuzzerError::Disconnected
+    }
+}
+
+fn check_frequency(frequency: u32) -> Result<(), BuzzerError> {
+    if !(MIN_FREQUENCY_HZ..=MAX_FREQUENCY_HZ).contains(&frequency) {
+        return Err(BuzzerError::FrequencyOutOfRange(frequency));
+    }
+    Ok(())
+}
 
-    // TODO:
-    //Play { score? },
-    //Cancel,
-    //QueryStatus,
+// いずれかの時刻のうち最も早いもの
+fn earliest(deadlines: &[Option<i64>]) -> Option<i64> {
+    deadlines.iter().flatten().min().copied()
 }
 
 pub struct BuzzerDriver {
     sender: Option<mpsc::SyncSender<BuzzerCommand>>,
+    master: MasterVolume,
+    ui_sounds_enabled: bool,
+    dnd: DndSettings,
+    // 前回 update_dnd() した時点でおやすみモードが有効だったか
+    dnd_was_active: bool,
+    // おやすみモードの設定の保存先
+    storage: Option<EspNvs<NvsDefault>>,
+    // おやすみモード中に鳴らさなかった通知音 (LED と OLED で代わりに知らせる)
+    visual_alerts: VecDeque<SoundEffect>,
+}
+
+fn now_us() -> i64 {
+    unsafe { esp_idf_sys::esp_timer_get_time() }
+}
+
+// 現在の UNIX 時刻 [s] (システム時刻が 1970 年より前なら None)
+fn unix_time_s() -> Option<i64> {
+    SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|duration| duration.as_secs() as i64)
+}
+
+// LEDC への出力 (周波数とデューティ比の変化分だけ設定する)
+// - 消音から発音に変わった時にエンベロープを最初からかける
+// - 発音中の周波数の変化 (アルペジオやビブラートなど) ではエンベロープを継続する
+struct BuzzerOutput<'d> {
+    timer: LedcTimerDriver<'d, TIMER0>,
+    driver: LedcDriver<'d>,
+    // 現在出力中の周波数 (None は消音)
+    frequency: Option<u32>,
+    // 発音開始時刻 (エンベロープの起点)
+    note_start_us: i64,
+    duty: u32,
+}
+
+impl BuzzerOutput<'_> {
+    fn set_frequency(&mut self, frequency: Option<u32>, now_us: i64) {
+        // ピッチエフェクトで範囲外になった周波数は出力できる範囲に丸める
+        let frequency = frequency.map(|frequency| frequency.clamp(MIN_FREQUENCY_HZ, MAX_FREQUENCY_HZ));
+        if frequency == self.frequency {
+            return;
+        }
+        // LEDC の設定に失敗した場合は警告を出して状態を変えずに戻る (ブザースレッドは止めず、次の呼び出しでやり直す)
+        if let Some(frequency) = frequency {
+            if let Err(e) = self.timer.set_frequency(frequency.Hz()) {
+                log::warn!("[buz] failed to set {} Hz: {}", frequency, e);
+                return;
+            }
+        }
+        match frequency {
+            Some(_) if self.frequency.is_some() => (),
+            Some(frequency) => {
+                if let Err(e) = self.timer.resume() {
+                    log::warn!("[buz] failed to start: {}", e);
+                    return;
+                }
+                self.note_start_us = now_us;
+                // デューティ比の設定が音出力のトリガーとなるので、発音開始時は必ず設定し直す
+                self.duty = u32::MAX;
+                log::debug!("[buz] start: {} Hz", frequency);
+            },
+            None => {
+                if let Err(e) = self.timer.pause() {
+                    log::warn!("[buz] failed to stop: {}", e);
+                    return;
+                }
+                log::debug!("[buz] stop");
+            },
+        }
+        self.frequency = frequency;
+    }
+
+    // 発音中ならエンベロープと音量に応じたデューティ比を設定する
+    fn update_duty(&mut self, instrument: &Instrument, master: &MasterVolume, now_us: i64) {
+        if self.frequency.is_none() {
+            return;
+        }
+        let permille = output_duty_permille(instrument, master, now_us - self.note_start_us);
+        let duty = self.driver.get_max_duty() * permille / 1000;
+        if duty != self.duty {
+            // 失敗した場合は次の呼び出しでやり直す
+            match self.driver.set_duty(duty) {
+                Ok(()) => self.duty = duty,
+                Err(e) => log::warn!("[buz] failed to set duty {}: {}", duty, e),
+            }
+        }
+    }
+
+    // エンベロープの変化中なら次にデューティ比を更新する時刻
+    fn next_envelope_deadline_us(&self, instrument: &Instrument, now_us: i64) -> Option<i64> {
+        if self.frequency.is_some() && now_us - self.note_start_us < instrument.envelope.settle_us() {
+            Some(now_us + ENVELOPE_STEP_US)
+        } else {
+            None
+        }
+    }
 }
 
 impl BuzzerDriver {
     pub fn new() -> Self {
         Self {
             sender: None,
+            master: MasterVolume::new(),
+            ui_sounds_enabled: true,
+            dnd: DndSettings::new(),
+            dnd_was_active: false,
+            storage: None,
+            visual_alerts: VecDeque::new(),
         }
     }
-    
+
     pub fn start_thread(&mut self, buzzer_pin: Gpio4, channel: CHANNEL0, timer: TIMER0) -> anyhow::Result<()> {
-        let timer_config = &TimerConfig::new().resolution(Resolution::Bits10).frequency(1.kHz().into());
-        let mut timer = LedcTimerDriver::new(timer, timer_config)?;
-        let mut driver = LedcDriver::new(
+        let timer_config = &TimerConfig::new().resolution(LEDC_RESOLUTION).frequency(1.kHz().into());
+        let timer = LedcTimerDriver::new(timer, timer_config)?;
+        let driver = LedcDriver::new(
             channel,
             &timer,
             buzzer_pin,
         )?;
-        
-        let (tx, rx) = mpsc::sync_channel::<BuzzerCommand>(5);
+        let mut output = BuzzerOutput { timer, driver, frequency: None, note_start_us: 0, duty: 0 };
+        let mut master = self.master;
+
+        let (tx, rx) = mpsc::sync_channel::<BuzzerCommand>(COMMAND_QUEUE_LENGTH);
         let _ = std::thread::spawn(move || {
-            for command in rx {
-                match command {
-                    BuzzerCommand::StartTone { frequency } => {
-                        let max_duty = driver.get_max_duty();
-                        timer.set_frequency(frequency.Hz()).unwrap();
-                        driver.set_duty(max_duty / 2).unwrap();    // これが音出力のトリガーとなる
-                        timer.resume().unwrap();
+            let mut mixer = Mixer::new();
 
-                        log::info!("[buz] start: {} Hz", frequency);
+            loop {
+                // 演奏中は次に音が切り替わる時刻、エンベロープや高さの変化中は次の更新時刻までコマンドを待つ
+                let now = now_us();
+                let deadline_us = earliest(&[
+                    mixer.next_deadline_us(now),
+                    output.next_envelope_deadline_us(&mixer.instrument(), now),
+                ]);
+                let command = match deadline_us {
+                    // 1 tick 未満の待ち時間はビジーウェイトで合わせる (アルペジオの切り替えを正確にするため)
+                    Some(deadline_us) if deadline_us - now < 1000 => {
+                        Ets::delay_us((deadline_us - now).max(0) as u32);
+                        match rx.try_recv() {
+                            Ok(command) => Some(command),
+                            Err(mpsc::TryRecvError::Empty) => None,
+                            Err(mpsc::TryRecvError::Disconnected) => break,
+                        }
+                    },
+                    // tick 単位で待ち、端数は次のループで待つ
+                    Some(deadline_us) => {
+                        let timeout_ms = ((deadline_us - now) / 1000) as u64;
+                        match rx.recv_timeout(Duration::from_millis(timeout_ms)) {
+                            Ok(command) => Some(command),
+                            Err(RecvTimeoutError::Timeout) => None,
+                            Err(RecvTimeoutError::Disconnected) => break,
+                        }
+                    },
+                    None => match rx.recv() {
+                        Ok(command) => Some(command),
+                        Err(_) => break,
                     },
-                    BuzzerCommand::StopTone => {
-                        timer.pause().unwrap();
+                };
 
-                        log::info!("[buz] stop");
+                match command {
+                    Some(BuzzerCommand::StartTone { voice, frequency }) => {
+                        mixer.start_tone(voice, vec![frequency], 0);
+                        log::info!("[buz] {}: start: {} Hz", voice.name(), frequency);
+                    },
+                    Some(BuzzerCommand::StartChord { voice, frequencies, step_us }) => {
+                        log::info!("[buz] {}: chord: {:?} Hz, {} us", voice.name(), frequencies, step_us);
+                        mixer.start_tone(voice, frequencies, step_us);
+                    },
+                    Some(BuzzerCommand::StopTone { voice }) => {
+                        mixer.stop(voice);
+                        log::info!("[buz] {}: stop", voice.name());
+                    },
+                    Some(BuzzerCommand::Play { voice, score, instrument }) => {
+                        log::info!("[buz] {}: play: {} events, {} bpm", voice.name(), score.events.len(), score.tempo_bpm);
+                        if let Err(e) = mixer.play(voice, score, instrument, now_us()) {
+                            log::warn!("[buz] invalid score: {}", e);
+                        }
                     },
+                    Some(BuzzerCommand::Cancel { voice }) => {
+                        match voice {
+                            Some(voice) => mixer.stop(voice),
+                            None => mixer.stop_all(),
+                        }
+                        log::info!("[buz] cancel: {}", voice.map_or("all", |voice| voice.name()));
+                    },
+                    Some(BuzzerCommand::QueryStatus { voice, reply }) => {
+                        let _ = reply.send(mixer.status(voice, now_us()));
+                    },
+                    Some(BuzzerCommand::SetInstrument { instrument }) => {
+                        mixer.set_instrument(instrument);
+                        log::info!("[buz] instrument: {:?}", instrument);
+                    },
+                    Some(BuzzerCommand::SetMasterVolume { master: next }) => {
+                        master = next;
+                        log::info!("[buz] master volume: {}% (muted: {})", master.volume_percent, master.muted);
+                    },
+                    None => (),
                 }
+
+                let now = now_us();
+                let current = mixer.current();
+                let mixed = mixer.update(now);
+                if mixer.current() != current {
+                    log::info!("[buz] voice: {} -> {}",
+                        current.map_or("none", |voice| voice.name()), mixer.current().map_or("none", |voice| voice.name()));
+                }
+                // 新しい音は (同じ高さでも) エンベロープを最初からかける
+                if mixed.note_started {
+                    output.set_frequency(None, now);
+                }
+                output.set_frequency(mixed.frequency, now);
+                output.update_duty(&mixer.instrument(), &master, now);
             }
         });
         self.sender = Some(tx);
         Ok(())
     }
-    
-    pub fn start_tone(&mut self, frequency: u32) -> Result<(), SendError<BuzzerCommand>> {
-        self.sender.as_mut().unwrap().send(BuzzerCommand::StartTone { frequency })
+
+    // コマンドをブザースレッドに送る
+    // キューが一杯の場合の扱いは voice の優先度による
+    // - 通知 (Alert): 取りこぼすと気付けないので空くまで待つ (ブロックする)
+    // - アプリの音と操作音: 待たずに捨て、警告を出して Ok を返す (音が欠けるだけでアプリは止めない)
+    // おやすみモード中 (手動または静音時間内) は送らずに捨てる
+    fn send(&mut self, voice: VoicePriority, command: BuzzerCommand) -> Result<(), BuzzerError> {
+        if self.is_dnd_active() {
+            log::debug!("[buz] do not disturb, suppressed a {} command", voice.name());
+            return Ok(());
+        }
+        let sender = self.sender.as_mut().ok_or(BuzzerError::Disconnected)?;
+        if voice.waits_on_overflow() {
+            return Ok(sender.send(command)?);
+        }
+        match sender.try_send(command) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                log::warn!("[buz] command queue is full, dropped a {} command", voice.name());
+                Ok(())
+            },
+            Err(TrySendError::Disconnected(_)) => Err(BuzzerError::Disconnected),
+        }
+    }
+
+    // 停止や設定のコマンドは取りこぼすと状態がずれるので、キューが空くまで待つ (ブロックする)
+    // stop_tone() / stop_voice() / cancel() / set_instrument() / set_master_volume() / set_mute() / query_status() が該当
+    // (キューが一杯になるのはブザースレッドが詰まっている時だけなので、通常は待たない)
+    fn send_control(&mut self, command: BuzzerCommand) -> Result<(), BuzzerError> {
+        let sender = self.sender.as_mut().ok_or(BuzzerError::Disconnected)?;
+        Ok(sender.send(command)?)
+    }
+
+    // アプリの音 (Music ボイス) として単音を鳴らす
+    // 出力できない周波数はエラー
+    pub fn start_tone(&mut self, frequency: u32) -> Result<(), BuzzerError> {
+        check_frequency(frequency)?;
+        self.send(VoicePriority::Music, BuzzerCommand::StartTone { voice: VoicePriority::Music, frequency })
+    }
+
+    pub fn start_note(&mut self, note: Note) -> Result<(), BuzzerError> {
+        self.start_tone(note.frequency())
+    }
+
+    // 和音を step_ms 間隔のアルペジオで鳴らす (音を 1 つしか出せないブザーでの擬似和音)
+    // 空の和音は消音
+    pub fn start_chord(&mut self, notes: &[Note], step_ms: u32) -> Result<(), BuzzerError> {
+        if notes.is_empty() {
+            return self.stop_tone();
+        }
+        let frequencies = notes.iter().map(|note| note.frequency()).collect::<Vec<_>>();
+        for frequency in frequencies.iter() {
+            check_frequency(*frequency)?;
+        }
+        let step_us = step_ms.max(1) as i64 * 1000;
+        self.send(VoicePriority::Music, BuzzerCommand::StartChord { voice: VoicePriority::Music, frequencies, step_us })
+    }
+
+    pub fn stop_tone(&mut self) -> Result<(), BuzzerError> {
+        self.send_control(BuzzerCommand::StopTone { voice: VoicePriority::Music })
+    }
+
+    // アプリの音 (Music ボイス) として楽譜の演奏を開始する (演奏の完了は待たない)
+    pub fn play(&mut self, score: Score) -> Result<(), BuzzerError> {
+        self.play_on(VoicePriority::Music, score, None)
+    }
+
+    // 指定したボイスで楽譜の演奏を開始する
+    // 出力できない周波数の音符を含む楽譜はエラー
+    pub fn play_on(&mut self, voice: VoicePriority, score: Score, instrument: Option<Instrument>) -> Result<(), BuzzerError> {
+        for event in score.events.iter() {
+            if let ScoreEvent::Note { frequency, .. } = event {
+                check_frequency(*frequency)?;
+            }
+        }
+        self.send(voice, BuzzerCommand::Play { voice, score, instrument })
+    }
+
+    // 効果音をその種類に応じたボイスで鳴らす
+    // UI 音が無効の場合、操作音は鳴らさない
+    // おやすみモード中は鳴らさず、通知音は視覚通知 (take_visual_alert()) に置き換える
+    pub fn play_effect(&mut self, effect: SoundEffect) -> Result<(), BuzzerError> {
+        if effect.is_ui_feedback() && !self.ui_sounds_enabled {
+            return Ok(());
+        }
+        if self.is_dnd_active() {
+            if effect.voice() == VoicePriority::Alert {
+                if self.visual_alerts.len() >= MAX_PENDING_VISUAL_ALERTS {
+                    self.visual_alerts.pop_front();
+                }
+                self.visual_alerts.push_back(effect);
+                log::info!("[buz] do not disturb, {} -> visual alert", effect.name());
+            }
+            return Ok(());
+        }
+        self.play_on(effect.voice(), effect.score(), Some(effect.instrument()))
+    }
+
+    // 名前で効果音を鳴らす (未知の名前は警告を出して無視する)
+    pub fn play_effect_by_name(&mut self, name: &str) -> Result<(), BuzzerError> {
+        match SoundEffect::from_name(name) {
+            Some(effect) => self.play_effect(effect),
+            None => {
+                log::warn!("[buz] unknown sound effect: {}", name);
+                Ok(())
+            },
+        }
+    }
+
+    // 操作音 (クリック音など) の有効/無効 (attach_storage() 後は NVS にも保存する)
+    pub fn set_ui_sounds_enabled(&mut self, enabled: bool) -> anyhow::Result<()> {
+        self.ui_sounds_enabled = enabled;
+        log::info!("[buz] ui sounds: {}", enabled);
+        if let Some(storage) = self.storage.as_mut() {
+            storage.set_u8(NVS_KEY_UI_SOUNDS, enabled as u8)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_ui_sounds_enabled(&self) -> bool {
+        self.ui_sounds_enabled
+    }
+
+    // 指定したボイスの演奏を中断する (中断していた低い優先度のボイスが再開する)
+    pub fn stop_voice(&mut self, voice: VoicePriority) -> Result<(), BuzzerError> {
+        self.send_control(BuzzerCommand::Cancel { voice: Some(voice) })
+    }
+
+    // 全てのボイスの演奏を中断する
+    pub fn cancel(&mut self) -> Result<(), BuzzerError> {
+        self.send_control(BuzzerCommand::Cancel { voice: None })
+    }
+
+    // アプリの音 (Music ボイス) が演奏中か、どこまで演奏したかを取得する
+    pub fn query_status(&mut self) -> Result<PlayerStatus, BuzzerError> {
+        let (reply, receiver) = mpsc::channel();
+        self.send_control(BuzzerCommand::QueryStatus { voice: VoicePriority::Music, reply })?;
+        receiver.recv().map_err(|_| BuzzerError::Disconnected)
+    }
+
+    // 以降の単音・楽譜の音量と音色を設定する
+    pub fn set_instrument(&mut self, instrument: Instrument) -> Result<(), BuzzerError> {
+        self.send_control(BuzzerCommand::SetInstrument { instrument })
+    }
+
+    // 全アプリ共通の音量 (0 ~ 100%)
+    pub fn set_master_volume(&mut self, percent: u8) -> Result<(), BuzzerError> {
+        self.master.volume_percent = percent.min(100);
+        self.send_control(BuzzerCommand::SetMasterVolume { master: self.master })
+    }
+
+    pub fn set_mute(&mut self, muted: bool) -> Result<(), BuzzerError> {
+        self.master.muted = muted;
+        self.send_control(BuzzerCommand::SetMasterVolume { master: self.master })
+    }
+
+    pub fn get_master_volume(&self) -> MasterVolume {
+        self.master
+    }
+
+    // おやすみモードと操作音の設定を NVS から読み込み、以降の変更を保存する
+    // 保存されていない、または読めない場合は現在の設定のまま
+    pub fn attach_storage(&mut self, storage: EspNvs<NvsDefault>) -> anyhow::Result<()> {
+        if let Some(enabled) = storage.get_u8(NVS_KEY_UI_SOUNDS)? {
+            self.ui_sounds_enabled = enabled != 0;
+            log::info!("[buz] ui sounds: {}", self.ui_sounds_enabled);
+        }
+        let mut buf = [0u8; 16];
+        match storage.get_blob(NVS_KEY_DND, &mut buf)? {
+            Some(bytes) => match DndSettings::from_bytes(bytes) {
+                Ok(settings) => {
+                    self.dnd = settings;
+                    log::info!("[buz] do not disturb: {:?}", self.dnd);
+                },
+                Err(e) => log::warn!("[buz] ignored saved do not disturb settings: {}", e),
+            },
+            None => log::info!("[buz] do not disturb settings are not saved yet"),
+        }
+        self.storage = Some(storage);
+        self.update_dnd()?;
+        Ok(())
+    }
+
+    fn save_dnd(&mut self) -> anyhow::Result<()> {
+        if let Some(storage) = self.storage.as_mut() {
+            storage.set_blob(NVS_KEY_DND, &self.dnd.to_bytes())?;
+        }
+        self.update_dnd()?;
+        Ok(())
+    }
+
+    // おやすみモードを手動で有効/無効にする
+    pub fn set_dnd(&mut self, enable: bool) -> anyhow::Result<()> {
+        self.dnd = self.dnd.manual(enable);
+        log::info!("[buz] do not disturb (manual): {}", enable);
+        self.save_dnd()
+    }
+
+    // 毎日おやすみモードにする時間帯 (None はスケジュールなし)
+    pub fn set_quiet_hours(&mut self, quiet_hours: Option<QuietHours>) -> anyhow::Result<()> {
+        self.dnd = self.dnd.quiet_hours(quiet_hours);
+        match quiet_hours {
+            Some(quiet_hours) => log::info!("[buz] quiet hours: {}", quiet_hours),
+            None => log::info!("[buz] quiet hours: none"),
+        }
+        self.save_dnd()
+    }
+
+    pub fn get_dnd_settings(&self) -> DndSettings {
+        self.dnd
+    }
+
+    // 静音時間の判定に使うシステム時刻を現地時刻 minute_of_day (0:00 からの分数) に合わせる
+    // RTC のバックアップがないので電源を切ると未設定に戻る (その間スケジュールは無視される)
+    pub fn set_local_time(&mut self, minute_of_day: u16) -> anyhow::Result<()> {
+        let unix_time_s = self.dnd.unix_time_at(minute_of_day)?;
+        let time = esp_idf_sys::timeval { tv_sec: unix_time_s as _, tv_usec: 0 };
+        if unsafe { esp_idf_sys::settimeofday(&time, std::ptr::null()) } != 0 {
+            anyhow::bail!("settimeofday failed");
+        }
+        log::info!("[buz] clock: {}:{:02}", minute_of_day / 60, minute_of_day % 60);
+        self.update_dnd()?;
+        Ok(())
+    }
+
+    // 現地時刻の 0:00 からの分数 (時計が未設定なら None)
+    pub fn local_minute_of_day(&self) -> Option<u16> {
+        unix_time_s()
+            .filter(|&unix_time_s| unix_time_s >= CLOCK_VALID_AFTER_UNIX_S)
+            .map(|unix_time_s| self.dnd.local_minute_of_day(unix_time_s))
+    }
+
+    pub fn is_dnd_active(&self) -> bool {
+        self.dnd.is_active(unix_time_s())
+    }
+
+    // おやすみモードに入ったら鳴っている音を全て止める (静音時間の開始に気付けるよう定期的に呼び出すこと)
+    // 戻り値は現在おやすみモードが有効か
+    pub fn update_dnd(&mut self) -> Result<bool, BuzzerError> {
+        let active = self.is_dnd_active();
+        if active && !self.dnd_was_active && self.sender.is_some() {
+            log::info!("[buz] do not disturb started");
+            self.cancel()?;
+        }
+        self.dnd_was_active = active;
+        Ok(active)
     }
 
-    pub fn stop_tone(&mut self) -> Result<(), SendError<BuzzerCommand>> {
-        self.sender.as_mut().unwrap().send(BuzzerCommand::StopTone)
+    // おやすみモード中に鳴らさなかった通知を古い順に取り出す
+    pub fn take_visual_alert(&mut self) -> Option<SoundEffect> {
+        self.visual_alerts.pop_front()
     }
 }
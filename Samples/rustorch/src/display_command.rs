@@ -0,0 +1,346 @@
+// 表示スレッドへの描画コマンドと、その FrameBuffer への描画 (ハードウェア非依存)
+
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use embedded_graphics::{
+    image::Image,
+    mono_font::{ascii::FONT_6X10, MonoTextStyle},
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{Circle, CornerRadii, Line, Polyline, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle, RoundedRectangle},
+    text::{Baseline, Text},
+};
+use tinybmp::Bmp;
+
+use crate::frame_buffer::FrameBuffer;
+use crate::grayscale::{GrayscaleImage, GrayscaleMethod};
+use crate::image_asset::{ImageAsset, ImageAssetError};
+use crate::sprite::{Animator, Flip, Sprite, SpriteError, SpriteId, SpriteSheet};
+use crate::text::{self, TextStyle};
+
+pub enum DisplayCommand {
+    Clear,
+    DrawImage { image: &'static [u8], point: Point },
+    // build.rs で変換した画像 (images::<名前>)
+    DrawAsset { asset: ImageAsset, point: Point },
+    DrawText { text: String, point: Point },
+    // area 内に style に従って配置する (折り返し、寄せ、はみ出しの省略)
+    DrawTextBox { text: String, area: Rectangle, style: TextStyle },
+    DrawPixel { point: Point, color: BinaryColor },
+    DrawLine { start: Point, end: Point, style: PrimitiveStyle<BinaryColor> },
+    DrawRectangle { area: Rectangle, style: PrimitiveStyle<BinaryColor> },
+    DrawRoundedRectangle { area: Rectangle, corner_radius: u32, style: PrimitiveStyle<BinaryColor> },
+    // top_left は外接する正方形の左上
+    DrawCircle { top_left: Point, diameter: u32, style: PrimitiveStyle<BinaryColor> },
+    // 折れ線 (線のみ、塗りつぶしは無視される)
+    DrawPolyline { points: Vec<Point>, style: PrimitiveStyle<BinaryColor> },
+    // 領域内の点灯/消灯を反転する (選択中の項目の強調など)
+    InvertRegion { area: Rectangle },
+    // 領域内を消灯する
+    ClearRegion { area: Rectangle },
+    // スプライトシートの 1 フレームを描く (動かさない場合)
+    DrawSprite { sheet: SpriteSheet, index: usize, point: Point, flip: Flip },
+    // 表示スレッドでアニメーションを始める (同じ id のものは置き換える)
+    // 以降はアプリが描き直さなくても、描いた画面の上にフレームを進めながら重ねて表示する
+    StartAnimation { id: SpriteId, sprite: Sprite },
+    StopAnimation { id: SpriteId },
+    // アニメーションを続けたまま位置と反転を変える
+    MoveSprite { id: SpriteId, point: Point, flip: Flip },
+    // 描いた画面の上に階調のある画像を重ね、サブフレームを切り替え続けて表示する (HideGrayscale まで)
+    // 表示中も描画やアニメーションのコマンドは受け付けるが、アニメーションのフレームは進まない
+    ShowGrayscale { image: GrayscaleImage, point: Point, method: GrayscaleMethod },
+    HideGrayscale,
+    Update,
+    // 描画コマンドの列をまとめて描いて画面を更新する (途中で失敗したら何も反映しない)
+    Frame(DisplayFrame),
+    // 表示中の画面をシリアルコンソールに出力する
+    Screenshot,
+    // 画面を更新する度に Screenshot と同じ出力をする
+    SetMirroring(bool),
+}
+
+// 1 画面分の描画コマンドの列
+// - DisplayDriver::submit() でまとめて送るので、途中まで描いた画面が表示されることはない
+// - 描き始めは直前に表示していた内容 (全体を描き直す場合は clear() から始める)
+#[derive(Default)]
+pub struct DisplayFrame {
+    commands: Vec<DisplayCommand>,
+}
+
+impl DisplayFrame {
+    pub fn new() -> Self {
+        DisplayFrame { commands: Vec::new() }
+    }
+
+    pub fn commands(&self) -> &[DisplayCommand] {
+        &self.commands
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn push(&mut self, command: DisplayCommand) -> &mut Self {
+        self.commands.push(command);
+        self
+    }
+
+    pub fn clear(&mut self) -> &mut Self {
+        self.push(DisplayCommand::Clear)
+    }
+
+    pub fn draw_image(&mut self, image: &'static [u8], point: Point) -> &mut Self {
+        self.push(DisplayCommand::DrawImage { image, point })
+    }
+
+    pub fn draw_asset(&mut self, asset: ImageAsset, point: Point) -> &mut Self {
+        self.push(DisplayCommand::DrawAsset { asset, point })
+    }
+
+    pub fn draw_text(&mut self, text: String, point: Point) -> &mut Self {
+        self.push(DisplayCommand::DrawText { text, point })
+    }
+
+    pub fn draw_text_box(&mut self, text: String, area: Rectangle, style: TextStyle) -> &mut Self {
+        self.push(DisplayCommand::DrawTextBox { text, area, style })
+    }
+
+    pub fn draw_pixel(&mut self, point: Point, color: BinaryColor) -> &mut Self {
+        self.push(DisplayCommand::DrawPixel { point, color })
+    }
+
+    pub fn draw_line(&mut self, start: Point, end: Point, style: PrimitiveStyle<BinaryColor>) -> &mut Self {
+        self.push(DisplayCommand::DrawLine { start, end, style })
+    }
+
+    pub fn draw_rectangle(&mut self, area: Rectangle, style: PrimitiveStyle<BinaryColor>) -> &mut Self {
+        self.push(DisplayCommand::DrawRectangle { area, style })
+    }
+
+    pub fn draw_rounded_rectangle(&mut self, area: Rectangle, corner_radius: u32, style: PrimitiveStyle<BinaryColor>) -> &mut Self {
+        self.push(DisplayCommand::DrawRoundedRectangle { area, corner_radius, style })
+    }
+
+    pub fn draw_circle(&mut self, top_left: Point, diameter: u32, style: PrimitiveStyle<BinaryColor>) -> &mut Self {
+        self.push(DisplayCommand::DrawCircle { top_left, diameter, style })
+    }
+
+    pub fn draw_polyline(&mut self, points: Vec<Point>, style: PrimitiveStyle<BinaryColor>) -> &mut Self {
+        self.push(DisplayCommand::DrawPolyline { points, style })
+    }
+
+    pub fn invert_region(&mut self, area: Rectangle) -> &mut Self {
+        self.push(DisplayCommand::InvertRegion { area })
+    }
+
+    pub fn clear_region(&mut self, area: Rectangle) -> &mut Self {
+        self.push(DisplayCommand::ClearRegion { area })
+    }
+
+    pub fn draw_sprite(&mut self, sheet: SpriteSheet, index: usize, point: Point, flip: Flip) -> &mut Self {
+        self.push(DisplayCommand::DrawSprite { sheet, index, point, flip })
+    }
+
+    pub fn start_animation(&mut self, id: SpriteId, sprite: Sprite) -> &mut Self {
+        self.push(DisplayCommand::StartAnimation { id, sprite })
+    }
+
+    pub fn stop_animation(&mut self, id: SpriteId) -> &mut Self {
+        self.push(DisplayCommand::StopAnimation { id })
+    }
+
+    pub fn move_sprite(&mut self, id: SpriteId, point: Point, flip: Flip) -> &mut Self {
+        self.push(DisplayCommand::MoveSprite { id, point, flip })
+    }
+
+    pub fn show_grayscale(&mut self, image: GrayscaleImage, point: Point, method: GrayscaleMethod) -> &mut Self {
+        self.push(DisplayCommand::ShowGrayscale { image, point, method })
+    }
+
+    pub fn hide_grayscale(&mut self) -> &mut Self {
+        self.push(DisplayCommand::HideGrayscale)
+    }
+}
+
+// 表示スレッドが処理中の時にフレームを送るかどうか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmitPolicy {
+    // 必ず送る (画面遷移など、取りこぼしたくない描画向け)
+    // 前のフレームの転送は待たずにキューに積み、キューが一杯の時だけ空くまで待つ
+    Wait,
+    // 前のフレームを画面に送っている最中なら捨てる (毎フレーム描き直すアニメーション向け)
+    DropIfBusy,
+}
+
+// 表示スレッドに送ったがまだ画面に送り終わっていないフレームの数
+// (DisplayDriver と表示スレッドで共有する)
+// 表示スレッドは I2C の転送が終わってから release() する
+// (グレースケール表示中はサブフレームの背景に取り込んだ時点、送るのは次のサブフレーム)
+#[derive(Clone, Default)]
+pub struct PendingFrames {
+    count: Arc<AtomicUsize>,
+}
+
+impl PendingFrames {
+    pub fn new() -> Self {
+        PendingFrames { count: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Acquire)
+    }
+
+    // 送ってよければ数を増やして true を返す
+    pub fn try_acquire(&self, policy: SubmitPolicy) -> bool {
+        match policy {
+            SubmitPolicy::Wait => {
+                self.count.fetch_add(1, Ordering::AcqRel);
+                true
+            },
+            SubmitPolicy::DropIfBusy => self.count.compare_exchange(0, 1, Ordering::AcqRel, Ordering::Acquire).is_ok(),
+        }
+    }
+
+    // 画面に送り終わった (または送れなかった)
+    pub fn release(&self) {
+        self.count.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderError {
+    // BMP として読めない画像
+    InvalidImage,
+    // 画像アセットを展開できない
+    Asset(ImageAssetError),
+    // スプライトを描けない (フレーム番号がシートにないなど)
+    Sprite(SpriteError),
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::InvalidImage => write!(f, "image is not a valid BMP"),
+            RenderError::Asset(e) => write!(f, "image asset: {}", e),
+            RenderError::Sprite(e) => write!(f, "sprite: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+// 点灯色の線 (width ピクセル)
+pub fn stroke(width: u32) -> PrimitiveStyle<BinaryColor> {
+    PrimitiveStyle::with_stroke(BinaryColor::On, width)
+}
+
+// 点灯色の塗りつぶし
+pub fn fill() -> PrimitiveStyle<BinaryColor> {
+    PrimitiveStyle::with_fill(BinaryColor::On)
+}
+
+// 消灯色で塗りつぶし、点灯色で縁取る
+pub fn outlined_fill(width: u32) -> PrimitiveStyle<BinaryColor> {
+    PrimitiveStyleBuilder::new()
+        .stroke_color(BinaryColor::On)
+        .stroke_width(width)
+        .fill_color(BinaryColor::Off)
+        .build()
+}
+
+// フレームを画面イメージに描く
+// 全てのコマンドを描けた時だけ target に反映する
+pub fn render_frame(frame: &DisplayFrame, target: &mut FrameBuffer) -> Result<(), RenderError> {
+    let mut scratch = target.clone();
+    for command in frame.commands.iter() {
+        render(command, &mut scratch)?;
+    }
+    *target = scratch;
+    Ok(())
+}
+
+// コマンドを画面イメージに描く (Update やキャプチャは何もしない)
+pub fn render(command: &DisplayCommand, frame: &mut FrameBuffer) -> Result<(), RenderError> {
+    // FrameBuffer への描画は失敗しない
+    match command {
+        DisplayCommand::Clear => {
+            frame.clear(BinaryColor::Off).unwrap();
+        },
+        DisplayCommand::DrawImage { image, point } => {
+            let bmp = Bmp::<BinaryColor>::from_slice(image).map_err(|_| RenderError::InvalidImage)?;
+            Image::new(&bmp, *point).draw(frame).unwrap();
+        },
+        DisplayCommand::DrawAsset { asset, point } => {
+            let image = asset.decode().map_err(RenderError::Asset)?;
+            for y in 0..image.height {
+                for x in 0..image.width {
+                    let color = if image.get(x, y) { BinaryColor::On } else { BinaryColor::Off };
+                    frame.set_pixel(*point + Point::new(x as i32, y as i32), color);
+                }
+            }
+        },
+        DisplayCommand::DrawText { text, point } => {
+            let style = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+            Text::with_baseline(text, *point, style, Baseline::Top).draw(frame).unwrap();
+        },
+        DisplayCommand::DrawTextBox { text, area, style } => {
+            text::draw_text_box(frame, text, area, style).unwrap();
+        },
+        DisplayCommand::DrawPixel { point, color } => {
+            frame.set_pixel(*point, *color);
+        },
+        DisplayCommand::DrawLine { start, end, style } => {
+            Line::new(*start, *end).into_styled(*style).draw(frame).unwrap();
+        },
+        DisplayCommand::DrawRectangle { area, style } => {
+            area.into_styled(*style).draw(frame).unwrap();
+        },
+        DisplayCommand::DrawRoundedRectangle { area, corner_radius, style } => {
+            let radii = CornerRadii::new(Size::new(*corner_radius, *corner_radius));
+            RoundedRectangle::new(*area, radii).into_styled(*style).draw(frame).unwrap();
+        },
+        DisplayCommand::DrawCircle { top_left, diameter, style } => {
+            Circle::new(*top_left, *diameter).into_styled(*style).draw(frame).unwrap();
+        },
+        DisplayCommand::DrawPolyline { points, style } => {
+            Polyline::new(points).into_styled(*style).draw(frame).unwrap();
+        },
+        DisplayCommand::InvertRegion { area } => {
+            frame.invert_region(area);
+        },
+        DisplayCommand::ClearRegion { area } => {
+            frame.fill_region(area, BinaryColor::Off);
+        },
+        DisplayCommand::DrawSprite { sheet, index, point, flip } => {
+            sheet.draw(frame, *index, *point, *flip).map_err(RenderError::Sprite)?;
+        },
+        DisplayCommand::StartAnimation { .. } | DisplayCommand::StopAnimation { .. } | DisplayCommand::MoveSprite { .. } => (),
+        DisplayCommand::ShowGrayscale { .. } | DisplayCommand::HideGrayscale => (),
+        DisplayCommand::Update | DisplayCommand::Screenshot | DisplayCommand::SetMirroring(_) => (),
+        DisplayCommand::Frame(display_frame) => render_frame(display_frame, frame)?,
+    }
+    Ok(())
+}
+
+// アニメーションのコマンドを Animator に反映する (描画のコマンドは何もしない)
+// Frame の中のコマンドも反映する (途中で失敗しても、それまでのコマンドは反映したまま)
+pub fn animate(command: &DisplayCommand, animator: &mut Animator, now_ms: u64) -> Result<(), SpriteError> {
+    match command {
+        DisplayCommand::StartAnimation { id, sprite } => animator.start(*id, sprite.clone(), now_ms)?,
+        DisplayCommand::StopAnimation { id } => {
+            animator.stop(*id);
+        },
+        DisplayCommand::MoveSprite { id, point, flip } => {
+            animator.move_to(*id, *point, *flip);
+        },
+        DisplayCommand::Frame(display_frame) => {
+            for command in display_frame.commands.iter() {
+                animate(command, animator, now_ms)?;
+            }
+        },
+        _ => (),
+    }
+    Ok(())
+}
@@ -5,13 +5,10 @@ use esp_idf_hal::i2c::I2cConfig;
 use esp_idf_hal::i2c::I2cDriver;
 
 use embedded_graphics::{
-    mono_font::{ascii::FONT_6X10, MonoTextStyleBuilder},
     pixelcolor::BinaryColor,
     prelude::*,
-    text::{Baseline, Text},
-    image::Image,
+    primitives::{PrimitiveStyle, Rectangle},
 };
-use tinybmp::Bmp;
 
 use ssd1306::{
     prelude::*,
@@ -19,76 +16,259 @@ use ssd1306::{
     Ssd1306
 };
 
+use std::sync::{Arc, Mutex};
 use std::sync::mpsc;
-use std::sync::mpsc::SendError;
+use std::sync::mpsc::{RecvTimeoutError, SendError, TryRecvError};
+use std::time::Duration;
 
-pub enum DisplayCommand {
-    Clear,
-    DrawImage { image: &'static [u8], point: Point },
-    DrawText { text: String, point: Point },
-    Update,
-}
+pub use rustorch::display_command::DisplayCommand;
+use rustorch::display_command::{self, DisplayFrame, PendingFrames, SubmitPolicy};
+use rustorch::frame_buffer::{FlushStats, FrameBuffer};
+use rustorch::grayscale::{GrayscaleImage, GrayscaleMethod, GrayscalePlayer};
+use rustorch::image_asset::ImageAsset;
+use rustorch::screenshot;
+use rustorch::sprite::{Animator, Flip, Sprite, SpriteId, SpriteSheet};
+use rustorch::text::TextStyle;
+
+// SSD1306 の I2C のクロック (oled-1mhz フィーチャ有効時は仕様外の 1MHz)
+#[cfg(not(feature = "oled-1mhz"))]
+pub const I2C_BAUDRATE_KHZ: u32 = 400;
+#[cfg(feature = "oled-1mhz")]
+pub const I2C_BAUDRATE_KHZ: u32 = 1000;
+
+// グレースケールのサブフレームのプリチャージ期間 (Brightness::NORMAL と同じ)
+const GRAYSCALE_PRECHARGE: u8 = 0x2;
 
 pub struct DisplayDriver {
     sender: Option<mpsc::SyncSender<DisplayCommand>>,
+    pending: PendingFrames,
+    flush_stats: Arc<Mutex<FlushStats>>,
 }
 
 impl DisplayDriver {
     pub fn new() -> Self {
         Self {
             sender: None,
+            pending: PendingFrames::new(),
+            flush_stats: Arc::new(Mutex::new(FlushStats::new())),
         }
     }
 
     pub fn start_thread(&mut self, i2c0: I2C0, sda: Gpio6, scl: Gpio7) -> anyhow::Result<()> {
-        let i2c_config = I2cConfig::new().baudrate(400.kHz().into()).scl_enable_pullup(false).sda_enable_pullup(false);
+        let i2c_config = I2cConfig::new().baudrate(I2C_BAUDRATE_KHZ.kHz().into()).scl_enable_pullup(false).sda_enable_pullup(false);
         let i2c = I2cDriver::new(i2c0, sda, scl, &i2c_config)?;
         let i2c_interface = I2CDisplayInterface::new(i2c);
        
-        let mut display = Ssd1306::new(i2c_interface, DisplaySize128x64, DisplayRotation::Rotate0)
-            .into_buffered_graphics_mode();
+        // 描画は FrameBuffer 側で行い、SSD1306 には Update でまとめて送る
+        let mut display = Ssd1306::new(i2c_interface, DisplaySize128x64, DisplayRotation::Rotate0);
         display.init().unwrap();
 
-        display.clear(BinaryColor::On).unwrap();
-        display.flush().unwrap();
+        let mut frame = FrameBuffer::new();
+        frame.clear(BinaryColor::On).unwrap();
+        display.draw(frame.as_bytes()).unwrap();
+
+        // 全体を送ると 400kHz の I2C で 25ms ほどかかるので、前回送った内容 (shown) から変わった範囲だけを送る
+        // 何か送った場合は true を返す
+        // contrast はグレースケールのサブフレームのコントラスト (None で通常の明るさに戻す)
+        let mut shown = frame.clone();
+        let flush_stats = Arc::clone(&self.flush_stats);
+        let mut shown_contrast: Option<u8> = None;
+        let mut flush = move |current: &FrameBuffer, shown: &mut FrameBuffer, contrast: Option<u8>| {
+            let start_us = unsafe { esp_idf_sys::esp_timer_get_time() };
+            // 画素が同じでもコントラストだけ変わることがあるので先に送る
+            if contrast != shown_contrast {
+                let brightness = match contrast {
+                    Some(contrast) => Brightness::custom(GRAYSCALE_PRECHARGE, contrast),
+                    None => Brightness::NORMAL,
+                };
+                display.set_brightness(brightness).unwrap();
+                shown_contrast = contrast;
+            }
+            let regions = current.dirty_regions(shown);
+            if regions.is_empty() {
+                return false;
+            }
+            for region in regions.iter() {
+                let top = (region.page * 8) as u8;
+                display.set_draw_area((region.start_column as u8, top), (region.end_column as u8, top + 8)).unwrap();
+                display.draw(current.region_bytes(region)).unwrap();
+            }
+            *shown = current.clone();
+            let elapsed_us = unsafe { esp_idf_sys::esp_timer_get_time() } - start_us;
+            let bytes = regions.iter().map(|region| region.len()).sum::<usize>();
+            flush_stats.lock().unwrap().record(elapsed_us as u32, bytes as u32);
+            true
+        };
+
+        // 画面のキャプチャ (シリアルコンソールへの出力)
+        let mut mirroring = false;
+        let mut screenshot_sequence = 0u32;
+        let mut screenshot = move |shown: &FrameBuffer| {
+            println!("{}", screenshot::encode_line(screenshot_sequence, shown));
+            screenshot_sequence = screenshot_sequence.wrapping_add(1);
+        };
+
+        // アニメーションは描いた画面 (frame) には残さず、画面に送る直前に重ねる
+        let mut animator = Animator::new();
+        let now_ms = || (unsafe { esp_idf_sys::esp_timer_get_time() } / 1000) as u64;
 
-        let text_style = MonoTextStyleBuilder::new()
-            .font(&FONT_6X10)
-            .text_color(BinaryColor::On)
-            .build();
-        
         let (tx, rx) = mpsc::sync_channel::<DisplayCommand>(10);
+        let pending = self.pending.clone();
 
         let _ = std::thread::spawn(move || {
 
             // デフォルトの優先度が 5 なのでそれより低くしておく
             unsafe { esp_idf_sys::vTaskPrioritySet(std::ptr::null_mut(), 4); };
 
-            for command in rx {
-                match command {
-                    DisplayCommand::Clear => {
-                        display.clear(BinaryColor::Off).unwrap();
-                    }
-                    DisplayCommand::DrawImage { image, point } => {
-                        let bmp = Bmp::from_slice(image).unwrap();
-                        let gfx_img: Image<Bmp<BinaryColor>> = Image::new(&bmp, point);
-                        gfx_img.draw(&mut display).unwrap();
+            // グレースケール表示中はサブフレームを切り替え続ける
+            let mut grayscale: Option<GrayscalePlayer> = None;
+
+            loop {
+                let command = if let Some(player) = grayscale.as_mut() {
+                    // グレースケール表示中はコマンドを待たず、来ていなければ次のサブフレームを送る
+                    match rx.try_recv() {
+                        Ok(command) => command,
+                        Err(TryRecvError::Empty) => {
+                            let subframe = player.next_subframe();
+                            if !flush(&subframe.frame, &mut shown, Some(subframe.contrast)) {
+                                // 送るものがなければ他のタスクに譲る
+                                std::thread::sleep(Duration::from_millis(1));
+                            }
+                            continue;
+                        },
+                        Err(TryRecvError::Disconnected) => break,
                     }
-                    DisplayCommand::DrawText { text, point } => {
-                        let text_img = Text::with_baseline(&text, point, text_style, Baseline::Top);
-                        text_img.draw(&mut display).unwrap();
+                } else {
+                    // アニメーション中は次のフレームの時刻までコマンドを待ち、来なければフレームを進める
+                    match animator.next_deadline_ms() {
+                        Some(deadline_ms) => match rx.recv_timeout(Duration::from_millis(deadline_ms.saturating_sub(now_ms()))) {
+                            Ok(command) => command,
+                            Err(RecvTimeoutError::Timeout) => {
+                                if animator.advance(now_ms()) && flush(&animator.compose(&frame), &mut shown, None) && mirroring {
+                                    screenshot(&shown);
+                                }
+                                continue;
+                            },
+                            Err(RecvTimeoutError::Disconnected) => break,
+                        },
+                        None => match rx.recv() {
+                            Ok(command) => command,
+                            Err(_) => break,
+                        },
                     }
+                };
+                // 画面に反映するかどうか
+                let mut refresh = false;
+                // submit() されたフレームを処理した (画面に送り終わったら PendingFrames を減らす)
+                let mut frame_done = false;
+                match command {
                     DisplayCommand::Update => {
-                        display.flush().unwrap();
+                        refresh = true;
+                    },
+                    DisplayCommand::Frame(display_frame) => {
+                        // 描けなかったフレームは捨てて、前の画面を表示したままにする
+                        match display_command::render_frame(&display_frame, &mut frame) {
+                            Ok(()) => {
+                                for command in display_frame.commands() {
+                                    if let Err(e) = display_command::animate(command, &mut animator, now_ms()) {
+                                        log::warn!("display: {}", e);
+                                    }
+                                    match command {
+                                        DisplayCommand::ShowGrayscale { image, point, method } => {
+                                            grayscale = Some(GrayscalePlayer::new(&animator.compose(&frame), image.clone(), *point, *method));
+                                        },
+                                        DisplayCommand::HideGrayscale => grayscale = None,
+                                        _ => (),
+                                    }
+                                }
+                                refresh = true;
+                            },
+                            Err(e) => log::warn!("display: frame dropped: {}", e),
+                        }
+                        frame_done = true;
+                    },
+                    DisplayCommand::StartAnimation { .. } | DisplayCommand::StopAnimation { .. } | DisplayCommand::MoveSprite { .. } => {
+                        // 描画コマンドと違い Update を待たずに画面に反映する
+                        if let Err(e) = display_command::animate(&command, &mut animator, now_ms()) {
+                            log::warn!("display: {}", e);
+                        }
+                        refresh = true;
+                    },
+                    DisplayCommand::ShowGrayscale { image, point, method } => {
+                        // その時点の描いた画面を背景にする (サブフレームは次のループから送る)
+                        grayscale = Some(GrayscalePlayer::new(&animator.compose(&frame), image, point, method));
+                    },
+                    DisplayCommand::HideGrayscale => {
+                        grayscale = None;
+                        refresh = true;
+                    },
+                    DisplayCommand::Screenshot => {
+                        screenshot(&shown);
+                    },
+                    DisplayCommand::SetMirroring(enable) => {
+                        mirroring = enable;
+                        // 切り替え時点の画面を最初のフレームにする
+                        if enable {
+                            screenshot(&shown);
+                        }
+                    },
+                    command => {
+                        if let Err(e) = display_command::render(&command, &mut frame) {
+                            log::warn!("display: {}", e);
+                        }
+                    },
+                }
+                if refresh {
+                    match grayscale.as_mut() {
+                        // サブフレームの背景を描き直す (キャプチャはサブフレーム毎には出さない)
+                        Some(player) => player.set_base(&animator.compose(&frame)),
+                        None => {
+                            if flush(&animator.compose(&frame), &mut shown, None) && mirroring {
+                                screenshot(&shown);
+                            }
+                        },
                     }
                 }
+                // グレースケール表示中は次のサブフレームと一緒に送られる
+                if frame_done {
+                    pending.release();
+                }
             }
         });
         self.sender = Some(tx);
         Ok(())
     }
 
-    // 描画系の前に一度だけ呼び出すこと
+    // 1 画面分の描画をまとめて送り、全部描けたら画面を更新する
+    // - 戻り値は送ったかどうか (DropIfBusy で前のフレームを処理中なら送らずに false)
+    // - clear() や draw_*() を個別に送るより、途中まで描いた画面が見えることがなく、送信の失敗で画面が崩れることもない
+    pub fn submit(&mut self, frame: DisplayFrame, policy: SubmitPolicy) -> Result<bool, SendError<DisplayCommand>> {
+        if !self.pending.try_acquire(policy) {
+            return Ok(false);
+        }
+        let result = self.sender.as_mut().unwrap().send(DisplayCommand::Frame(frame));
+        if result.is_err() {
+            self.pending.release();
+        }
+        result.map(|_| true)
+    }
+
+    // 画面への転送時間の実測値 (変わった範囲だけを送るので、更新する範囲が狭いほど短い)
+    // グレースケール表示中はサブフレーム 1 枚の転送時間 (GrayscaleEstimate の見積もりと比べられる)
+    pub fn get_flush_stats(&self) -> FlushStats {
+        *self.flush_stats.lock().unwrap()
+    }
+
+    pub fn reset_flush_stats(&mut self) {
+        *self.flush_stats.lock().unwrap() = FlushStats::new();
+    }
+
+    // 表示スレッドが処理中のフレームがある
+    pub fn is_busy(&self) -> bool {
+        self.pending.count() > 0
+    }
+
+    // 画面全体の消去
     pub fn clear(&mut self) -> Result<(), SendError<DisplayCommand>> {
         self.sender.as_mut().unwrap().send(DisplayCommand::Clear)
     }
@@ -98,14 +278,106 @@ impl DisplayDriver {
         self.sender.as_mut().unwrap().send(DisplayCommand::DrawImage { image, point })
     }
 
+    // build.rs で変換した画像の描画
+    pub fn draw_asset(&mut self, asset: ImageAsset, point: Point) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::DrawAsset { asset, point })
+    }
+
     // テキスト描画
     pub fn draw_text(&mut self, text: String, point: Point) -> Result<(), SendError<DisplayCommand>> {
         self.sender.as_mut().unwrap().send(DisplayCommand::DrawText { text, point })
     }
 
+    // 枠内へのテキスト描画 (フォント、寄せ、折り返しは style で指定)
+    pub fn draw_text_box(&mut self, text: String, area: Rectangle, style: TextStyle) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::DrawTextBox { text, area, style })
+    }
+
+    // 1 ピクセル描画
+    pub fn draw_pixel(&mut self, point: Point, color: BinaryColor) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::DrawPixel { point, color })
+    }
+
+    // 線分描画 (style は display_command::stroke() など)
+    pub fn draw_line(&mut self, start: Point, end: Point, style: PrimitiveStyle<BinaryColor>) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::DrawLine { start, end, style })
+    }
+
+    // 矩形描画 (塗りつぶしは display_command::fill() などのスタイルで指定)
+    pub fn draw_rectangle(&mut self, area: Rectangle, style: PrimitiveStyle<BinaryColor>) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::DrawRectangle { area, style })
+    }
+
+    // 角丸矩形描画
+    pub fn draw_rounded_rectangle(&mut self, area: Rectangle, corner_radius: u32, style: PrimitiveStyle<BinaryColor>) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::DrawRoundedRectangle { area, corner_radius, style })
+    }
+
+    // 円描画 (top_left は外接する正方形の左上)
+    pub fn draw_circle(&mut self, top_left: Point, diameter: u32, style: PrimitiveStyle<BinaryColor>) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::DrawCircle { top_left, diameter, style })
+    }
+
+    // 折れ線描画
+    pub fn draw_polyline(&mut self, points: Vec<Point>, style: PrimitiveStyle<BinaryColor>) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::DrawPolyline { points, style })
+    }
+
+    // 領域の点灯/消灯を反転
+    pub fn invert_region(&mut self, area: Rectangle) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::InvertRegion { area })
+    }
+
+    // 領域の消去
+    pub fn clear_region(&mut self, area: Rectangle) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::ClearRegion { area })
+    }
+
+    // スプライトシートの 1 フレームを描く
+    pub fn draw_sprite(&mut self, sheet: SpriteSheet, index: usize, point: Point, flip: Flip) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::DrawSprite { sheet, index, point, flip })
+    }
+
+    // アニメーションを始める (表示スレッドがフレームを進めるので、アプリは描き直さなくてよい)
+    // - 同じ id のアニメーションは置き換える
+    // - 描いた画面の上に重ねて表示し、すぐに画面に反映する
+    pub fn start_animation(&mut self, id: SpriteId, sprite: Sprite) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::StartAnimation { id, sprite })
+    }
+
+    // アニメーションを止めて消す
+    pub fn stop_animation(&mut self, id: SpriteId) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::StopAnimation { id })
+    }
+
+    // アニメーションを続けたまま動かす
+    pub fn move_sprite(&mut self, id: SpriteId, point: Point, flip: Flip) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::MoveSprite { id, point, flip })
+    }
+
+    // 描いた画面の上に階調のある画像を重ねて表示する (HideGrayscale まで表示スレッドが I2C を使い続ける)
+    pub fn show_grayscale(&mut self, image: GrayscaleImage, point: Point, method: GrayscaleMethod) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::ShowGrayscale { image, point, method })
+    }
+
+    pub fn hide_grayscale(&mut self) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::HideGrayscale)
+    }
+
+    // 表示中の画面をシリアルコンソールに出力する (形式は screenshot モジュール)
+    pub fn screenshot(&mut self) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::Screenshot)
+    }
+
+    // 画面を更新する度にシリアルコンソールに出力する (ホスト側で動画として見る)
+    // - 1 フレームで約 1.4KB 出力するので、頻繁に更新する画面ではフレームレートが落ちる
+    pub fn set_mirroring(&mut self, enable: bool) -> Result<(), SendError<DisplayCommand>> {
+        self.sender.as_mut().unwrap().send(DisplayCommand::SetMirroring(enable))
+    }
+
     // 画面の更新
-    // - 画面更新に数十ミリ秒かかる
-    // - 画面描画が完了するまでは次の描画依頼を出しても詰まることに注意
+    // - 前回の更新から変わった範囲だけを送る (全体が変わった場合は数十ミリ秒かかる)
+    // - 画面描画が完了するまでは次の描画依頼を出しても詰まることに注意 (詰まらせたくない場合は submit() を使う)
     pub fn update(&mut self) -> Result<(), SendError<DisplayCommand>> {
         self.sender.as_mut().unwrap().send(DisplayCommand::Update)
     }
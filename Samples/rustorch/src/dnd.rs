@@ -0,0 +1,195 @@
+// おやすみモード (Do Not Disturb)
+// - 手動の ON/OFF と、毎日決まった時間帯 (静音時間) に自動で有効になるスケジュールを持つ
+// - 有効な間はブザーを鳴らさず、通知音は LED の点滅と OLED のトーストで代替する
+// - 設定は NVS に保存するためバイト列と相互に変換できる
+
+use std::fmt;
+
+pub const MINUTES_PER_DAY: u16 = 24 * 60;
+
+// これより前の時刻はシステム時刻が未設定 (起動直後の 1970 年) とみなしてスケジュールを無視する
+// (2024-01-01 00:00:00 UTC)
+pub const CLOCK_VALID_AFTER_UNIX_S: i64 = 1_704_067_200;
+
+// 保存形式のバージョン (形式を変えたら上げる)
+const FORMAT_VERSION: u8 = 1;
+const ENCODED_LENGTH: usize = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DndError {
+    // 0:00 からの分数が 1 日を超えている
+    InvalidTime(u16),
+    // 開始と終了が同じ時刻
+    EmptyQuietHours,
+    // 保存データの長さやバージョンが合わない
+    InvalidData,
+}
+
+impl fmt::Display for DndError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DndError::InvalidTime(minute) => write!(f, "time {} is out of range (0 ~ {} minutes)", minute, MINUTES_PER_DAY - 1),
+            DndError::EmptyQuietHours => write!(f, "quiet hours must not start and end at the same time"),
+            DndError::InvalidData => write!(f, "invalid saved settings"),
+        }
+    }
+}
+
+impl std::error::Error for DndError {}
+
+// 静音時間 (開始時刻を含み、終了時刻を含まない)
+// 開始より終了が早い場合は日付をまたぐ (例: 22:00 ~ 7:00)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    // 0:00 からの分数
+    start_minute: u16,
+    end_minute: u16,
+}
+
+impl QuietHours {
+    pub fn new(start_minute: u16, end_minute: u16) -> Result<Self, DndError> {
+        for minute in [start_minute, end_minute] {
+            if minute >= MINUTES_PER_DAY {
+                return Err(DndError::InvalidTime(minute));
+            }
+        }
+        if start_minute == end_minute {
+            return Err(DndError::EmptyQuietHours);
+        }
+        Ok(QuietHours { start_minute, end_minute })
+    }
+
+    pub fn start_minute(&self) -> u16 {
+        self.start_minute
+    }
+
+    pub fn end_minute(&self) -> u16 {
+        self.end_minute
+    }
+
+    pub fn contains(&self, minute_of_day: u16) -> bool {
+        if self.start_minute < self.end_minute {
+            (self.start_minute..self.end_minute).contains(&minute_of_day)
+        } else {
+            minute_of_day >= self.start_minute || minute_of_day < self.end_minute
+        }
+    }
+}
+
+impl fmt::Display for QuietHours {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{:02} ~ {}:{:02}",
+            self.start_minute / 60, self.start_minute % 60, self.end_minute / 60, self.end_minute % 60)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DndSettings {
+    // 手動で有効にしている
+    pub manual: bool,
+    pub quiet_hours: Option<QuietHours>,
+    // 静音時間の判定に使う UTC からのずれ [分]
+    pub utc_offset_minutes: i16,
+}
+
+impl DndSettings {
+    // 無効、スケジュールなし、日本時間
+    pub const fn new() -> Self {
+        DndSettings {
+            manual: false,
+            quiet_hours: None,
+            utc_offset_minutes: 9 * 60,
+        }
+    }
+
+    #[must_use]
+    pub fn manual(mut self, enable: bool) -> Self {
+        self.manual = enable;
+        self
+    }
+
+    #[must_use]
+    pub fn quiet_hours(mut self, quiet_hours: Option<QuietHours>) -> Self {
+        self.quiet_hours = quiet_hours;
+        self
+    }
+
+    #[must_use]
+    pub fn utc_offset(mut self, minutes: i16) -> Self {
+        self.utc_offset_minutes = minutes;
+        self
+    }
+
+    // UNIX 時刻 [s] に対応する現地時刻の 0:00 からの分数
+    pub fn local_minute_of_day(&self, unix_time_s: i64) -> u16 {
+        let local_minutes = unix_time_s.div_euclid(60) + self.utc_offset_minutes as i64;
+        local_minutes.rem_euclid(MINUTES_PER_DAY as i64) as u16
+    }
+
+    // 現地時刻 minute_of_day (0:00 からの分数) に時計を合わせる時の UNIX 時刻 [s]
+    // 日付は分からないので CLOCK_VALID_AFTER_UNIX_S 以降の最初のその時刻にする (静音時間の判定には時刻だけを使う)
+    pub fn unix_time_at(&self, minute_of_day: u16) -> Result<i64, DndError> {
+        if minute_of_day >= MINUTES_PER_DAY {
+            return Err(DndError::InvalidTime(minute_of_day));
+        }
+        let minutes = (minute_of_day as i64 - self.utc_offset_minutes as i64 - CLOCK_VALID_AFTER_UNIX_S / 60)
+            .rem_euclid(MINUTES_PER_DAY as i64);
+        Ok(CLOCK_VALID_AFTER_UNIX_S + minutes * 60)
+    }
+
+    // 時刻 unix_time_s [s] においておやすみモードが有効か
+    // 時刻が不明 (None やシステム時刻の未設定) の場合は手動設定だけで判定する
+    pub fn is_active(&self, unix_time_s: Option<i64>) -> bool {
+        if self.manual {
+            return true;
+        }
+        match (self.quiet_hours, unix_time_s) {
+            (Some(quiet_hours), Some(unix_time_s)) if unix_time_s >= CLOCK_VALID_AFTER_UNIX_S =>
+                quiet_hours.contains(self.local_minute_of_day(unix_time_s)),
+            _ => false,
+        }
+    }
+
+    // 保存用のバイト列 (リトルエンディアン)
+    pub fn to_bytes(&self) -> [u8; ENCODED_LENGTH] {
+        let (has_schedule, start, end) = match self.quiet_hours {
+            Some(quiet_hours) => (1, quiet_hours.start_minute, quiet_hours.end_minute),
+            None => (0, 0, 0),
+        };
+        let start = start.to_le_bytes();
+        let end = end.to_le_bytes();
+        let offset = self.utc_offset_minutes.to_le_bytes();
+        [
+            FORMAT_VERSION,
+            self.manual as u8,
+            has_schedule,
+            start[0], start[1],
+            end[0], end[1],
+            offset[0], offset[1],
+        ]
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DndError> {
+        if bytes.len() != ENCODED_LENGTH || bytes[0] != FORMAT_VERSION || bytes[1] > 1 || bytes[2] > 1 {
+            return Err(DndError::InvalidData);
+        }
+        let quiet_hours = if bytes[2] == 1 {
+            let start = u16::from_le_bytes([bytes[3], bytes[4]]);
+            let end = u16::from_le_bytes([bytes[5], bytes[6]]);
+            Some(QuietHours::new(start, end).map_err(|_| DndError::InvalidData)?)
+        } else {
+            None
+        };
+        Ok(DndSettings {
+            manual: bytes[1] == 1,
+            quiet_hours,
+            utc_offset_minutes: i16::from_le_bytes([bytes[7], bytes[8]]),
+        })
+    }
+}
+
+impl Default for DndSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,216 @@
+// OLED (SSD1306 128x64) の画面イメージ (ハードウェア非依存)
+// - SSD1306 の GDDRAM と同じ並び (1 バイトが縦 8 ピクセル、ページ毎に横 128 バイト)
+// - embedded-graphics の描画先になり、描いた内容を読み出せる (反転などに必要)
+// - 前回画面に送った内容と比べて、変わった部分だけを送れるようにする
+
+use core::convert::Infallible;
+
+use embedded_graphics::{
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::Rectangle,
+};
+
+pub const WIDTH: u32 = 128;
+pub const HEIGHT: u32 = 64;
+// 縦 8 ピクセル単位の行 (ページ) の数
+pub const PAGE_COUNT: u32 = HEIGHT / 8;
+pub const BUFFER_SIZE: usize = (WIDTH * PAGE_COUNT) as usize;
+
+// 変わった範囲 (1 ページ内の列の範囲、end_column は含まない)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirtyRegion {
+    pub page: u32,
+    pub start_column: u32,
+    pub end_column: u32,
+}
+
+impl DirtyRegion {
+    // 転送するバイト数
+    pub fn len(&self) -> usize {
+        (self.end_column - self.start_column) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start_column == self.end_column
+    }
+}
+
+#[derive(Clone, PartialEq, Eq)]
+pub struct FrameBuffer {
+    buffer: [u8; BUFFER_SIZE],
+}
+
+impl FrameBuffer {
+    pub fn new() -> Self {
+        FrameBuffer { buffer: [0; BUFFER_SIZE] }
+    }
+
+    // 画面外は None
+    fn index(point: Point) -> Option<(usize, u8)> {
+        if point.x < 0 || point.y < 0 || point.x >= WIDTH as i32 || point.y >= HEIGHT as i32 {
+            return None;
+        }
+        let index = (point.y as u32 / 8 * WIDTH + point.x as u32) as usize;
+        Some((index, 1 << (point.y % 8)))
+    }
+
+    // 画面外は消灯扱い
+    pub fn get_pixel(&self, point: Point) -> BinaryColor {
+        match Self::index(point) {
+            Some((index, mask)) if self.buffer[index] & mask != 0 => BinaryColor::On,
+            _ => BinaryColor::Off,
+        }
+    }
+
+    // 画面外は無視する
+    pub fn set_pixel(&mut self, point: Point, color: BinaryColor) {
+        if let Some((index, mask)) = Self::index(point) {
+            match color {
+                BinaryColor::On => self.buffer[index] |= mask,
+                BinaryColor::Off => self.buffer[index] &= !mask,
+            }
+        }
+    }
+
+    // 画面内に切り詰めた領域
+    pub fn clip(area: &Rectangle) -> Rectangle {
+        area.intersection(&Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT)))
+    }
+
+    pub fn fill_region(&mut self, area: &Rectangle, color: BinaryColor) {
+        for point in Self::clip(area).points() {
+            self.set_pixel(point, color);
+        }
+    }
+
+    pub fn invert_region(&mut self, area: &Rectangle) {
+        for point in Self::clip(area).points() {
+            self.set_pixel(point, self.get_pixel(point).invert());
+        }
+    }
+
+    // SSD1306 にそのまま送れるバイト列
+    pub fn as_bytes(&self) -> &[u8; BUFFER_SIZE] {
+        &self.buffer
+    }
+
+    // shown (前回画面に送った内容) から変わった範囲
+    // ページ毎に、最初に変わった列から最後に変わった列までをまとめる
+    pub fn dirty_regions(&self, shown: &FrameBuffer) -> Vec<DirtyRegion> {
+        let width = WIDTH as usize;
+        let mut regions = Vec::new();
+        for page in 0..PAGE_COUNT {
+            let range = page as usize * width..(page as usize + 1) * width;
+            let current = &self.buffer[range.clone()];
+            let previous = &shown.buffer[range];
+            let changed = |(column, (a, b)): (usize, (&u8, &u8))| if a != b { Some(column) } else { None };
+            let first = current.iter().zip(previous).enumerate().find_map(changed);
+            let last = current.iter().zip(previous).enumerate().rev().find_map(changed);
+            if let (Some(first), Some(last)) = (first, last) {
+                regions.push(DirtyRegion { page, start_column: first as u32, end_column: last as u32 + 1 });
+            }
+        }
+        regions
+    }
+
+    // 範囲の GDDRAM のバイト列
+    pub fn region_bytes(&self, region: &DirtyRegion) -> &[u8] {
+        let offset = (region.page * WIDTH) as usize;
+        &self.buffer[offset + region.start_column as usize..offset + region.end_column as usize]
+    }
+
+    // 点灯しているピクセル数
+    pub fn count_on(&self) -> u32 {
+        self.buffer.iter().map(|byte| byte.count_ones()).sum()
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OriginDimensions for FrameBuffer {
+    fn size(&self) -> Size {
+        Size::new(WIDTH, HEIGHT)
+    }
+}
+
+impl DrawTarget for FrameBuffer {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            self.set_pixel(point, color);
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: BinaryColor) -> Result<(), Self::Error> {
+        self.buffer.fill(if color.is_on() { 0xFF } else { 0x00 });
+        Ok(())
+    }
+}
+
+// 画面への転送時間の集計
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushStats {
+    pub count: u32,
+    // 直近の転送時間とバイト数
+    pub last_us: u32,
+    pub last_bytes: u32,
+    pub max_us: u32,
+    pub sum_us: u64,
+    pub sum_bytes: u64,
+}
+
+impl FlushStats {
+    pub const fn new() -> Self {
+        FlushStats {
+            count: 0,
+            last_us: 0,
+            last_bytes: 0,
+            max_us: 0,
+            sum_us: 0,
+            sum_bytes: 0,
+        }
+    }
+
+    pub fn record(&mut self, elapsed_us: u32, bytes: u32) {
+        self.count += 1;
+        self.last_us = elapsed_us;
+        self.last_bytes = bytes;
+        self.max_us = self.max_us.max(elapsed_us);
+        self.sum_us += elapsed_us as u64;
+        self.sum_bytes += bytes as u64;
+    }
+
+    pub fn mean_us(&self) -> u32 {
+        if self.count == 0 {
+            0
+        } else {
+            (self.sum_us / self.count as u64) as u32
+        }
+    }
+
+    // 実測から bytes バイトの転送にかかる時間を見積もる (まだ計測していなければ None)
+    // 小さなカウンタなどを何 fps で更新できるかの目安にする
+    pub fn estimate_us(&self, bytes: u32) -> Option<u32> {
+        if self.sum_bytes == 0 {
+            return None;
+        }
+        Some((self.sum_us * bytes as u64 / self.sum_bytes) as u32)
+    }
+}
+
+impl Default for FlushStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,253 @@
+// グレースケール表示 (ハードウェア非依存)
+// - SSD1306 は 1 ピクセル 1 ビットなので、点灯/消灯の違うサブフレームを素早く切り替えて中間の明るさに見せる
+// - Pwm: 同じ明るさのサブフレームを levels - 1 枚切り替え、点灯している枚数で明るさを表す
+// - WeightedPlanes: ビット毎のプレーンを、重みに比例したコントラスト (SSD1306 の 0x81 コマンド) で表示する
+//   (サブフレームが log2(levels) 枚で済むのでちらつきにくいが、コントラストと明るさは比例しないので階調はおおよそ)
+// - SSD1306 のパネルのリフレッシュ (約 100Hz) より I2C の転送の方が遅いので、切り替えの速さは転送時間で決まる
+//   変わったページの範囲だけを送るので、背景が多い画像ほど速い (GrayscaleEstimate で見積もる)
+
+use std::fmt;
+
+use embedded_graphics::{
+    pixelcolor::{BinaryColor, Rgb888},
+    prelude::*,
+};
+use tinybmp::Bmp;
+
+use crate::frame_buffer::{DirtyRegion, FrameBuffer};
+
+pub const MIN_LEVELS: u8 = 2;
+pub const MAX_LEVELS: u8 = 8;
+// WeightedPlanes の最上位ビットのプレーンのコントラスト (下位のプレーンは半分ずつ)
+pub const MAX_CONTRAST: u8 = 0xFF;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrayscaleError {
+    // 階調数が MIN_LEVELS..=MAX_LEVELS にない
+    InvalidLevels(u8),
+    // 画素の数が幅 x 高さと合わないか、階調数以上の値がある
+    InvalidPixels,
+    // BMP として読めない画像
+    InvalidImage,
+}
+
+impl fmt::Display for GrayscaleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrayscaleError::InvalidLevels(levels) => write!(f, "{} levels is not supported ({}..={})", levels, MIN_LEVELS, MAX_LEVELS),
+            GrayscaleError::InvalidPixels => write!(f, "pixels do not match the image size or levels"),
+            GrayscaleError::InvalidImage => write!(f, "image is not a valid BMP"),
+        }
+    }
+}
+
+impl std::error::Error for GrayscaleError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrayscaleMethod {
+    Pwm,
+    WeightedPlanes,
+}
+
+// 0 (消灯) から levels - 1 (点灯) までの値の画像
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrayscaleImage {
+    width: u32,
+    height: u32,
+    levels: u8,
+    // 左上から行毎
+    pixels: Vec<u8>,
+}
+
+impl GrayscaleImage {
+    pub fn new(width: u32, height: u32, levels: u8, pixels: Vec<u8>) -> Result<Self, GrayscaleError> {
+        if !(MIN_LEVELS..=MAX_LEVELS).contains(&levels) {
+            return Err(GrayscaleError::InvalidLevels(levels));
+        }
+        if pixels.len() != (width * height) as usize || pixels.iter().any(|value| *value >= levels) {
+            return Err(GrayscaleError::InvalidPixels);
+        }
+        Ok(GrayscaleImage { width, height, levels, pixels })
+    }
+
+    // 8 ビットの輝度 (0..=255) を levels 段階にする
+    pub fn from_luminance(width: u32, height: u32, luminance: &[u8], levels: u8) -> Result<Self, GrayscaleError> {
+        if !(MIN_LEVELS..=MAX_LEVELS).contains(&levels) {
+            return Err(GrayscaleError::InvalidLevels(levels));
+        }
+        let max = levels as u32 - 1;
+        let pixels = luminance.iter().map(|value| ((*value as u32 * max + 127) / 255) as u8).collect();
+        Self::new(width, height, levels, pixels)
+    }
+
+    // 8 ビットのパレット画像などの BMP から
+    pub fn from_bmp(data: &[u8], levels: u8) -> Result<Self, GrayscaleError> {
+        let bmp = Bmp::<Rgb888>::from_slice(data).map_err(|_| GrayscaleError::InvalidImage)?;
+        let size = bmp.bounding_box().size;
+        let mut luminance = vec![0u8; (size.width * size.height) as usize];
+        for Pixel(point, color) in bmp.pixels() {
+            let value = (color.r() as u32 * 299 + color.g() as u32 * 587 + color.b() as u32 * 114) / 1000;
+            luminance[(point.y as u32 * size.width + point.x as u32) as usize] = value as u8;
+        }
+        Self::from_luminance(size.width, size.height, &luminance, levels)
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    pub fn levels(&self) -> u8 {
+        self.levels
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> u8 {
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+// 1 枚のサブフレームと、表示する時のコントラスト
+#[derive(Clone)]
+pub struct Subframe {
+    pub frame: FrameBuffer,
+    pub contrast: u8,
+}
+
+// WeightedPlanes のビット数
+fn plane_count(levels: u8) -> u32 {
+    u8::BITS - (levels - 1).leading_zeros()
+}
+
+pub fn subframe_count(levels: u8, method: GrayscaleMethod) -> usize {
+    match method {
+        GrayscaleMethod::Pwm => levels as usize - 1,
+        GrayscaleMethod::WeightedPlanes => plane_count(levels) as usize,
+    }
+}
+
+// base の top_left に image を置いたサブフレームの列 (image の外は base のまま)
+pub fn subframes(base: &FrameBuffer, image: &GrayscaleImage, top_left: Point, method: GrayscaleMethod) -> Vec<Subframe> {
+    let count = subframe_count(image.levels, method);
+    let max = image.levels as u32 - 1;
+    (0..count as u32).map(|k| {
+        let mut frame = base.clone();
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let value = image.get(x, y) as u32;
+                let on = match method {
+                    // value 枚が点灯するように、点灯するサブフレームを散らす
+                    // (max / 2 ずらして、どの値も最後のサブフレームに偏らないようにする)
+                    GrayscaleMethod::Pwm => ((k + 1) * value + max / 2) / max != (k * value + max / 2) / max,
+                    // 2^bits - 1 段階に引き伸ばしてから k ビット目
+                    GrayscaleMethod::WeightedPlanes => {
+                        let full = (1 << count) - 1;
+                        let scaled = (value * full + max / 2) / max;
+                        scaled & (1 << k) != 0
+                    },
+                };
+                let color = if on { BinaryColor::On } else { BinaryColor::Off };
+                frame.set_pixel(top_left + Point::new(x as i32, y as i32), color);
+            }
+        }
+        let contrast = match method {
+            GrayscaleMethod::Pwm => MAX_CONTRAST,
+            GrayscaleMethod::WeightedPlanes => MAX_CONTRAST >> (count as u32 - 1 - k),
+        };
+        Subframe { frame, contrast }
+    }).collect()
+}
+
+// 表示スレッドでサブフレームを順番に出す
+pub struct GrayscalePlayer {
+    image: GrayscaleImage,
+    top_left: Point,
+    method: GrayscaleMethod,
+    subframes: Vec<Subframe>,
+    next: usize,
+}
+
+impl GrayscalePlayer {
+    pub fn new(base: &FrameBuffer, image: GrayscaleImage, top_left: Point, method: GrayscaleMethod) -> Self {
+        let subframes = subframes(base, &image, top_left, method);
+        GrayscalePlayer { image, top_left, method, subframes, next: 0 }
+    }
+
+    // 背景 (描いた画面) が変わった
+    pub fn set_base(&mut self, base: &FrameBuffer) {
+        self.subframes = subframes(base, &self.image, self.top_left, self.method);
+        self.next %= self.subframes.len();
+    }
+
+    pub fn next_subframe(&mut self) -> &Subframe {
+        let index = self.next;
+        self.next = (self.next + 1) % self.subframes.len();
+        &self.subframes[index]
+    }
+}
+
+// I2C の転送時間の見積もり (ホストで計算するだけで、実機では計測していない)
+// - SSD1306 の I2C のドライバは、範囲の指定 (コマンド 6 バイト) と 16 バイト毎のデータを別々のトランザクションで送る
+// - 1 バイトは ACK を含めて 9 クロック、トランザクション毎にアドレスと制御バイトの 2 バイトが付く
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct I2cTiming {
+    pub clock_hz: u32,
+    // トランザクション毎のドライバの処理時間 (仮の値、実機の Gray benchmark のログの見積もりと実測を比べて合わせる)
+    pub transaction_overhead_us: u32,
+}
+
+const DATA_CHUNK_BYTES: usize = 16;
+const DRAW_AREA_COMMAND_BYTES: usize = 6;
+const TRANSACTION_HEADER_BYTES: usize = 2;
+
+impl I2cTiming {
+    pub const STANDARD_400K: I2cTiming = I2cTiming { clock_hz: 400_000, transaction_overhead_us: 40 };
+    pub const FAST_1M: I2cTiming = I2cTiming { clock_hz: 1_000_000, transaction_overhead_us: 40 };
+
+    pub fn flush_us(&self, regions: &[DirtyRegion]) -> u32 {
+        let mut transactions = 0;
+        let mut bytes = 0;
+        for region in regions.iter() {
+            let chunks = region.len().div_ceil(DATA_CHUNK_BYTES);
+            transactions += 1 + chunks;
+            bytes += DRAW_AREA_COMMAND_BYTES + region.len();
+        }
+        bytes += transactions * TRANSACTION_HEADER_BYTES;
+        let clock_us = (bytes as u64 * 9 * 1_000_000).div_ceil(self.clock_hz as u64);
+        clock_us as u32 + transactions as u32 * self.transaction_overhead_us
+    }
+}
+
+// サブフレームを一巡する間の転送量と時間の見積もり (I2cTiming から計算した値で、実測値ではない)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GrayscaleEstimate {
+    pub subframes: usize,
+    pub bytes_per_cycle: usize,
+    pub cycle_us: u32,
+}
+
+impl GrayscaleEstimate {
+    // 繰り返し表示している間の値 (前のサブフレームから変わった範囲だけを送る)
+    pub fn estimate(subframes: &[Subframe], timing: &I2cTiming) -> Self {
+        let mut shown = subframes.last().map(|subframe| subframe.frame.clone()).unwrap_or_default();
+        let mut bytes_per_cycle = 0;
+        let mut cycle_us = 0;
+        for subframe in subframes.iter() {
+            let regions = subframe.frame.dirty_regions(&shown);
+            bytes_per_cycle += regions.iter().map(|region| region.len()).sum::<usize>();
+            cycle_us += timing.flush_us(&regions);
+            shown = subframe.frame.clone();
+        }
+        GrayscaleEstimate { subframes: subframes.len(), bytes_per_cycle, cycle_us }
+    }
+
+    // 1 秒間に一巡できる回数 (これが低いとちらついて見える)
+    pub fn frame_rate(&self) -> f32 {
+        if self.cycle_us == 0 {
+            return f32::INFINITY;
+        }
+        1_000_000.0 / self.cycle_us as f32
+    }
+
+    pub fn subframe_rate(&self) -> f32 {
+        self.frame_rate() * self.subframes as f32
+    }
+}
@@ -0,0 +1,313 @@
+// 画像アセットの 2 値化とパック (ハードウェア非依存、build.rs とも共有する)
+// - build.rs で PNG/GIF を読み込んで GrayImage にし、ImageConvert の設定で 2 値化して Rust のソースを生成する
+// - パックした形式は SSD1306 の GDDRAM と同じ (1 バイトが縦 8 ピクセル、ページ毎に横 width バイト)
+// - 圧縮する場合は PackBits 風の RLE
+//   制御バイト n が 0x00..=0x7F なら続く n + 1 バイトをそのまま、0x80..=0xFF なら次の 1 バイトを n - 0x80 + 2 回繰り返す
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageAssetError {
+    // RLE のデータが途中で終わっている
+    TruncatedData,
+    // 展開した大きさが画像の大きさと合わない (期待するバイト数, 実際のバイト数)
+    SizeMismatch(usize, usize),
+    // 画素の数が幅 x 高さと合わない
+    InvalidPixels,
+}
+
+impl fmt::Display for ImageAssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageAssetError::TruncatedData => write!(f, "RLE data is truncated"),
+            ImageAssetError::SizeMismatch(expected, actual) => write!(f, "decoded size {} bytes (expected {} bytes)", actual, expected),
+            ImageAssetError::InvalidPixels => write!(f, "pixel count does not match the image size"),
+        }
+    }
+}
+
+impl std::error::Error for ImageAssetError {}
+
+// 8 ビットの輝度の画像 (0 が消灯、255 が点灯)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrayImage {
+    pub width: u32,
+    pub height: u32,
+    // 左上から行毎
+    pub pixels: Vec<u8>,
+}
+
+impl GrayImage {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, ImageAssetError> {
+        if pixels.len() != (width * height) as usize {
+            return Err(ImageAssetError::InvalidPixels);
+        }
+        Ok(GrayImage { width, height, pixels })
+    }
+
+    // RGBA (1 ピクセル 4 バイト) から変換する (透明な部分は消灯)
+    pub fn from_rgba(width: u32, height: u32, rgba: &[u8]) -> Result<Self, ImageAssetError> {
+        if rgba.len() != (width * height * 4) as usize {
+            return Err(ImageAssetError::InvalidPixels);
+        }
+        let pixels = rgba.chunks(4).map(|pixel| {
+            let luminance = (pixel[0] as u32 * 299 + pixel[1] as u32 * 587 + pixel[2] as u32 * 114) / 1000;
+            (luminance * pixel[3] as u32 / 255) as u8
+        }).collect();
+        Ok(GrayImage { width, height, pixels })
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> u8 {
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+// 2 値化の方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dither {
+    // 閾値以上を点灯 (線画や文字向け)
+    Threshold(u8),
+    // 誤差拡散 (写真などの階調を粒の密度で表す)
+    FloydSteinberg,
+    // 4x4 の Bayer 行列による組織的ディザ (規則的な模様になるが、アニメーションでちらつきにくい)
+    Ordered,
+}
+
+const BAYER_4X4: [[u8; 4]; 4] = [
+    [ 0,  8,  2, 10],
+    [12,  4, 14,  6],
+    [ 3, 11,  1,  9],
+    [15,  7, 13,  5],
+];
+
+// 1 ピクセル 1 ビットの画像 (SSD1306 のページ形式)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedImage {
+    pub width: u32,
+    pub height: u32,
+    pub pages: Vec<u8>,
+}
+
+impl PackedImage {
+    pub fn page_count(height: u32) -> u32 {
+        height.div_ceil(8)
+    }
+
+    pub fn byte_count(width: u32, height: u32) -> usize {
+        (width * Self::page_count(height)) as usize
+    }
+
+    pub fn new(width: u32, height: u32) -> Self {
+        PackedImage { width, height, pages: vec![0; Self::byte_count(width, height)] }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        self.pages[(y / 8 * self.width + x) as usize] & (1 << (y % 8)) != 0
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, on: bool) {
+        let index = (y / 8 * self.width + x) as usize;
+        if on {
+            self.pages[index] |= 1 << (y % 8);
+        } else {
+            self.pages[index] &= !(1 << (y % 8));
+        }
+    }
+}
+
+pub fn dither(image: &GrayImage, method: Dither) -> PackedImage {
+    let mut packed = PackedImage::new(image.width, image.height);
+    match method {
+        Dither::Threshold(threshold) => {
+            for y in 0..image.height {
+                for x in 0..image.width {
+                    packed.set(x, y, image.get(x, y) >= threshold);
+                }
+            }
+        },
+        Dither::Ordered => {
+            for y in 0..image.height {
+                for x in 0..image.width {
+                    // 閾値を 8..=248 に散らす
+                    let threshold = BAYER_4X4[(y % 4) as usize][(x % 4) as usize] as u32 * 16 + 8;
+                    packed.set(x, y, image.get(x, y) as u32 >= threshold);
+                }
+            }
+        },
+        Dither::FloydSteinberg => {
+            let width = image.width as usize;
+            let mut values: Vec<i32> = image.pixels.iter().map(|value| *value as i32).collect();
+            for y in 0..image.height as usize {
+                for x in 0..width {
+                    let value = values[y * width + x];
+                    let on = value >= 128;
+                    packed.set(x as u32, y as u32, on);
+                    let error = value - if on { 255 } else { 0 };
+                    let mut spread = |dx: isize, dy: usize, weight: i32| {
+                        let nx = x as isize + dx;
+                        let ny = y + dy;
+                        if nx >= 0 && (nx as usize) < width && ny < image.height as usize {
+                            values[ny * width + nx as usize] += error * weight / 16;
+                        }
+                    };
+                    spread(1, 0, 7);
+                    spread(-1, 1, 3);
+                    spread(0, 1, 5);
+                    spread(1, 1, 1);
+                }
+            }
+        },
+    }
+    packed
+}
+
+pub fn rle_encode(data: &[u8]) -> Vec<u8> {
+    let mut result = Vec::new();
+    let mut literal: Vec<u8> = Vec::new();
+    let mut i = 0;
+    while i < data.len() {
+        let run = data[i..].iter().take(129).take_while(|byte| **byte == data[i]).count();
+        if run >= 2 {
+            for chunk in literal.chunks(128) {
+                result.push(chunk.len() as u8 - 1);
+                result.extend_from_slice(chunk);
+            }
+            literal.clear();
+            result.push(0x80 + (run - 2) as u8);
+            result.push(data[i]);
+            i += run;
+        } else {
+            literal.push(data[i]);
+            i += 1;
+        }
+    }
+    for chunk in literal.chunks(128) {
+        result.push(chunk.len() as u8 - 1);
+        result.extend_from_slice(chunk);
+    }
+    result
+}
+
+pub fn rle_decode(data: &[u8], expected_len: usize) -> Result<Vec<u8>, ImageAssetError> {
+    let mut result = Vec::with_capacity(expected_len);
+    let mut i = 0;
+    while i < data.len() {
+        let control = data[i] as usize;
+        if control < 0x80 {
+            let bytes = data.get(i + 1..i + 2 + control).ok_or(ImageAssetError::TruncatedData)?;
+            result.extend_from_slice(bytes);
+            i += 2 + control;
+        } else {
+            let byte = *data.get(i + 1).ok_or(ImageAssetError::TruncatedData)?;
+            result.extend(std::iter::repeat(byte).take(control - 0x80 + 2));
+            i += 2;
+        }
+    }
+    if result.len() != expected_len {
+        return Err(ImageAssetError::SizeMismatch(expected_len, result.len()));
+    }
+    Ok(result)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Raw,
+    Rle,
+}
+
+// build.rs が生成する画像の定数 (アプリ側からは images::<名前> で参照する)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageAsset {
+    pub width: u32,
+    pub height: u32,
+    pub encoding: Encoding,
+    pub data: &'static [u8],
+}
+
+impl ImageAsset {
+    pub fn decode(&self) -> Result<PackedImage, ImageAssetError> {
+        let expected_len = PackedImage::byte_count(self.width, self.height);
+        let pages = match self.encoding {
+            Encoding::Raw if self.data.len() == expected_len => self.data.to_vec(),
+            Encoding::Raw => return Err(ImageAssetError::SizeMismatch(expected_len, self.data.len())),
+            Encoding::Rle => rle_decode(self.data, expected_len)?,
+        };
+        Ok(PackedImage { width: self.width, height: self.height, pages })
+    }
+}
+
+// 画像アセットの変換設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageConvert {
+    dither: Dither,
+    compress: bool,
+    invert: bool,
+}
+
+impl ImageConvert {
+    // 既定は閾値 128 で、圧縮しない
+    pub const fn new() -> Self {
+        ImageConvert { dither: Dither::Threshold(128), compress: false, invert: false }
+    }
+
+    #[must_use]
+    pub fn dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    // RLE で圧縮する (小さくならない場合はそのまま)
+    #[must_use]
+    pub fn compress(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    // 暗い部分を点灯させる (白地に黒で描いた画像向け)
+    #[must_use]
+    pub fn invert(mut self, invert: bool) -> Self {
+        self.invert = invert;
+        self
+    }
+
+    pub fn convert(&self, image: &GrayImage) -> (Encoding, Vec<u8>) {
+        let packed = if self.invert {
+            let pixels = image.pixels.iter().map(|value| 255 - value).collect();
+            dither(&GrayImage { width: image.width, height: image.height, pixels }, self.dither)
+        } else {
+            dither(image, self.dither)
+        };
+        if self.compress {
+            let compressed = rle_encode(&packed.pages);
+            if compressed.len() < packed.pages.len() {
+                return (Encoding::Rle, compressed);
+            }
+        }
+        (Encoding::Raw, packed.pages)
+    }
+}
+
+impl Default for ImageConvert {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 名前を定数名 (大文字) とするソースを生成する
+pub fn to_rust_source(name: &str, image: &GrayImage, convert: &ImageConvert) -> String {
+    let (encoding, data) = convert.convert(image);
+    let mut source = String::new();
+    source.push_str(&format!("// {}x{}, {:?}, {} bytes\n", image.width, image.height, encoding, data.len()));
+    source.push_str(&format!("pub const {}: crate::image_asset::ImageAsset = crate::image_asset::ImageAsset {{\n", name.to_uppercase()));
+    source.push_str(&format!("    width: {},\n", image.width));
+    source.push_str(&format!("    height: {},\n", image.height));
+    source.push_str(&format!("    encoding: crate::image_asset::Encoding::{:?},\n", encoding));
+    source.push_str("    data: &[\n");
+    for chunk in data.chunks(16) {
+        let bytes: Vec<String> = chunk.iter().map(|byte| format!("0x{:02X}", byte)).collect();
+        source.push_str(&format!("        {},\n", bytes.join(", ")));
+    }
+    source.push_str("    ],\n");
+    source.push_str("};\n");
+    source
+}
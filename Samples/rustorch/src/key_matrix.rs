@@ -15,16 +15,8 @@ pub struct KeyMatrixPins {
     pub key_out2: AnyOutputPin,
 }
 
-pub struct Button;
-impl Button {
-    pub const UP:    u8 = 0x01;
-    pub const LEFT:  u8 = 0x02;
-    pub const DOWN:  u8 = 0x04;
-    pub const RIGHT: u8 = 0x08;
-    pub const A:     u8 = 0x10;
-    pub const B:     u8 = 0x20;
-    pub const MASK:  u8 = 0x3F; // マスク操作用
-}
+// ウィジェットなどハードウェア非依存のモジュールからも使うので別モジュールにしている
+pub use rustorch::button::Button;
 
 const KEY_STATUS_HISTORY_COUNT: usize = 3;
 
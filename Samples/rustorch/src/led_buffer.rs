@@ -0,0 +1,107 @@
+use std::sync::{Arc, Condvar, Mutex};
+
+// 7 セグ 4 桁分の表示内容 (セグメントパターンと輝度はセットで扱う)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LedFrame {
+    pub segments: [u8; 4],
+    // 0 ~ 100% の割合で表す
+    pub brightness: [u8; 4],
+}
+
+impl LedFrame {
+    // 点灯するセグメントが 1 つでもあるか
+    pub fn is_visible(&self) -> bool {
+        self.segments.iter().zip(self.brightness.iter())
+            .any(|(&segments, &brightness)| segments != 0 && brightness > 0)
+    }
+}
+
+impl Default for LedFrame {
+    fn default() -> Self {
+        LedFrame {
+            segments: [ 0, 0, 0, 0 ],
+            brightness: [ 100, 100, 100, 100 ],
+        }
+    }
+}
+
+// 表示データのダブルバッファ
+// - アプリ側はバックバッファを書き換え、commit() でフロントバッファへまとめて反映する
+// - ダイナミック点灯側は LedFrameScanner 経由で 0 桁目の境界でのみフロントバッファを取り込む
+//   --> 1 回の走査 (4 桁分) の途中で表示内容と輝度が切り替わることはない
+// - 何も表示しない間はダイナミック点灯側を待たせておき、コミットで起こす
+pub struct LedFrameBuffer {
+    back: LedFrame,
+    front: Arc<(Mutex<LedFrame>, Condvar)>,
+}
+
+impl LedFrameBuffer {
+    pub fn new() -> Self {
+        LedFrameBuffer {
+            back: LedFrame::default(),
+            front: Arc::new((Mutex::new(LedFrame::default()), Condvar::new())),
+        }
+    }
+
+    pub fn back(&self) -> &LedFrame {
+        &self.back
+    }
+
+    pub fn back_mut(&mut self) -> &mut LedFrame {
+        &mut self.back
+    }
+
+    // バックバッファの内容を表示内容・輝度まとめてフロントバッファへ反映する
+    // 毎フレーム呼ばれる想定なので、内容が変わった時だけ待機中の点灯スレッドを起こす
+    pub fn commit(&mut self) {
+        let (front, condvar) = &*self.front;
+        let mut locked = front.lock().unwrap();
+        if *locked != self.back {
+            *locked = self.back;
+            condvar.notify_all();
+        }
+    }
+
+    // ダイナミック点灯スレッドに渡す読み出し口を生成する
+    pub fn scanner(&self) -> LedFrameScanner {
+        LedFrameScanner {
+            front: Arc::clone(&self.front),
+            latched: *self.front.0.lock().unwrap(),
+        }
+    }
+}
+
+impl Default for LedFrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct LedFrameScanner {
+    front: Arc<(Mutex<LedFrame>, Condvar)>,
+    latched: LedFrame,
+}
+
+impl LedFrameScanner {
+    // 指定桁の (セグメントパターン, 輝度) を返す
+    // 0 桁目の時だけフロントバッファを取り込み、残りの桁は取り込み済みの内容を使う
+    pub fn digit(&mut self, index: usize) -> (u8, u8) {
+        if index == 0 {
+            self.latched = *self.front.0.lock().unwrap();
+        }
+        (self.latched.segments[index], self.latched.brightness[index])
+    }
+
+    // 取り込み済みの内容に点灯するセグメントがあるか
+    pub fn is_visible(&self) -> bool {
+        self.latched.is_visible()
+    }
+
+    // 点灯するセグメントがある内容がコミットされるまで待つ
+    // 戻った時点で取り込み済みとなるので、続けて 0 桁目から走査すればよい
+    pub fn wait_until_visible(&mut self) {
+        let (front, condvar) = &*self.front;
+        let locked = condvar.wait_while(front.lock().unwrap(), |frame| !frame.is_visible()).unwrap();
+        self.latched = *locked;
+    }
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+
+// FreeRTOS の tick 周波数 (sdkconfig のデフォルト値)
+// 1000HZ 設定なので tick == ミリ秒
+pub const TICK_RATE_HZ: u32 = 1000;
+
+// 4 桁のダイナミック点灯
+pub const DIGIT_COUNT: u32 = 4;
+
+// デッドタイムの下限 [us]
+// led-pwm では消灯 (デューティ 0) が次の PWM 周期 (10kHz => 100us) から反映されるので、1 周期以上待たないと前の桁が残る
+#[cfg(feature = "led-pwm")]
+pub const MIN_DEAD_TIME_US: u32 = 100;
+#[cfg(not(feature = "led-pwm"))]
+pub const MIN_DEAD_TIME_US: u32 = 0;
+
+// 既定のデューティ (led-pwm ではデッドタイムの分だけ 1 桁の周期 4ms から削る)
+#[cfg(feature = "led-pwm")]
+const DEFAULT_DUTY_CYCLE_PERCENT: u8 = 97;
+#[cfg(not(feature = "led-pwm"))]
+const DEFAULT_DUTY_CYCLE_PERCENT: u8 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedConfigError {
+    // 桁の切り替え周期が tick 単位 (1ms) で表現できない
+    RefreshRateOutOfRange(u32),
+    // 0% や 100% 超過は指定不可
+    DutyCycleOutOfRange(u8),
+    // デッドタイム + 最大点灯時間が 1 桁の周期に収まらない
+    DeadTimeTooLong(u32),
+    // デッドタイムが MIN_DEAD_TIME_US より短い
+    DeadTimeTooShort(u32),
+}
+
+impl fmt::Display for LedConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedConfigError::RefreshRateOutOfRange(hz) => write!(f, "refresh rate {} Hz is out of range (1 ~ {} Hz)", hz, LedConfig::MAX_REFRESH_RATE_HZ),
+            LedConfigError::DutyCycleOutOfRange(percent) => write!(f, "duty cycle {}% is out of range (1 ~ 100%)", percent),
+            LedConfigError::DeadTimeTooLong(us) => write!(f, "dead time {} us does not fit in the digit period", us),
+            LedConfigError::DeadTimeTooShort(us) => write!(f, "dead time {} us is shorter than {} us", us, MIN_DEAD_TIME_US),
+        }
+    }
+}
+
+impl std::error::Error for LedConfigError {}
+
+// 7 セグのダイナミック点灯の設定
+// - refresh_rate_hz: 4 桁全体を 1 周する周波数
+// - duty_cycle_percent: 1 桁の周期のうち点灯に使ってよい割合の上限 (輝度 100% の時の点灯時間)
+// - dead_time_us: 全桁を消灯してから次の桁のセグメント線を切り替えるまでの待ち時間 (ゴースト対策)
+// - measure_timing: 有効にすると 1 桁毎の点灯タイミングのジッタを計測してログに出す
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LedConfig {
+    pub refresh_rate_hz: u32,
+    pub duty_cycle_percent: u8,
+    pub dead_time_us: u32,
+    pub measure_timing: bool,
+}
+
+impl LedConfig {
+    // 1 桁の周期は最短 1 tick
+    pub const MAX_REFRESH_RATE_HZ: u32 = TICK_RATE_HZ / DIGIT_COUNT;
+
+    // 従来の固定値 (1 桁 4ms => 62.5Hz) 相当
+    pub const fn new() -> Self {
+        LedConfig {
+            refresh_rate_hz: 62,
+            duty_cycle_percent: DEFAULT_DUTY_CYCLE_PERCENT,
+            dead_time_us: MIN_DEAD_TIME_US,
+            measure_timing: false,
+        }
+    }
+
+    #[must_use]
+    pub fn refresh_rate(mut self, hz: u32) -> Self {
+        self.refresh_rate_hz = hz;
+        self
+    }
+
+    #[must_use]
+    pub fn duty_cycle(mut self, percent: u8) -> Self {
+        self.duty_cycle_percent = percent;
+        self
+    }
+
+    #[must_use]
+    pub fn dead_time(mut self, us: u32) -> Self {
+        self.dead_time_us = us;
+        self
+    }
+
+    #[must_use]
+    pub fn measure_timing(mut self, enable: bool) -> Self {
+        self.measure_timing = enable;
+        self
+    }
+
+    pub fn validate(&self) -> Result<(), LedConfigError> {
+        if self.refresh_rate_hz == 0 || self.refresh_rate_hz > Self::MAX_REFRESH_RATE_HZ {
+            return Err(LedConfigError::RefreshRateOutOfRange(self.refresh_rate_hz));
+        }
+        if self.duty_cycle_percent == 0 || self.duty_cycle_percent > 100 {
+            return Err(LedConfigError::DutyCycleOutOfRange(self.duty_cycle_percent));
+        }
+        #[cfg(feature = "led-pwm")]
+        if self.dead_time_us < MIN_DEAD_TIME_US {
+            return Err(LedConfigError::DeadTimeTooShort(self.dead_time_us));
+        }
+        if self.dead_time_us + self.max_on_time_us() > self.digit_period_us() {
+            return Err(LedConfigError::DeadTimeTooLong(self.dead_time_us));
+        }
+        Ok(())
+    }
+
+    // 1 桁あたりの点灯周期 [tick]
+    // tick 単位に丸めるので実際のリフレッシュレートは指定値に最も近い値となる
+    pub fn digit_period_ticks(&self) -> u32 {
+        let divisor = self.refresh_rate_hz.max(1) * DIGIT_COUNT;
+        ((TICK_RATE_HZ + divisor / 2) / divisor).max(1)
+    }
+
+    pub fn digit_period_us(&self) -> u32 {
+        self.digit_period_ticks() * (1_000_000 / TICK_RATE_HZ)
+    }
+
+    // 丸め後の実際のリフレッシュレート [mHz]
+    pub fn actual_refresh_rate_mhz(&self) -> u32 {
+        1_000_000_000 / (self.digit_period_us() * DIGIT_COUNT)
+    }
+
+    // 輝度 100% の時の点灯時間 [us]
+    pub fn max_on_time_us(&self) -> u32 {
+        self.digit_period_us() * self.duty_cycle_percent.min(100) as u32 / 100
+    }
+
+    // 輝度 (0 ~ 100%) に対応する点灯時間 [us]
+    pub fn on_time_us(&self, brightness: u8) -> u32 {
+        self.max_on_time_us() * brightness.min(100) as u32 / 100
+    }
+}
+
+impl Default for LedConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 計測モード用: 桁の点灯開始間隔の理想値からのずれを集計する
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimingStats {
+    pub count: u32,
+    pub min_jitter_us: i64,
+    pub max_jitter_us: i64,
+    pub sum_abs_jitter_us: u64,
+}
+
+impl TimingStats {
+    pub const fn new() -> Self {
+        TimingStats {
+            count: 0,
+            min_jitter_us: i64::MAX,
+            max_jitter_us: i64::MIN,
+            sum_abs_jitter_us: 0,
+        }
+    }
+
+    // 実測の間隔と期待値 (1 桁の周期) を記録する
+    pub fn record(&mut self, interval_us: i64, expected_us: i64) {
+        let jitter = interval_us - expected_us;
+        self.count += 1;
+        self.min_jitter_us = self.min_jitter_us.min(jitter);
+        self.max_jitter_us = self.max_jitter_us.max(jitter);
+        self.sum_abs_jitter_us += jitter.unsigned_abs();
+    }
+
+    pub fn mean_abs_jitter_us(&self) -> u64 {
+        if self.count == 0 {
+            0
+        } else {
+            self.sum_abs_jitter_us / self.count as u64
+        }
+    }
+}
+
+impl Default for TimingStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -2,28 +2,42 @@
 use esp_idf_hal::gpio::PinDriver;
 use esp_idf_hal::gpio::AnyOutputPin;
 use std::thread;
-use std::sync::{Arc, Mutex};
 
 use esp_idf_sys::xTaskDelayUntil;
 use esp_idf_sys::xTaskGetTickCount;
 use esp_idf_sys::TickType_t;
+use esp_idf_hal::delay::Ets;
+use std::sync::{Arc, Mutex};
 
-use esp_idf_hal::timer::*;
+#[cfg(not(feature = "led-pwm"))]
+use esp_idf_hal::gpio::Output;
+#[cfg(not(feature = "led-pwm"))]
+use esp_idf_hal::timer::{TimerDriver, TIMER10};
+#[cfg(not(feature = "led-pwm"))]
 use esp_idf_hal::task::notification::Notification;
+#[cfg(not(feature = "led-pwm"))]
 use std::num::NonZeroU32;
 
-const NUMBER_SEGMENT_TABLE: [u8; 10] = [
-    0xFC,   // 0
-    0x60,   // 1
-    0xDA,   // 2
-    0xF2,   // 3
-    0x66,   // 4
-    0xB6,   // 5
-    0xBE,   // 6
-    0xE4,   // 7
-    0xFE,   // 8
-    0xF6,   // 9
-];
+#[cfg(feature = "led-pwm")]
+use esp_idf_hal::ledc::*;
+#[cfg(feature = "led-pwm")]
+use esp_idf_hal::ledc::config::TimerConfig;
+#[cfg(feature = "led-pwm")]
+use esp_idf_hal::units::Hertz;
+
+use rustorch::led_buffer::LedFrameBuffer;
+use rustorch::led_config::{LedConfig, LedConfigError, TimingStats};
+use rustorch::led_format::*;
+
+// 計測モード時にジッタの集計結果をログに出す間隔 [桁]
+const TIMING_REPORT_INTERVAL: u32 = 1000;
+
+// PWM による輝度制御の設定 (led-pwm フィーチャ有効時)
+// 桁の切り替え周期よりも十分速くしておく
+#[cfg(feature = "led-pwm")]
+const PWM_FREQUENCY: Hertz = Hertz(10_000);
+#[cfg(feature = "led-pwm")]
+const PWM_RESOLUTION: Resolution = Resolution::Bits12;
 
 pub struct LedPins {
     pub seg_a: AnyOutputPin,
@@ -40,22 +54,176 @@ pub struct LedPins {
     pub seg_digit4: AnyOutputPin,
 }
 
+// 7 セグ 1 桁毎の輝度制御に使用するペリフェラル
+// - デフォルト: 汎用タイマ (TIMER10) の割り込みで点灯時間を計る
+// - led-pwm フィーチャ: LEDC の PWM で桁選択線を直接ゲートする (汎用タイマは不要になる)
+#[cfg(not(feature = "led-pwm"))]
+pub struct LedDimmer {
+    pub timer: TIMER10,
+}
+
+#[cfg(feature = "led-pwm")]
+pub struct LedDimmer {
+    pub timer: TIMER1,
+    pub channel_digit1: CHANNEL1,
+    pub channel_digit2: CHANNEL2,
+    pub channel_digit3: CHANNEL3,
+    pub channel_digit4: CHANNEL4,
+}
+
+// 桁選択線の ON/OFF と輝度制御
+trait DigitGate {
+    // 指定桁を 1 桁の周期 period_us のうち on_time_us の間だけ点灯する
+    fn light(&mut self, digit: usize, on_time_us: u32, period_us: u32) -> anyhow::Result<()>;
+    // 全桁を消灯する
+    fn turn_off_all(&mut self) -> anyhow::Result<()>;
+}
+
+// 汎用タイマのワンショット割り込みで点灯時間を計る方式
+// 点灯時間が経過するまでタスクが待つので light() から戻った時点で消灯済みとなる
+#[cfg(not(feature = "led-pwm"))]
+struct TimerDigitGate {
+    digits: [PinDriver<'static, AnyOutputPin, Output>; 4],
+    timer: TimerDriver<'static>,
+    notification: Notification,
+}
+
+#[cfg(not(feature = "led-pwm"))]
+impl TimerDigitGate {
+    fn new(digit_pins: [AnyOutputPin; 4], dimmer: LedDimmer) -> anyhow::Result<Self> {
+        let [ digit1, digit2, digit3, digit4 ] = digit_pins;
+        let digits = [
+            PinDriver::output(digit1)?,
+            PinDriver::output(digit2)?,
+            PinDriver::output(digit3)?,
+            PinDriver::output(digit4)?,
+        ];
+
+        // 7 セグ 1 桁毎の輝度制御に使用 (us ~ ms) 
+        let timer_config = esp_idf_hal::timer::config::Config::new().auto_reload(false).divider(2); // Divider の最小値は 2
+        let mut timer = TimerDriver::new(dimmer.timer, &timer_config)?;
+
+        let notification = Notification::new();
+        let notifer = notification.notifier();
+        unsafe {
+            timer.subscribe(move || {
+                notifer.notify_and_yield(NonZeroU32::new(1).unwrap());
+            })?;
+        }
+
+        Ok(TimerDigitGate { digits, timer, notification })
+    }
+}
+
+#[cfg(not(feature = "led-pwm"))]
+impl DigitGate for TimerDigitGate {
+    fn light(&mut self, digit: usize, on_time_us: u32, _period_us: u32) -> anyhow::Result<()> {
+        // 1秒あたり timer.tick_hz() だけカウントされる
+        // 1usあたり timer.tick_hz() / 1000_000 だけカウントされる
+        let us_per_count = self.timer.tick_hz() / 1000_000;
+        let counter_value = on_time_us as u64 * us_per_count;
+
+        self.timer.set_alarm(counter_value)?;
+        self.timer.set_counter(0)?;
+        self.timer.enable_interrupt()?;
+        self.timer.enable_alarm(true)?;
+        self.timer.enable(true)?;
+
+        self.digits[digit].set_high()?;
+
+        // auto_reload 無しなので割り込み後にタイマは自動停止するはず
+        let _ = self.notification.wait(esp_idf_hal::delay::BLOCK);
+
+        self.turn_off_all()
+    }
+
+    fn turn_off_all(&mut self) -> anyhow::Result<()> {
+        for digit in self.digits.iter_mut() {
+            digit.set_low()?;
+        }
+        Ok(())
+    }
+}
+
+// LEDC の PWM で桁選択線をゲートする方式
+// デューティを設定した後はハードウェアが点灯を維持するので light() は即座に戻る
+// 割り込みもタスクの追加起床も発生しない
+// デューティの変更 (turn_off_all() を含む) は次の PWM 周期から反映されるので、
+// 消灯してから次の桁のセグメントを出力するまで PWM の 1 周期 (100us) 以上待つ (LedConfig の MIN_DEAD_TIME_US)
+#[cfg(feature = "led-pwm")]
+struct PwmDigitGate<'d> {
+    digits: [LedcDriver<'d>; 4],
+}
+
+#[cfg(feature = "led-pwm")]
+impl<'d> PwmDigitGate<'d> {
+    fn new(
+        digit_pins: [AnyOutputPin; 4],
+        timer: &'d LedcTimerDriver<'d, TIMER1>,
+        channels: (CHANNEL1, CHANNEL2, CHANNEL3, CHANNEL4),
+    ) -> anyhow::Result<Self> {
+        let [ digit1, digit2, digit3, digit4 ] = digit_pins;
+        let digits = [
+            LedcDriver::new(channels.0, timer, digit1)?,
+            LedcDriver::new(channels.1, timer, digit2)?,
+            LedcDriver::new(channels.2, timer, digit3)?,
+            LedcDriver::new(channels.3, timer, digit4)?,
+        ];
+        let mut gate = PwmDigitGate { digits };
+        gate.turn_off_all()?;
+        Ok(gate)
+    }
+}
+
+#[cfg(feature = "led-pwm")]
+impl DigitGate for PwmDigitGate<'_> {
+    fn light(&mut self, digit: usize, on_time_us: u32, period_us: u32) -> anyhow::Result<()> {
+        // 1 桁の周期に占める点灯時間の割合をそのまま PWM のデューティにする
+        let max_duty = self.digits[digit].get_max_duty();
+        let duty = (max_duty as u64 * on_time_us.min(period_us) as u64 / period_us.max(1) as u64) as u32;
+        self.digits[digit].set_duty(duty)?;
+        Ok(())
+    }
+
+    fn turn_off_all(&mut self) -> anyhow::Result<()> {
+        for digit in self.digits.iter_mut() {
+            digit.set_duty(0)?;
+        }
+        Ok(())
+    }
+}
+
+// 書き込み系 API (write_data, set_brightness 等) はバックバッファへの書き込みのみ行う
+// commit() を呼び出した時点で表示内容と輝度がまとめて反映される
+// 何も点灯しない内容がコミットされている間はダイナミック点灯を休止する (次のコミットで再開)
 pub struct LedDriver {
-    display_data: Arc<Mutex<[u8; 4]>>,
-    brightness: Arc<Mutex<[u8; 4]>>,
+    frame_buffer: LedFrameBuffer,
+    config: Arc<Mutex<LedConfig>>,
 }
 
 impl LedDriver {
     pub fn new() -> Self {
         LedDriver {
-            display_data: Arc::new(Mutex::new([ 0, 0, 0, 0 ])),
-            brightness: Arc::new(Mutex::new([ 100, 100, 100, 100 ])),
+            frame_buffer: LedFrameBuffer::new(),
+            config: Arc::new(Mutex::new(LedConfig::new())),
         }
     }
 
-    pub fn start_dynamic_lighting(&mut self, pins: LedPins, timer10: TIMER10) -> anyhow::Result<()> {
-        let display_data_clone = Arc::clone(&self.display_data);
-        let brightness_clone = Arc::clone(&self.brightness);
+    // リフレッシュレート・デューティ・デッドタイム・計測モードの設定
+    // 点灯中に呼び出した場合は次の 0 桁目の走査から反映される
+    pub fn set_config(&mut self, config: LedConfig) -> Result<(), LedConfigError> {
+        config.validate()?;
+        *self.config.lock().unwrap() = config;
+        Ok(())
+    }
+
+    pub fn get_config(&self) -> LedConfig {
+        *self.config.lock().unwrap()
+    }
+
+    pub fn start_dynamic_lighting(&mut self, pins: LedPins, dimmer: LedDimmer) -> anyhow::Result<()> {
+        let mut scanner = self.frame_buffer.scanner();
+        let config_clone = Arc::clone(&self.config);
 
         let _ = thread::spawn(move || -> anyhow::Result<()> {
             let mut seg_a = PinDriver::output(pins.seg_a)?;
@@ -66,32 +234,58 @@ impl LedDriver {
             let mut seg_f = PinDriver::output(pins.seg_f)?;
             let mut seg_g = PinDriver::output(pins.seg_g)?;
             let mut seg_dot = PinDriver::output(pins.seg_dot)?;
-            let mut seg_digit1 = PinDriver::output(pins.seg_digit1)?;
-            let mut seg_digit2 = PinDriver::output(pins.seg_digit2)?;
-            let mut seg_digit3 = PinDriver::output(pins.seg_digit3)?;
-            let mut seg_digit4 = PinDriver::output(pins.seg_digit4)?;
-        
-            // タイマ本数が余ってないので周期は固定
-            // 1000HZ 設定なので tick == ミリ秒
-            const PERIOD: TickType_t = 4;
-
-            // 7 セグ 1 桁毎の輝度制御に使用 (us ~ ms) 
-            let timer_config = esp_idf_hal::timer::config::Config::new().auto_reload(false).divider(2); // Divider の最小値は 2
-            let mut timer = esp_idf_hal::timer::TimerDriver::new(timer10, &timer_config)?;
-
-            let notification = Notification::new();
-            let notifer = notification.notifier();
-            unsafe {
-                timer.subscribe(move || {
-                    notifer.notify_and_yield(NonZeroU32::new(1).unwrap());
-                })?;
-            }
+
+            // 桁選択線は輝度制御の方式に応じてタイマ + GPIO か LEDC で駆動する
+            let digit_pins = [ pins.seg_digit1, pins.seg_digit2, pins.seg_digit3, pins.seg_digit4 ];
+            #[cfg(not(feature = "led-pwm"))]
+            let mut digit_gate = TimerDigitGate::new(digit_pins, dimmer)?;
+            #[cfg(feature = "led-pwm")]
+            let LedDimmer { timer, channel_digit1, channel_digit2, channel_digit3, channel_digit4 } = dimmer;
+            #[cfg(feature = "led-pwm")]
+            let ledc_timer = LedcTimerDriver::new(timer, &TimerConfig::new().resolution(PWM_RESOLUTION).frequency(PWM_FREQUENCY))?;
+            #[cfg(feature = "led-pwm")]
+            let mut digit_gate = PwmDigitGate::new(
+                digit_pins,
+                &ledc_timer,
+                (channel_digit1, channel_digit2, channel_digit3, channel_digit4),
+            )?;
 
             let mut last_wake_time: TickType_t = unsafe { xTaskGetTickCount() };
+
+            let mut config = *config_clone.lock().unwrap();
+            let mut timing_stats = TimingStats::new();
+            let mut last_light_time_us: Option<i64> = None;
             
             let mut i = 0;
             loop {
-                let bit_pattern = display_data_clone.lock().unwrap()[(i % 4) as usize];
+                // 0 桁目の境界でのみ最新のコミット内容が取り込まれる
+                let (mut bit_pattern, mut brightness) = scanner.digit((i % 4) as usize);
+
+                // 全消灯 (clear() 後や輝度 0%) の間は定周期の起床を止め、次に表示内容がコミットされるまで待つ
+                if i % 4 == 0 && !scanner.is_visible() {
+                    digit_gate.turn_off_all()?;
+                    log::debug!("[led] idle");
+                    scanner.wait_until_visible();
+                    log::debug!("[led] resume");
+
+                    // 休止中に変わった設定も取り込み直し、0 桁目から走査し直す
+                    last_wake_time = unsafe { xTaskGetTickCount() };
+                    last_light_time_us = None;
+                    i = 0;
+                    (bit_pattern, brightness) = scanner.digit(0);
+                }
+
+                // 設定も表示内容と同じく 0 桁目の境界でのみ取り込む (休止から戻った直後を含む)
+                if i % 4 == 0 {
+                    let latest = *config_clone.lock().unwrap();
+                    if latest != config {
+                        log::info!("[led] config: {:?} (actual {} mHz)", latest, latest.actual_refresh_rate_mhz());
+                        config = latest;
+                        timing_stats = TimingStats::new();
+                        last_light_time_us = None;
+                    }
+                }
+
                 if (bit_pattern & ((1 as u8) << 7)) != 0 { seg_a.set_high()? } else { seg_a.set_low()?; }
                 if (bit_pattern & ((1 as u8) << 6)) != 0 { seg_b.set_high()? } else { seg_b.set_low()?; }
                 if (bit_pattern & ((1 as u8) << 5)) != 0 { seg_c.set_high()? } else { seg_c.set_low()?; }
@@ -100,52 +294,44 @@ impl LedDriver {
                 if (bit_pattern & ((1 as u8) << 2)) != 0 { seg_f.set_high()? } else { seg_f.set_low()?; }
                 if (bit_pattern & ((1 as u8) << 1)) != 0 { seg_g.set_high()? } else { seg_g.set_low()?; }
                 if (bit_pattern & ((1 as u8) << 0)) != 0 { seg_dot.set_high()? } else { seg_dot.set_low()?; }
-            
-                // 0 ~ 100% の割合で表す
-                let brightness = brightness_clone.lock().unwrap()[(i % 4) as usize];
-
-                // 1秒あたり timer.tick_hz() だけカウントされる
-                // 1usあたり timer.tick_hz() / 1000_000 だけカウントされる
-                // 100% の時 PERIOD*1000 [us] で、0% の時 0 [us] とする
-                let to_counter_value = |_percent: u8| -> u64 {
-                    let us_per_count = timer.tick_hz() / 1000_000;
-                    (PERIOD as u64 * 1000 * _percent as u64 / 100) * us_per_count
-                };
-                
+
                 if i % 25 == 0 {
-                    log::debug!("[led] br: {}%, cnt: {}", brightness, to_counter_value(brightness));
+                    log::debug!("[led] br: {}%", brightness);
                 }
 
-                if brightness > 0 {
-                    timer.set_alarm(to_counter_value(brightness))?;
-                    timer.set_counter(0)?;
-                    timer.enable_interrupt()?;
-                    timer.enable_alarm(true)?;
-                    timer.enable(true)?;
-
-                    // ON 期間
-                    match i % 4 {
-                        0 => seg_digit1.set_high()?,
-                        1 => seg_digit2.set_high()?,
-                        2 => seg_digit3.set_high()?,
-                        3 => seg_digit4.set_high()?,
-                        _ => (),
+                // 計測モード: 桁の切り替え間隔を記録する
+                if config.measure_timing {
+                    let now_us = unsafe { esp_idf_sys::esp_timer_get_time() };
+                    if let Some(last_us) = last_light_time_us {
+                        timing_stats.record(now_us - last_us, config.digit_period_us() as i64);
                     }
+                    last_light_time_us = Some(now_us);
 
-                    // auto_reload 無しなので割り込み後にタイマは自動停止するはず
-                    let _ = notification.wait(esp_idf_hal::delay::BLOCK);
+                    if timing_stats.count >= TIMING_REPORT_INTERVAL {
+                        log::info!("[led] jitter: min {} us, max {} us, mean |{}| us ({} digits, period {} us)",
+                            timing_stats.min_jitter_us, timing_stats.max_jitter_us,
+                            timing_stats.mean_abs_jitter_us(), timing_stats.count, config.digit_period_us());
+                        timing_stats = TimingStats::new();
+                    }
+                }
+
+                // ON 期間
+                let on_time_us = config.on_time_us(brightness);
+                if on_time_us > 0 {
+                    digit_gate.light((i % 4) as usize, on_time_us, config.digit_period_us())?;
                 }
 
-                // OFF 期間
-                seg_digit1.set_low()?;
-                seg_digit2.set_low()?;
-                seg_digit3.set_low()?;
-                seg_digit4.set_low()?;
-                
                 unsafe {
-                    xTaskDelayUntil(&mut last_wake_time, PERIOD)
+                    xTaskDelayUntil(&mut last_wake_time, config.digit_period_ticks() as TickType_t)
                 };
 
+                // OFF 期間 (次の桁のセグメントを出力する前に全桁を消灯しておく)
+                // 消灯が反映されるまで待ってからセグメント線を切り替える (前の桁に次の桁の表示が出ないように)
+                digit_gate.turn_off_all()?;
+                if config.dead_time_us > 0 {
+                    Ets::delay_us(config.dead_time_us);
+                }
+
                 i += 1;
             }
         });
@@ -153,7 +339,7 @@ impl LedDriver {
     }
 
     pub fn write_data(&mut self, data: [u8; 4]) {
-        *self.display_data.lock().unwrap() = data;
+        self.frame_buffer.back_mut().segments = data;
     }
 
     fn parse(format: &String) -> Option<[u8; 4]> {
@@ -195,11 +381,52 @@ impl LedDriver {
         self.write_data(data.unwrap());
     }
 
+    // 符号付き整数 (-999 ~ 9999)
+    pub fn write_integer(&mut self, value: i32, padding: Padding) {
+        self.write_data(format_integer(value, padding));
+    }
+
+    // 固定小数点数 (例: value = 1234, decimals = 1 -> "123.4")
+    pub fn write_fixed(&mut self, value: i32, decimals: u8, padding: Padding) {
+        self.write_data(format_fixed(value, decimals, padding));
+    }
+
+    // 分.秒 (MM.SS)
+    pub fn write_minutes_seconds(&mut self, total_seconds: u32, colon: bool, padding: Padding) {
+        self.write_data(format_minutes_seconds(total_seconds, colon, padding));
+    }
+
+    // 時.分 (HH.MM)
+    pub fn write_hours_minutes(&mut self, total_minutes: u32, colon: bool, padding: Padding) {
+        self.write_data(format_hours_minutes(total_minutes, colon, padding));
+    }
+
+    // 16 進数 (0 ~ FFFF)
+    pub fn write_hex(&mut self, value: u32, padding: Padding) {
+        self.write_data(format_hex(value, padding));
+    }
+
+    // パーセント (0 ~ 999%)
+    pub fn write_percent(&mut self, percent: u32, padding: Padding) {
+        self.write_data(format_percent(percent, padding));
+    }
+
     pub fn clear(&mut self) {
         self.write_data([ 0, 0, 0, 0 ]);
     }
 
     pub fn set_brightness(&mut self, brightness: [u8; 4]) {
-        *self.brightness.lock().unwrap() = brightness;
+        self.frame_buffer.back_mut().brightness = brightness;
+    }
+
+    // バックバッファの輝度 (次の commit() で反映されるもの)
+    pub fn get_brightness(&self) -> [u8; 4] {
+        self.frame_buffer.back().brightness
+    }
+
+    // バックバッファの表示内容と輝度をまとめて点灯スレッドへ反映する
+    // 反映されるのは次の 0 桁目の走査からなので、表示が途中で混ざることはない
+    pub fn commit(&mut self) {
+        self.frame_buffer.commit();
     }
 }
@@ -0,0 +1,152 @@
+// 7 セグ 4 桁向けの数値フォーマット
+// ビット配置は LedDriver と同じ (bit7: a, bit6: b, ... bit1: g, bit0: dot)
+
+pub const NUMBER_SEGMENT_TABLE: [u8; 10] = [
+    0xFC,   // 0
+    0x60,   // 1
+    0xDA,   // 2
+    0xF2,   // 3
+    0x66,   // 4
+    0xB6,   // 5
+    0xBE,   // 6
+    0xE4,   // 7
+    0xFE,   // 8
+    0xF6,   // 9
+];
+
+pub const HEX_SEGMENT_TABLE: [u8; 16] = [
+    0xFC,   // 0
+    0x60,   // 1
+    0xDA,   // 2
+    0xF2,   // 3
+    0x66,   // 4
+    0xB6,   // 5
+    0xBE,   // 6
+    0xE4,   // 7
+    0xFE,   // 8
+    0xF6,   // 9
+    0xEE,   // A
+    0x3E,   // b
+    0x9C,   // C
+    0x7A,   // d
+    0x9E,   // E
+    0x8E,   // F
+];
+
+pub const SEGMENT_DOT: u8 = 0x01;
+pub const SEGMENT_MINUS: u8 = 0x02;
+// 7 セグで % は表現できないので上半分の四角 (° 形) で代用する
+pub const SEGMENT_PERCENT: u8 = 0xC6;
+
+// 表示範囲の上限を超えた (上側の横棒 4 つ)
+pub const OVERFLOW_PATTERN: [u8; 4] = [ 0x80, 0x80, 0x80, 0x80 ];
+// 表示範囲の下限を超えた (下側の横棒 4 つ)
+pub const UNDERFLOW_PATTERN: [u8; 4] = [ 0x10, 0x10, 0x10, 0x10 ];
+
+// 上位桁の埋め方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Padding {
+    Zero,
+    Space,
+}
+
+// 数値を右詰めで out に書き込む
+// - 下位 min_digits 桁は常に数字を表示し、それより上位の桁は padding に従って埋める
+// - out に収まらなかった場合は false を返す
+fn put_number(out: &mut [u8], value: u32, radix: u32, min_digits: usize, padding: Padding) -> bool {
+    if min_digits > out.len() {
+        return false;
+    }
+    let mut rest = value;
+    for (nth, segment) in out.iter_mut().rev().enumerate() {
+        if rest == 0 && nth >= min_digits.max(1) {
+            *segment = match padding {
+                Padding::Zero => HEX_SEGMENT_TABLE[0],
+                Padding::Space => 0x00,
+            };
+        } else {
+            *segment = HEX_SEGMENT_TABLE[(rest % radix) as usize];
+            rest /= radix;
+        }
+    }
+    rest == 0
+}
+
+// 符号付きの数値を書き込む
+// 負の場合は先頭に '-' を付ける (ゼロ埋め時は最上位桁、空白埋め時は数字の直前)
+fn put_signed(out: &mut [u8; 4], value: i32, min_digits: usize, padding: Padding) -> bool {
+    if value >= 0 {
+        return put_number(out, value as u32, 10, min_digits, padding);
+    }
+    if !put_number(&mut out[1..], value.unsigned_abs(), 10, min_digits, padding) {
+        return false;
+    }
+    let first_digit = out[1..].iter().position(|&segment| segment != 0x00).map_or(4, |i| i + 1);
+    out[first_digit - 1] = SEGMENT_MINUS;
+    true
+}
+
+// 符号付き整数 (-999 ~ 9999)
+pub fn format_integer(value: i32, padding: Padding) -> [u8; 4] {
+    format_fixed(value, 0, padding)
+}
+
+// 固定小数点数
+// value を 10^decimals で割った値を表示する (例: value = 1234, decimals = 1 -> "123.4")
+// 小数点の左には最低 1 桁表示する (例: value = 5, decimals = 2 -> "0.05")
+pub fn format_fixed(value: i32, decimals: u8, padding: Padding) -> [u8; 4] {
+    if decimals > 3 {
+        return OVERFLOW_PATTERN;
+    }
+    let mut result = [ 0u8; 4 ];
+    if !put_signed(&mut result, value, decimals as usize + 1, padding) {
+        return if value < 0 { UNDERFLOW_PATTERN } else { OVERFLOW_PATTERN };
+    }
+    if decimals > 0 {
+        result[3 - decimals as usize] |= SEGMENT_DOT;
+    }
+    result
+}
+
+// 分.秒 (MM.SS, 最大 99 分 59 秒)
+// colon が true の時は 2 桁目のドットを点灯して区切りとする
+pub fn format_minutes_seconds(total_seconds: u32, colon: bool, padding: Padding) -> [u8; 4] {
+    format_sexagesimal(total_seconds, colon, padding)
+}
+
+// 時.分 (HH.MM, 最大 99 時間 59 分)
+pub fn format_hours_minutes(total_minutes: u32, colon: bool, padding: Padding) -> [u8; 4] {
+    format_sexagesimal(total_minutes, colon, padding)
+}
+
+fn format_sexagesimal(value: u32, colon: bool, padding: Padding) -> [u8; 4] {
+    let mut result = [ 0u8; 4 ];
+    if !put_number(&mut result[0..2], value / 60, 10, 1, padding) {
+        return OVERFLOW_PATTERN;
+    }
+    put_number(&mut result[2..4], value % 60, 10, 2, Padding::Zero);
+    if colon {
+        result[1] |= SEGMENT_DOT;
+    }
+    result
+}
+
+// 16 進数 (0 ~ FFFF)
+pub fn format_hex(value: u32, padding: Padding) -> [u8; 4] {
+    let mut result = [ 0u8; 4 ];
+    if !put_number(&mut result, value, 16, 1, padding) {
+        return OVERFLOW_PATTERN;
+    }
+    result
+}
+
+// パーセント (0 ~ 999%)
+// 3 桁の数値 + 4 桁目に % 記号
+pub fn format_percent(percent: u32, padding: Padding) -> [u8; 4] {
+    let mut result = [ 0u8; 4 ];
+    if !put_number(&mut result[0..3], percent, 10, 1, padding) {
+        return OVERFLOW_PATTERN;
+    }
+    result[3] = SEGMENT_PERCENT;
+    result
+}
@@ -0,0 +1,44 @@
+// ハードウェア非依存のモジュール
+// - rustorch-test と共有していて、アプリからは未使用の API も含むのでライブラリとして公開する
+// - ドライバとアプリ (main.rs 側) からは rustorch::<モジュール> で参照する
+
+pub mod button;
+
+pub mod led_buffer;
+pub mod led_config;
+pub mod led_format;
+
+pub mod note;
+pub mod score;
+pub mod mml;
+pub mod rtttl;
+pub mod midi;
+pub mod melodies;
+// build.rs で asserts/sounds/*.mid から変換した楽譜
+pub mod midi_tunes {
+    include!(concat!(env!("OUT_DIR"), "/midi_tunes.rs"));
+}
+pub mod pitch_effect;
+pub mod tone;
+pub mod voice;
+pub mod sound_effects;
+pub mod dnd;
+
+pub mod frame_buffer;
+pub mod bitmap_font;
+// build.rs で asserts/fonts/japanese.bdf から使う文字だけ取り出したフォント
+#[cfg(feature = "japanese-font")]
+pub mod japanese_font {
+    include!(concat!(env!("OUT_DIR"), "/japanese_font.rs"));
+}
+pub mod text;
+pub mod display_command;
+pub mod screenshot;
+pub mod image_asset;
+// build.rs で asserts/images の PNG/GIF から変換した画像
+pub mod images {
+    include!(concat!(env!("OUT_DIR"), "/images.rs"));
+}
+pub mod sprite;
+pub mod widget;
+pub mod grayscale;
@@ -3,6 +3,7 @@ use app_context::AppFramework;
 use esp_idf_hal::gpio::InputPin;
 use esp_idf_hal::gpio::OutputPin;
 use esp_idf_hal::peripherals::Peripherals;
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
 use std::sync::{Arc, Mutex};
 
 mod key_matrix;
@@ -10,9 +11,18 @@ use key_matrix::KeyMatrix;
 use key_matrix::KeyMatrixPins;
 use key_matrix::Button;
 
+// ハードウェア非依存のモジュールは lib.rs (rustorch-test と共有)
+use rustorch::led_format::Padding;
+use rustorch::led_config::LedConfig;
+use rustorch::sound_effects::SoundEffect;
+use rustorch::text::{HorizontalAlign, TextStyle, VerticalAlign};
+use rustorch::display_command::{DisplayFrame, SubmitPolicy};
+use rustorch::widget::{layout, layout::{Direction, Length}, ListMenu, Screen};
+
 mod led_driver;
 use led_driver::LedDriver;
 use led_driver::LedPins;
+use led_driver::LedDimmer;
 
 mod buzzer_driver;
 use buzzer_driver::BuzzerDriver;
@@ -23,6 +33,7 @@ use volume::Volume;
 mod display_driver;
 use display_driver::DisplayDriver;
 use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::Rectangle;
 
 mod app_context;
 
@@ -32,6 +43,10 @@ mod app_pomodoro_timer;
 use app_pomodoro_timer::PomodoroTimer;
 mod app_slot_game;
 use app_slot_game::SlotGame;
+mod app_settings;
+use app_settings::Settings;
+mod app_grayscale_benchmark;
+use app_grayscale_benchmark::GrayscaleBenchmark;
 
 use esp_idf_hal::delay::FreeRtos;
 
@@ -45,17 +60,27 @@ fn print_freertos_tasks() {
 }
 
 fn draw_menu(display: &Arc<Mutex<DisplayDriver>>, app_names: &Vec<&str>, selected_index: usize) {
-    let mut locked = display.lock().unwrap();
-    locked.clear().unwrap();
-    locked.draw_text("== Menu ==".to_string(), Point::new(0, 0)).unwrap();
-    for (i, name) in app_names.iter().enumerate() {
-        if i == selected_index {
-            locked.draw_text(format!("> {}", name), Point::new(0, (i + 1) as i32 * 10)).unwrap();
-        } else {
-            locked.draw_text(format!("  {}", name), Point::new(0, (i + 1) as i32 * 10)).unwrap();
-        }
-    }
-    locked.update().unwrap();
+    let mut frame = DisplayFrame::new();
+    frame.clear();
+    let title_style = TextStyle::new()
+        .inverted(true)
+        .align(HorizontalAlign::Center, VerticalAlign::Middle);
+    frame.draw_text_box("== Menu ==".to_string(), Rectangle::new(Point::zero(), Size::new(128, 10)), title_style);
+    // 選択中の行は白黒反転で強調する (項目が多ければスクロールする)
+    let mut screen = Screen::new();
+    let list_area = layout::stack(layout::SCREEN, Direction::Vertical, &[Length::Fixed(10), Length::Fill])[1];
+    screen.add(list_area, ListMenu::new(app_names).with_selected(selected_index));
+    screen.draw_into(&mut frame);
+    display.lock().unwrap().submit(frame, SubmitPolicy::Wait).unwrap();
+}
+
+// 画面の最下行に短いメッセージを重ねて表示する (次に画面全体を描き直すまで残る)
+fn toast(display: &Arc<Mutex<DisplayDriver>>, message: &str) {
+    let area = Rectangle::new(Point::new(0, 54), Size::new(128, 10));
+    let mut frame = DisplayFrame::new();
+    // 収まらない分は ... で省略する
+    frame.clear_region(area).draw_text_box(message.to_string(), area, TextStyle::new());
+    display.lock().unwrap().submit(frame, SubmitPolicy::Wait).unwrap();
 }
 
 fn main() -> anyhow::Result<()> {
@@ -86,8 +111,25 @@ fn main() -> anyhow::Result<()> {
             seg_digit3: peripherals.pins.gpio19.downgrade_output(),
             seg_digit4: peripherals.pins.gpio20.downgrade_output(),
         };
+        // 輝度制御の方式は led-pwm フィーチャで切り替える
+        #[cfg(not(feature = "led-pwm"))]
+        let led_dimmer = LedDimmer {
+            timer: peripherals.timer10,
+        };
+        #[cfg(feature = "led-pwm")]
+        let led_dimmer = LedDimmer {
+            timer: peripherals.ledc.timer1,
+            channel_digit1: peripherals.ledc.channel1,
+            channel_digit2: peripherals.ledc.channel2,
+            channel_digit3: peripherals.ledc.channel3,
+            channel_digit4: peripherals.ledc.channel4,
+        };
+        // 62.5Hz だとカメラ越しにちらつきが見えるので 125Hz で点灯する
+        // 全桁を消灯してから次の桁に切り替えるまでブランキング期間を設けてゴーストを防ぐ
+        let led_config = LedConfig::new().refresh_rate(125).duty_cycle(90).dead_time(100);
         let led_driver_clone = Arc::clone(&led_driver);
-        led_driver_clone.lock().unwrap().start_dynamic_lighting(led_pins, peripherals.timer10)?;
+        led_driver_clone.lock().unwrap().set_config(led_config)?;
+        led_driver_clone.lock().unwrap().start_dynamic_lighting(led_pins, led_dimmer)?;
     }
 
     let key_matrix = Arc::new(Mutex::new(KeyMatrix::new()));
@@ -110,11 +152,16 @@ fn main() -> anyhow::Result<()> {
         let scl = peripherals.pins.gpio7;
         let display_driver_clone = Arc::clone(&display_driver);
         display_driver_clone.lock().unwrap().start_thread(i2c0, sda, scl)?;
+        // true にすると画面を更新する度にシリアルコンソールへ出力する
+        // (ホストの rustorch-test で `cargo run --bin oled_capture -- --gif screen.gif log.txt` として画像にする)
+        const MIRROR_DISPLAY: bool = false;
+        if MIRROR_DISPLAY {
+            display_driver_clone.lock().unwrap().set_mirroring(true)?;
+        }
         {
-            let mut locked = display_driver.lock().unwrap();
-            locked.clear()?;
-            locked.draw_text("Rustorch startup...".to_string(), Point::new(0, 0))?;
-            locked.update()?;
+            let mut frame = DisplayFrame::new();
+            frame.clear().draw_text("Rustorch startup...".to_string(), Point::new(0, 0));
+            display_driver.lock().unwrap().submit(frame, SubmitPolicy::Wait)?;
         }
     }
 
@@ -125,6 +172,9 @@ fn main() -> anyhow::Result<()> {
         let timer0 = peripherals.ledc.timer0;
         let buzzer_driver_clone = Arc::clone(&buzzer_driver);
         buzzer_driver_clone.lock().unwrap().start_thread(buzzer_pin, channel0, timer0)?;
+        // おやすみモードと操作音の設定は再起動後も保持する (設定アプリで変更する)
+        let nvs = EspNvs::new(EspDefaultNvsPartition::take()?, "rustorch", true)?;
+        buzzer_driver_clone.lock().unwrap().attach_storage(nvs)?;
     }
 
     let volume = Arc::new(Mutex::new(Volume::new(peripherals.adc1, peripherals.pins.gpio5)));
@@ -144,6 +194,8 @@ fn main() -> anyhow::Result<()> {
         Box::new(PomodoroTimer::new()),
         Box::new(ToyPiano::new()),
         Box::new(SlotGame::new()),
+        Box::new(Settings::new()),
+        Box::new(GrayscaleBenchmark::new()),
     ];
     // 選択中のアプリケーション
     let mut selected_index = 0 as usize;
@@ -166,6 +218,8 @@ fn main() -> anyhow::Result<()> {
         "Pomodoro timer",
         "Toy piano",
         "Slot game",
+        "Settings",
+        "Gray benchmark",
     ];
     // メニュー画面を表示
     draw_menu(&context.display, &app_names, selected_index);
@@ -179,32 +233,70 @@ fn main() -> anyhow::Result<()> {
 
     let mut return_to_menu_time = 0u64;
 
+    // 全体の音量の段階 [%]
+    const MASTER_VOLUME_LEVELS: [u8; 4] = [ 10, 30, 60, 100 ];
+
+    // おやすみモード中の通知の表示時間 (LED を点滅させ、OLED の最下行にトーストを出す)
+    const VISUAL_ALERT_FRAMES: u64 = 60 * 3;
+    const VISUAL_ALERT_BLINK_FRAMES: u64 = 10;
+    let mut visual_alert_end_frame: Option<u64> = None;
+    // 点滅前の LED の輝度 (点滅が終わったら戻す)
+    let mut brightness_before_alert = [ 100u8; 4 ];
+
     loop {
         match menu_state {
             MenuState::Selection => {
                 let adc_value = context.volume.lock().unwrap().read_raw();
                 let percent = Volume::to_percent(adc_value) as u8;
 
-                let format = format!("{:3}.{:1}", (frame_count / 60) % 1000, frame_count / 6 % 10);
-                context.led.lock().unwrap().write_format(&format);
-                context.led.lock().unwrap().set_brightness([ percent, percent, percent, percent ]);
+                {
+                    let mut locked = context.led.lock().unwrap();
+                    // 経過秒数を 0.1 秒単位で表示 (999.9 秒で一周)
+                    locked.write_fixed((frame_count / 6 % 10000) as i32, 1, Padding::Space);
+                    locked.set_brightness([ percent, percent, percent, percent ]);
+                }
 
                 let button = context.button.lock().unwrap().was_released(Button::MASK);
                 let is_up_event = button & Button::UP != 0;
                 let is_down_event = button & Button::DOWN != 0;
                 let is_run_event = button & Button::A != 0;
+                // B でミュート切り替え、←→ で全体の音量を変更する (全アプリ共通)
+                // (おやすみモードは設定アプリで切り替える)
+                let is_mute_event = button & Button::B != 0;
+                let is_volume_event = button & (Button::LEFT | Button::RIGHT) != 0;
+                if is_mute_event || is_volume_event {
+                    let mut locked = context.buzzer.lock().unwrap();
+                    let master = locked.get_master_volume();
+                    if is_mute_event {
+                        locked.set_mute(!master.muted)?;
+                    }
+                    if is_volume_event {
+                        let index = MASTER_VOLUME_LEVELS.iter().position(|&level| level == master.volume_percent).unwrap_or(0);
+                        let index = if button & Button::RIGHT != 0 {
+                            (index + 1).min(MASTER_VOLUME_LEVELS.len() - 1)
+                        } else {
+                            index.saturating_sub(1)
+                        };
+                        locked.set_master_volume(MASTER_VOLUME_LEVELS[index])?;
+                    }
+                    locked.play_effect(SoundEffect::Click)?;
+                    let master = locked.get_master_volume();
+                    log::info!("[menu] master volume: {}% (muted: {})", master.volume_percent, master.muted);
+                }
                 if is_up_event || is_down_event {
                     let direction= if is_down_event { 1 } else { apps.len() - 1 };
                     selected_index = (selected_index + direction) % apps.len();
+                    context.buzzer.lock().unwrap().play_effect(SoundEffect::Click)?;
                     draw_menu(&context.display, &app_names, selected_index);
                     log::info!("[menu] Selection index: -> {}", selected_index);
                 }
                 if is_run_event {
                     // 共通処理
+                    context.buzzer.lock().unwrap().play_effect(SoundEffect::Confirm)?;
                     {
-                        let mut locked = context.display.lock().unwrap();
-                        locked.clear().unwrap();
-                        locked.update().unwrap();
+                        let mut frame = DisplayFrame::new();
+                        frame.clear();
+                        context.display.lock().unwrap().submit(frame, SubmitPolicy::Wait)?;
                     }
     
                     let app = &mut apps[selected_index];
@@ -219,6 +311,7 @@ fn main() -> anyhow::Result<()> {
 
                 if app.is_finished() || context.button.lock().unwrap().is_pressed_all() {
                     app.finalize(&context)?;
+                    context.buzzer.lock().unwrap().play_effect(SoundEffect::Cancel)?;
                     draw_menu(&context.display, &app_names, selected_index);
                     return_to_menu_time = frame_count + (60 / 2);   // 0.5秒待ち
                     menu_state = MenuState::ReturnToMenu;
@@ -235,6 +328,39 @@ fn main() -> anyhow::Result<()> {
             },
         }
 
+        // 静音時間に入ったら鳴っている音を止め、おやすみモードで鳴らさなかった通知を LED の点滅と OLED のトーストで知らせる
+        {
+            let alert = {
+                let mut locked = context.buzzer.lock().unwrap();
+                if frame_count % 60 == 0 {
+                    locked.update_dnd()?;
+                }
+                locked.take_visual_alert()
+            };
+            if let Some(effect) = alert {
+                toast(&context.display, &format!("! {}", effect.name()));
+                // 点滅中に次の通知が来た場合は最初の点滅前の輝度を保持したまま延長する
+                if visual_alert_end_frame.is_none() {
+                    brightness_before_alert = context.led.lock().unwrap().get_brightness();
+                }
+                visual_alert_end_frame = Some(frame_count + VISUAL_ALERT_FRAMES);
+            }
+        }
+        if let Some(end_frame) = visual_alert_end_frame {
+            let brightness = if frame_count >= end_frame {
+                visual_alert_end_frame = None;
+                brightness_before_alert
+            } else if frame_count / VISUAL_ALERT_BLINK_FRAMES % 2 == 0 {
+                [ 0; 4 ]
+            } else {
+                brightness_before_alert
+            };
+            context.led.lock().unwrap().set_brightness(brightness);
+        }
+
+        // 1 フレーム中の 7 セグへの書き込みをまとめて反映する
+        context.led.lock().unwrap().commit();
+
         // 次のフレームまで待つ
         loop {
             let current_time_us = unsafe { esp_idf_sys::esp_timer_get_time() };
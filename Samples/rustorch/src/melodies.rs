@@ -0,0 +1,12 @@
+// 組み込みのサンプル曲 (RTTTL 形式、著作権の切れた曲のみ)
+// 画像と同様にファイルをそのままバイナリへ埋め込む
+
+pub const ODE_TO_JOY: &str = include_str!("../asserts/sounds/ode_to_joy.rtttl");
+pub const TWINKLE: &str = include_str!("../asserts/sounds/twinkle.rtttl");
+pub const FUR_ELISE: &str = include_str!("../asserts/sounds/fur_elise.rtttl");
+
+pub const SAMPLE_TUNES: [&str; 3] = [
+    ODE_TO_JOY,
+    TWINKLE,
+    FUR_ELISE,
+];
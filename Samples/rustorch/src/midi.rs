@@ -0,0 +1,386 @@
+// Standard MIDI File (フォーマット 0/1) を単音の楽譜に変換する
+//
+// - トラックとチャンネルで演奏するノートを絞り込む (どちらも省略時は全て対象)
+// - 和音など音が重なる部分は VoicePolicy に従って 1 音だけ選ぶ
+// - テンポはどのトラックにあっても反映する (フォーマット 1 は先頭トラックにまとめるのが普通)
+// - 先頭の無音は詰める
+//
+// build.rs で変換してソースとして埋め込む (to_rust_source) ことも、
+// include_bytes! で埋め込んだファイルを実行時に変換することもできる
+
+use std::fmt;
+
+use crate::score::{midi_note_to_frequency, Score, ScoreEvent, TICKS_PER_QUARTER};
+
+// テンポ指定がない場合の初期テンポ (SMF の規定値)
+const DEFAULT_MICROSECONDS_PER_QUARTER: u32 = 500_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidiError {
+    // MThd/MTrk チャンクがない、または長さが不正
+    InvalidHeader,
+    UnsupportedFormat(u16),
+    // SMPTE 形式の時間単位は未対応
+    UnsupportedDivision(u16),
+    // データが途中で終わっている (トラック番号)
+    UnexpectedEnd(usize),
+    // ランニングステータスなしでデータバイトが現れた (トラック番号)
+    InvalidStatus(usize),
+    TrackNotFound(usize),
+    // 対象のノートが 1 つもない
+    NoNotes,
+}
+
+impl fmt::Display for MidiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MidiError::InvalidHeader => write!(f, "invalid SMF header"),
+            MidiError::UnsupportedFormat(format) => write!(f, "unsupported SMF format {}", format),
+            MidiError::UnsupportedDivision(division) => write!(f, "unsupported time division 0x{:04X}", division),
+            MidiError::UnexpectedEnd(track) => write!(f, "track {}: unexpected end of data", track),
+            MidiError::InvalidStatus(track) => write!(f, "track {}: missing status byte", track),
+            MidiError::TrackNotFound(track) => write!(f, "track {} not found", track),
+            MidiError::NoNotes => write!(f, "no notes in the selected track/channel"),
+        }
+    }
+}
+
+impl std::error::Error for MidiError {}
+
+// 音が重なった時にどの音を鳴らすか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoicePolicy {
+    // 最も高い音 (メロディが上声部にある曲向け)
+    Highest,
+    // 最も低い音 (ベースライン向け)
+    Lowest,
+    // 最後に押された音 (モノフォニックシンセと同じ挙動)
+    Last,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MidiEventKind {
+    NoteOff(u8),
+    Tempo(u32),
+    NoteOn(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MidiEvent {
+    tick: u64,
+    kind: MidiEventKind,
+}
+
+// 変換の設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiImport {
+    track: Option<usize>,
+    channel: Option<u8>,
+    policy: VoicePolicy,
+}
+
+impl MidiImport {
+    pub fn new() -> Self {
+        MidiImport {
+            track: None,
+            channel: None,
+            policy: VoicePolicy::Highest,
+        }
+    }
+
+    // 対象のトラック (0 始まり)
+    #[must_use]
+    pub fn track(mut self, track: usize) -> Self {
+        self.track = Some(track);
+        self
+    }
+
+    // 対象のチャンネル (0 ~ 15)
+    #[must_use]
+    pub fn channel(mut self, channel: u8) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    #[must_use]
+    pub fn policy(mut self, policy: VoicePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn convert(&self, data: &[u8]) -> Result<Score, MidiError> {
+        let (division, tracks) = split_chunks(data)?;
+        if let Some(track) = self.track {
+            if track >= tracks.len() {
+                return Err(MidiError::TrackNotFound(track));
+            }
+        }
+
+        let mut events: Vec<MidiEvent> = Vec::new();
+        for (index, track) in tracks.iter().enumerate() {
+            let use_notes = !matches!(self.track, Some(selected) if selected != index);
+            read_track(index, track, use_notes, self.channel, &mut events)?;
+        }
+        // 同じ tick ではノートオフ → テンポ → ノートオンの順に処理する
+        events.sort_by_key(|event| (event.tick, match event.kind {
+            MidiEventKind::NoteOff(_) => 0,
+            MidiEventKind::Tempo(_) => 1,
+            MidiEventKind::NoteOn(_) => 2,
+        }));
+
+        self.allocate(&events, division)
+    }
+
+    // 重なったノートから 1 音を選びながら楽譜を組み立てる
+    fn allocate(&self, events: &[MidiEvent], division: u16) -> Result<Score, MidiError> {
+        // 押されているノート (押された順)
+        let mut held: Vec<u8> = Vec::new();
+        let mut score = Score::new(microseconds_to_bpm(DEFAULT_MICROSECONDS_PER_QUARTER)).gate(100);
+        // 出力中の音 (None は休符) と開始 tick
+        let mut sounding: Option<u8> = None;
+        let mut segment_start: Option<u64> = None;
+
+        let mut index = 0;
+        while index < events.len() {
+            let tick = events[index].tick;
+            let mut retriggered: Vec<u8> = Vec::new();
+            let mut tempo: Option<u32> = None;
+            while index < events.len() && events[index].tick == tick {
+                match events[index].kind {
+                    MidiEventKind::NoteOff(note) => {
+                        if let Some(position) = held.iter().position(|&held_note| held_note == note) {
+                            held.remove(position);
+                        }
+                    },
+                    MidiEventKind::Tempo(microseconds) => tempo = Some(microseconds),
+                    MidiEventKind::NoteOn(note) => {
+                        held.retain(|&held_note| held_note != note);
+                        held.push(note);
+                        retriggered.push(note);
+                    },
+                }
+                index += 1;
+            }
+
+            let next = match self.policy {
+                VoicePolicy::Highest => held.iter().copied().max(),
+                VoicePolicy::Lowest => held.iter().copied().min(),
+                VoicePolicy::Last => held.last().copied(),
+            };
+            let changed = next != sounding || next.is_some_and(|note| retriggered.contains(&note));
+
+            if changed || tempo.is_some() {
+                if let Some(start) = segment_start {
+                    let ticks = convert_ticks(tick, division) - convert_ticks(start, division);
+                    if ticks > 0 {
+                        score.events.push(match sounding {
+                            Some(note) => ScoreEvent::Note { frequency: midi_note_to_frequency(note), ticks },
+                            None => ScoreEvent::Rest { ticks },
+                        });
+                    }
+                }
+                if let Some(microseconds) = tempo {
+                    let bpm = microseconds_to_bpm(microseconds);
+                    // 最初の音符より前のテンポは初期テンポとする
+                    if score.events.is_empty() {
+                        score.tempo_bpm = bpm;
+                    } else {
+                        score.events.push(ScoreEvent::Tempo { bpm });
+                    }
+                }
+                sounding = next;
+                // 先頭の無音は詰める
+                if segment_start.is_some() || sounding.is_some() {
+                    segment_start = Some(tick);
+                }
+            }
+        }
+
+        // 末尾のテンポ変更と休符は不要
+        while matches!(score.events.last(), Some(ScoreEvent::Tempo { .. }) | Some(ScoreEvent::Rest { .. })) {
+            score.events.pop();
+        }
+        if !score.events.iter().any(|event| matches!(event, ScoreEvent::Note { .. })) {
+            return Err(MidiError::NoNotes);
+        }
+        Ok(score)
+    }
+}
+
+impl Default for MidiImport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn microseconds_to_bpm(microseconds_per_quarter: u32) -> u32 {
+    let microseconds = microseconds_per_quarter.max(1);
+    ((60_000_000 + microseconds / 2) / microseconds).max(1)
+}
+
+// SMF の tick を楽譜の tick に変換する
+// 区間ごとに丸めると誤差が蓄積するので、絶対 tick を変換してから差を取ること
+fn convert_ticks(tick: u64, division: u16) -> u32 {
+    ((tick * TICKS_PER_QUARTER as u64 + division as u64 / 2) / division as u64) as u32
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([ data[offset], data[offset + 1] ])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([ data[offset], data[offset + 1], data[offset + 2], data[offset + 3] ])
+}
+
+// ヘッダを解析し、時間単位とトラックチャンク本体の一覧を返す
+fn split_chunks(data: &[u8]) -> Result<(u16, Vec<&[u8]>), MidiError> {
+    if data.len() < 14 || &data[0..4] != b"MThd" || read_u32(data, 4) < 6 {
+        return Err(MidiError::InvalidHeader);
+    }
+    let header_length = read_u32(data, 4) as usize;
+    let format = read_u16(data, 8);
+    let track_count = read_u16(data, 10) as usize;
+    let division = read_u16(data, 12);
+    if format > 1 {
+        return Err(MidiError::UnsupportedFormat(format));
+    }
+    if division & 0x8000 != 0 || division == 0 {
+        return Err(MidiError::UnsupportedDivision(division));
+    }
+
+    // 長さはファイルの値なので、32 ビット環境 (ESP32) で桁あふれしないよう checked_add で足す
+    let mut tracks = Vec::new();
+    let mut offset = header_length.checked_add(8).ok_or(MidiError::InvalidHeader)?;
+    while tracks.len() < track_count {
+        let body = offset.checked_add(8).filter(|&body| body <= data.len()).ok_or(MidiError::InvalidHeader)?;
+        let length = read_u32(data, offset + 4) as usize;
+        let end = body.checked_add(length).filter(|&end| end <= data.len()).ok_or(MidiError::InvalidHeader)?;
+        // 未知のチャンクは読み飛ばす
+        if &data[offset..offset + 4] == b"MTrk" {
+            tracks.push(&data[body..end]);
+        }
+        offset = end;
+    }
+    Ok((division, tracks))
+}
+
+struct TrackReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    track: usize,
+}
+
+impl TrackReader<'_> {
+    fn byte(&mut self) -> Result<u8, MidiError> {
+        let value = *self.data.get(self.offset).ok_or(MidiError::UnexpectedEnd(self.track))?;
+        self.offset += 1;
+        Ok(value)
+    }
+
+    // 可変長数値
+    fn variable_length(&mut self) -> Result<u32, MidiError> {
+        let mut value: u32 = 0;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(MidiError::UnexpectedEnd(self.track))
+    }
+
+    fn skip(&mut self, length: usize) -> Result<&[u8], MidiError> {
+        let end = self.offset.checked_add(length)
+            .filter(|&end| end <= self.data.len())
+            .ok_or(MidiError::UnexpectedEnd(self.track))?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+}
+
+// トラックからノートとテンポのイベントを取り出す
+fn read_track(track: usize, data: &[u8], use_notes: bool, channel: Option<u8>, events: &mut Vec<MidiEvent>) -> Result<(), MidiError> {
+    let mut reader = TrackReader { data, offset: 0, track };
+    let mut tick: u64 = 0;
+    let mut running_status: Option<u8> = None;
+
+    while reader.offset < data.len() {
+        tick += reader.variable_length()? as u64;
+        let mut status = reader.byte()?;
+        let mut first_data: Option<u8> = None;
+        if status < 0x80 {
+            first_data = Some(status);
+            status = running_status.ok_or(MidiError::InvalidStatus(track))?;
+        }
+
+        match status {
+            0xFF => {
+                let meta_type = reader.byte()?;
+                let length = reader.variable_length()? as usize;
+                let body = reader.skip(length)?;
+                match meta_type {
+                    // テンポ (4 分音符あたりのマイクロ秒)
+                    0x51 if length == 3 => {
+                        let microseconds = ((body[0] as u32) << 16) | ((body[1] as u32) << 8) | body[2] as u32;
+                        events.push(MidiEvent { tick, kind: MidiEventKind::Tempo(microseconds) });
+                    },
+                    // トラック終端
+                    0x2F => break,
+                    _ => (),
+                }
+            },
+            0xF0 | 0xF7 => {
+                let length = reader.variable_length()? as usize;
+                reader.skip(length)?;
+            },
+            _ => {
+                running_status = Some(status);
+                let data1 = match first_data {
+                    Some(value) => value,
+                    None => reader.byte()?,
+                };
+                // プログラムチェンジとチャンネルプレッシャーだけデータが 1 バイト
+                let data2 = match status & 0xF0 {
+                    0xC0 | 0xD0 => 0,
+                    _ => reader.byte()?,
+                };
+                let selected = use_notes && !matches!(channel, Some(selected) if selected != status & 0x0F);
+                if !selected {
+                    continue;
+                }
+                match status & 0xF0 {
+                    0x90 if data2 > 0 => events.push(MidiEvent { tick, kind: MidiEventKind::NoteOn(data1) }),
+                    // ベロシティ 0 のノートオンはノートオフ
+                    0x80 | 0x90 => events.push(MidiEvent { tick, kind: MidiEventKind::NoteOff(data1) }),
+                    _ => (),
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+// build.rs 用: 楽譜を Rust のソースコード (楽譜を返す関数) に変換する
+pub fn to_rust_source(function_name: &str, score: &Score) -> String {
+    let mut source = String::new();
+    source.push_str(&format!("pub fn {}() -> crate::score::Score {{\n", function_name));
+    source.push_str("    crate::score::Score {\n");
+    source.push_str("        events: vec![\n");
+    for event in score.events.iter() {
+        let line = match event {
+            ScoreEvent::Note { frequency, ticks } => format!("crate::score::ScoreEvent::Note {{ frequency: {}, ticks: {} }}", frequency, ticks),
+            ScoreEvent::Rest { ticks } => format!("crate::score::ScoreEvent::Rest {{ ticks: {} }}", ticks),
+            ScoreEvent::Tempo { bpm } => format!("crate::score::ScoreEvent::Tempo {{ bpm: {} }}", bpm),
+        };
+        source.push_str(&format!("            {},\n", line));
+    }
+    source.push_str("        ],\n");
+    source.push_str(&format!("        tempo_bpm: {},\n", score.tempo_bpm));
+    source.push_str(&format!("        gate_percent: {},\n", score.gate_percent));
+    source.push_str("        loop_points: None,\n");
+    source.push_str("    }\n");
+    source.push_str("}\n");
+    source
+}
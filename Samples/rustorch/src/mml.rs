@@ -0,0 +1,287 @@
+// MML (Music Macro Language) パーサ
+//
+// 対応するコマンド (大文字小文字は区別しない)
+// - C D E F G A B : 音符。直後に +/# (半音上げ)、- (半音下げ)、音長、付点 (.) を書ける
+// - R (P)         : 休符。音長と付点を書ける
+// - N<n>          : MIDI ノート番号 (0 ~ 127) で音符を指定する (音長は L の値)
+// - T<n>          : テンポ (1 ~ 999)
+// - O<n>          : オクターブ (0 ~ 8)。O4 の A が 440Hz
+// - < >           : オクターブを 1 つ下げる / 上げる
+// - L<n>          : 音長を省略した時の音長 (付点も書ける)
+// - Q<n>          : ゲートタイム (1 ~ 8)。音長の n/8 だけ音を出す (楽譜全体で共通)
+// - &             : タイ。直前の音符と同じ高さの音符をつなげる
+// - $             : ループ開始位置。楽譜の最後まで演奏したらここへ戻る (無限ループ)
+// 空白と改行は無視する
+
+use std::fmt;
+
+use crate::score::{midi_note_to_frequency, Score, ScoreEvent, LoopPoints, TICKS_PER_QUARTER};
+
+const TICKS_PER_WHOLE: u32 = TICKS_PER_QUARTER * 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MmlErrorKind {
+    // 未対応の文字
+    UnexpectedCharacter(char),
+    // 数値が必要な位置に数値がない
+    MissingNumber(char),
+    // コマンドの引数が範囲外
+    OutOfRange(char, u32),
+    // tick 単位で表現できない音長 (例: L7)
+    InvalidLength(u32),
+    // タイの後に音符がない、または高さが違う
+    InvalidTie,
+}
+
+// エラー位置は 1 始まりの行番号と桁番号 (文字単位)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MmlError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: MmlErrorKind,
+}
+
+impl fmt::Display for MmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match self.kind {
+            MmlErrorKind::UnexpectedCharacter(ch) => write!(f, "unexpected character '{}'", ch),
+            MmlErrorKind::MissingNumber(command) => write!(f, "'{}' requires a number", command),
+            MmlErrorKind::OutOfRange(command, value) => write!(f, "'{}{}' is out of range", command, value),
+            MmlErrorKind::InvalidLength(length) => write!(f, "unsupported note length {}", length),
+            MmlErrorKind::InvalidTie => write!(f, "'&' must be followed by a note of the same pitch"),
+        }
+    }
+}
+
+impl std::error::Error for MmlError {}
+
+struct Cursor<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(source: &'a str) -> Self {
+        Cursor { chars: source.chars().peekable(), line: 1, column: 1 }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|ch| ch.to_ascii_uppercase())
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let ch = self.chars.next()?;
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(ch.to_ascii_uppercase())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|ch| ch.is_whitespace()) {
+            self.next();
+        }
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let mut value: Option<u32> = None;
+        while let Some(digit) = self.peek().and_then(|ch| ch.to_digit(10)) {
+            self.next();
+            value = Some(value.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        }
+        value
+    }
+
+    fn dots(&mut self) -> u32 {
+        let mut count = 0;
+        while self.peek() == Some('.') {
+            self.next();
+            count += 1;
+        }
+        count
+    }
+
+    fn error(&self, line: usize, column: usize, kind: MmlErrorKind) -> MmlError {
+        MmlError { line, column, kind }
+    }
+}
+
+// 音長 (4 なら 4 分音符) と付点の数を tick 数に変換する
+fn length_to_ticks(length: u32, dots: u32) -> Option<u32> {
+    if length == 0 || TICKS_PER_WHOLE % length != 0 {
+        return None;
+    }
+    let base = TICKS_PER_WHOLE / length;
+    let mut ticks = base;
+    let mut addition = base;
+    for _ in 0..dots {
+        if addition % 2 != 0 {
+            return None;
+        }
+        addition /= 2;
+        ticks += addition;
+    }
+    Some(ticks)
+}
+
+pub fn parse(source: &str) -> Result<Score, MmlError> {
+    let mut cursor = Cursor::new(source);
+    let mut score = Score::new(120);
+    let mut octave: i32 = 4;
+    let mut default_ticks = TICKS_PER_QUARTER;
+    let mut loop_start: Option<usize> = None;
+    // タイの途中 (& の位置)
+    let mut pending_tie: Option<(usize, usize)> = None;
+
+    loop {
+        cursor.skip_whitespace();
+        let line = cursor.line;
+        let column = cursor.column;
+        let Some(command) = cursor.next() else {
+            break;
+        };
+
+        // タイの直後は音符しか書けない
+        if let Some((tie_line, tie_column)) = pending_tie {
+            if !matches!(command, 'A'..='G' | 'N') {
+                return Err(cursor.error(tie_line, tie_column, MmlErrorKind::InvalidTie));
+            }
+        }
+
+        match command {
+            'A'..='G' | 'N' | 'R' | 'P' => {
+                let note = match command {
+                    'R' | 'P' => None,
+                    'N' => {
+                        let value = cursor.number().ok_or(cursor.error(line, column, MmlErrorKind::MissingNumber(command)))?;
+                        if value > 127 {
+                            return Err(cursor.error(line, column, MmlErrorKind::OutOfRange(command, value)));
+                        }
+                        Some(value as u8)
+                    },
+                    _ => {
+                        let pitch_class = match command {
+                            'C' => 0, 'D' => 2, 'E' => 4, 'F' => 5, 'G' => 7, 'A' => 9, _ => 11,
+                        };
+                        let accidental = match cursor.peek() {
+                            Some('+') | Some('#') => { cursor.next(); 1 },
+                            Some('-') => { cursor.next(); -1 },
+                            _ => 0,
+                        };
+                        // O0 の C- (11) ~ O8 の B+ (120) なので必ず MIDI ノート番号の範囲に収まる
+                        Some(((octave + 1) * 12 + pitch_class + accidental) as u8)
+                    },
+                };
+
+                // N は音長を書けない (数値がノート番号と区別できないため)
+                let ticks = if command == 'N' {
+                    default_ticks
+                } else {
+                    let length = cursor.number();
+                    let dots = cursor.dots();
+                    match length {
+                        Some(length) => length_to_ticks(length, dots)
+                            .ok_or(cursor.error(line, column, MmlErrorKind::InvalidLength(length)))?,
+                        None => {
+                            let mut ticks = default_ticks;
+                            let mut addition = default_ticks;
+                            for _ in 0..dots {
+                                addition /= 2;
+                                ticks += addition;
+                            }
+                            ticks
+                        },
+                    }
+                };
+
+                let event = match note {
+                    None => ScoreEvent::Rest { ticks },
+                    Some(note) => ScoreEvent::Note { frequency: midi_note_to_frequency(note), ticks },
+                };
+
+                // タイ: 直前の音符を伸ばす
+                if let Some((tie_line, tie_column)) = pending_tie.take() {
+                    match (score.events.last_mut(), event) {
+                        (Some(ScoreEvent::Note { frequency, ticks: previous_ticks }), ScoreEvent::Note { frequency: next_frequency, ticks })
+                            if *frequency == next_frequency => {
+                            *previous_ticks += ticks;
+                        },
+                        _ => return Err(cursor.error(tie_line, tie_column, MmlErrorKind::InvalidTie)),
+                    }
+                } else {
+                    score.events.push(event);
+                }
+            },
+            '&' => {
+                if !matches!(score.events.last(), Some(ScoreEvent::Note { .. })) {
+                    return Err(cursor.error(line, column, MmlErrorKind::InvalidTie));
+                }
+                pending_tie = Some((line, column));
+            },
+            'T' => {
+                let value = cursor.number().ok_or(cursor.error(line, column, MmlErrorKind::MissingNumber(command)))?;
+                if value == 0 || value > 999 {
+                    return Err(cursor.error(line, column, MmlErrorKind::OutOfRange(command, value)));
+                }
+                // 最初の音符より前のテンポは初期テンポとする
+                if score.events.is_empty() {
+                    score.tempo_bpm = value;
+                } else {
+                    score.events.push(ScoreEvent::Tempo { bpm: value });
+                }
+            },
+            'O' => {
+                let value = cursor.number().ok_or(cursor.error(line, column, MmlErrorKind::MissingNumber(command)))?;
+                if value > 8 {
+                    return Err(cursor.error(line, column, MmlErrorKind::OutOfRange(command, value)));
+                }
+                octave = value as i32;
+            },
+            '<' | '>' => {
+                let next_octave = if command == '<' { octave - 1 } else { octave + 1 };
+                if !(0..=8).contains(&next_octave) {
+                    return Err(cursor.error(line, column, MmlErrorKind::OutOfRange('O', next_octave.max(0) as u32)));
+                }
+                octave = next_octave;
+            },
+            'L' => {
+                let value = cursor.number().ok_or(cursor.error(line, column, MmlErrorKind::MissingNumber(command)))?;
+                let dots = cursor.dots();
+                default_ticks = length_to_ticks(value, dots).ok_or(cursor.error(line, column, MmlErrorKind::InvalidLength(value)))?;
+            },
+            'Q' => {
+                let value = cursor.number().ok_or(cursor.error(line, column, MmlErrorKind::MissingNumber(command)))?;
+                if value == 0 || value > 8 {
+                    return Err(cursor.error(line, column, MmlErrorKind::OutOfRange(command, value)));
+                }
+                score.gate_percent = (value * 100 / 8) as u8;
+            },
+            '$' => {
+                loop_start = Some(score.events.len());
+            },
+            _ => {
+                return Err(cursor.error(line, column, MmlErrorKind::UnexpectedCharacter(command)));
+            },
+        }
+    }
+
+    if let Some((tie_line, tie_column)) = pending_tie {
+        return Err(cursor.error(tie_line, tie_column, MmlErrorKind::InvalidTie));
+    }
+
+    // ループ区間に音符も休符もない場合はループしない
+    if let Some(start) = loop_start {
+        let end = score.events.len();
+        let has_length = score.events[start..].iter().any(|event| !matches!(event, ScoreEvent::Tempo { .. }));
+        if has_length {
+            score.loop_points = Some(LoopPoints { start, end, repeat: None });
+        }
+    }
+
+    Ok(score)
+}
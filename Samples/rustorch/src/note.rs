@@ -0,0 +1,173 @@
+// 音の高さ (平均律)
+// - MIDI ノート番号 (C4 = 60) とセント単位のずれで表す
+// - 周波数は基準音 A4 の周波数から整数演算だけで求める (LEDC の周波数設定にそのまま使える)
+
+use std::fmt;
+
+// 基準音 A4 の標準の周波数 [Hz]
+pub const STANDARD_A4_HZ: u32 = 440;
+const A4_MIDI: i32 = 69;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PitchClass {
+    C, CSharp, D, DSharp, E, F, FSharp, G, GSharp, A, ASharp, B,
+}
+
+impl PitchClass {
+    const ALL: [PitchClass; 12] = [
+        PitchClass::C, PitchClass::CSharp, PitchClass::D, PitchClass::DSharp,
+        PitchClass::E, PitchClass::F, PitchClass::FSharp, PitchClass::G,
+        PitchClass::GSharp, PitchClass::A, PitchClass::ASharp, PitchClass::B,
+    ];
+    const NAMES: [&'static str; 12] = [ "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B" ];
+
+    // C を 0 とした半音単位の位置
+    pub fn index(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_index(index: u8) -> Self {
+        PitchClass::ALL[(index % 12) as usize]
+    }
+
+    pub fn name(&self) -> &'static str {
+        PitchClass::NAMES[self.index() as usize]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteError {
+    // MIDI ノート番号の範囲 (0 ~ 127) 外
+    OutOfRange(i32),
+    // ずれは ±100 セントまで (それ以上は移調で表す)
+    CentsOutOfRange(i32),
+}
+
+impl fmt::Display for NoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NoteError::OutOfRange(number) => write!(f, "note number {} is out of range (0 ~ 127)", number),
+            NoteError::CentsOutOfRange(cents) => write!(f, "detune {} cents is out of range (-100 ~ 100)", cents),
+        }
+    }
+}
+
+impl std::error::Error for NoteError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Note {
+    number: u8,
+    cents: i16,
+}
+
+// 2^(n/12) (n = 0 ~ 11) を 10^9 倍した値
+const SEMITONE_RATIO_NANO: [u128; 12] = [
+    1_000_000_000,
+    1_059_463_094,
+    1_122_462_048,
+    1_189_207_115,
+    1_259_921_050,
+    1_334_839_854,
+    1_414_213_562,
+    1_498_307_077,
+    1_587_401_052,
+    1_681_792_831,
+    1_781_797_436,
+    1_887_748_625,
+];
+const NANO: u128 = 1_000_000_000;
+// ln(2) / 1200 を 10^15 倍した値 (1 セントあたりの指数)
+const LN2_PER_CENT_FEMTO: u128 = 577_622_650_467;
+
+// 2^(cents/1200) (cents = 0 ~ 99) を 10^9 倍した値
+// e^x の 3 次までのテイラー展開 (x < 0.058 なので誤差は 0.01 セント未満)
+fn cent_ratio_nano(cents: u32) -> u128 {
+    let x = cents as u128 * LN2_PER_CENT_FEMTO / 1_000_000;  // 10^9 倍
+    NANO + x + x * x / (2 * NANO) + x * x * x / (6 * NANO * NANO)
+}
+
+impl Note {
+    pub fn new(pitch_class: PitchClass, octave: i8) -> Result<Self, NoteError> {
+        let number = (octave as i32 + 1) * 12 + pitch_class.index() as i32;
+        Note::from_number(number)
+    }
+
+    pub fn from_midi(number: u8) -> Result<Self, NoteError> {
+        Note::from_number(number as i32)
+    }
+
+    fn from_number(number: i32) -> Result<Self, NoteError> {
+        if !(0..=127).contains(&number) {
+            return Err(NoteError::OutOfRange(number));
+        }
+        Ok(Note { number: number as u8, cents: 0 })
+    }
+
+    pub fn midi(&self) -> u8 {
+        self.number
+    }
+
+    pub fn pitch_class(&self) -> PitchClass {
+        PitchClass::from_index(self.number % 12)
+    }
+
+    // C4 = 60 となるオクターブ番号 (MIDI ノート番号 0 は -1)
+    pub fn octave(&self) -> i8 {
+        (self.number / 12) as i8 - 1
+    }
+
+    pub fn cents(&self) -> i16 {
+        self.cents
+    }
+
+    // ずれ [セント] を設定する
+    pub fn detune(self, cents: i16) -> Result<Self, NoteError> {
+        if !(-100..=100).contains(&cents) {
+            return Err(NoteError::CentsOutOfRange(cents as i32));
+        }
+        Ok(Note { cents, ..self })
+    }
+
+    // 半音単位で移調する (ずれはそのまま)
+    pub fn transpose(self, semitones: i32) -> Result<Self, NoteError> {
+        let number = self.number as i32 + semitones;
+        Ok(Note { cents: self.cents, ..Note::from_number(number)? })
+    }
+
+    // A4 を a4_hz とした時の周波数 [mHz]
+    pub fn frequency_mhz(&self, a4_hz: u32) -> u64 {
+        let total_cents = (self.number as i32 - A4_MIDI) * 100 + self.cents as i32;
+        let octave = total_cents.div_euclid(1200);
+        let semitone = (total_cents.rem_euclid(1200) / 100) as usize;
+        let cents = (total_cents.rem_euclid(100)) as u32;
+
+        let mut numerator = a4_hz as u128 * 1000 * SEMITONE_RATIO_NANO[semitone] * cent_ratio_nano(cents);
+        let mut denominator = NANO * NANO;
+        if octave >= 0 {
+            numerator <<= octave;
+        } else {
+            denominator <<= -octave;
+        }
+        ((numerator + denominator / 2) / denominator) as u64
+    }
+
+    // A4 を a4_hz とした時の周波数 [Hz] (四捨五入)
+    pub fn frequency_with_a4(&self, a4_hz: u32) -> u32 {
+        ((self.frequency_mhz(a4_hz) + 500) / 1000) as u32
+    }
+
+    // 標準のピッチ (A4 = 440Hz) での周波数 [Hz]
+    pub fn frequency(&self) -> u32 {
+        self.frequency_with_a4(STANDARD_A4_HZ)
+    }
+}
+
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.pitch_class().name(), self.octave())?;
+        if self.cents != 0 {
+            write!(f, "{:+}c", self.cents)?;
+        }
+        Ok(())
+    }
+}
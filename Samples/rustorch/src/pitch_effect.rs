@@ -0,0 +1,175 @@
+// 音の高さの時間変化 (ブザースレッドで計算する)
+// - アルペジオ: 和音の構成音を一定間隔で切り替えて鳴らす (LEDC 1 チャンネルでの擬似和音)
+// - ビブラート: 三角波で周期的に高さを揺らす
+// - ポルタメント: 直前の音から次の音へ滑らかに移る
+// - スイープ: 発音中に高さを変化させる (レーザー音やサイレン)
+// 全て発音開始からの経過時間 [us] の関数として計算するので、更新周期がぶれても音の長さはずれない
+
+// 高さが変化している間の更新周期 [us] (FreeRTOS の 1 tick)
+pub const PITCH_STEP_US: i64 = 1_000;
+
+// ln(2) / 1200 を 10^9 倍した値 (1 セントあたりの周波数の変化率)
+const LN2_PER_CENT_NANO: i64 = 577_623;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vibrato {
+    // 揺れ幅 (片側) [セント]
+    pub depth_cents: u32,
+    // 1 周期の長さ [ms]
+    pub period_ms: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SweepMode {
+    // 1 回変化したらそのまま
+    Once,
+    // 変化を繰り返す (のこぎり波)
+    Repeat,
+    // 往復を繰り返す (三角波)
+    PingPong,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sweep {
+    // 変化後の周波数 (発音した周波数に対する割合 [%])
+    pub target_percent: u32,
+    // 変化にかかる時間 [ms]
+    pub duration_ms: u32,
+    pub mode: SweepMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PitchEffect {
+    pub vibrato: Option<Vibrato>,
+    pub sweep: Option<Sweep>,
+    // 直前の音からの移行時間 [ms] (0 はポルタメントなし)
+    pub glide_ms: u32,
+}
+
+impl PitchEffect {
+    pub const NONE: PitchEffect = PitchEffect { vibrato: None, sweep: None, glide_ms: 0 };
+
+    // 発音中に高さが変化するか (ポルタメントは移行中だけなので含めない)
+    fn is_modulated(&self) -> bool {
+        self.vibrato.is_some() || self.sweep.is_some()
+    }
+}
+
+// 0 ~ period を 0 ~ 1000 ~ 0 に変換する三角波
+fn triangle_permille(elapsed_us: i64, period_us: i64) -> i64 {
+    let phase = elapsed_us.rem_euclid(period_us) * 2000 / period_us;
+    if phase < 1000 { phase } else { 2000 - phase }
+}
+
+// from から to へ progress [‰] だけ進んだ値
+fn interpolate(from: u32, to: u32, progress_permille: i64) -> i64 {
+    from as i64 + (to as i64 - from as i64) * progress_permille / 1000
+}
+
+// 鳴らしている音 (単音または和音) の高さを時刻から求める
+pub struct PitchGenerator {
+    // 鳴らしている音 (複数ある場合はアルペジオ)、空なら消音
+    frequencies: Vec<u32>,
+    // アルペジオで 1 音を鳴らす時間 [us]
+    step_us: i64,
+    // ポルタメントの開始周波数
+    glide_from: Option<u32>,
+    start_us: i64,
+    // 最後に出力した周波数
+    last_frequency: Option<u32>,
+}
+
+impl PitchGenerator {
+    pub fn new() -> Self {
+        PitchGenerator {
+            frequencies: Vec::new(),
+            step_us: 1,
+            glide_from: None,
+            start_us: 0,
+            last_frequency: None,
+        }
+    }
+
+    // 発音を開始する (frequencies が複数あれば step_us 間隔のアルペジオ)
+    // ポルタメントは直前に鳴らしていた音から始める
+    pub fn start(&mut self, frequencies: Vec<u32>, step_us: i64, effect: &PitchEffect, now_us: i64) {
+        self.glide_from = if effect.glide_ms > 0 { self.last_frequency } else { None };
+        self.frequencies = frequencies;
+        self.step_us = step_us.max(1);
+        self.start_us = now_us;
+    }
+
+    // 止めた後の音はポルタメントしない (休符や別の曲を挟んだ古い音から滑らせない)
+    pub fn stop(&mut self) {
+        self.frequencies.clear();
+        self.last_frequency = None;
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.frequencies.is_empty()
+    }
+
+    // 高さが変化している間なら次に更新すべき時刻
+    pub fn next_update_us(&self, effect: &PitchEffect, now_us: i64) -> Option<i64> {
+        if !self.is_active() {
+            return None;
+        }
+        let elapsed_us = now_us - self.start_us;
+        let gliding = self.glide_from.is_some() && elapsed_us < effect.glide_ms as i64 * 1000;
+        if effect.is_modulated() || gliding {
+            return Some(now_us + PITCH_STEP_US);
+        }
+        if self.frequencies.len() > 1 {
+            // 次の構成音に切り替わる時刻
+            return Some(self.start_us + (elapsed_us / self.step_us + 1) * self.step_us);
+        }
+        None
+    }
+
+    // 時刻 now_us に出力すべき周波数 (None は消音)
+    pub fn update(&mut self, effect: &PitchEffect, now_us: i64) -> Option<u32> {
+        if !self.is_active() {
+            return None;
+        }
+        let elapsed_us = (now_us - self.start_us).max(0);
+        let index = (elapsed_us / self.step_us) as usize % self.frequencies.len();
+        let base = self.frequencies[index];
+
+        // ポルタメント
+        let glide_us = effect.glide_ms as i64 * 1000;
+        let mut frequency = match self.glide_from {
+            Some(from) if elapsed_us < glide_us => interpolate(from, base, elapsed_us * 1000 / glide_us),
+            _ => base as i64,
+        };
+
+        // スイープ
+        if let Some(sweep) = effect.sweep {
+            let target = (frequency * sweep.target_percent as i64 / 100) as u32;
+            let duration_us = (sweep.duration_ms as i64 * 1000).max(1);
+            let progress = match sweep.mode {
+                SweepMode::Once => elapsed_us.min(duration_us) * 1000 / duration_us,
+                SweepMode::Repeat => elapsed_us % duration_us * 1000 / duration_us,
+                SweepMode::PingPong => triangle_permille(elapsed_us, duration_us * 2),
+            };
+            frequency = interpolate(frequency as u32, target, progress);
+        }
+
+        // ビブラート (中心から ±depth セント、1 セントあたりの変化率は ln(2)/1200 で近似)
+        if let Some(vibrato) = effect.vibrato {
+            let period_us = (vibrato.period_ms as i64 * 1000).max(1);
+            // 周期の 1/4 ずらして中心から始める
+            let wave = triangle_permille(elapsed_us + period_us / 4, period_us) * 2 - 1000;
+            frequency += frequency * vibrato.depth_cents as i64 * LN2_PER_CENT_NANO * wave / (1_000_000_000 * 1000);
+        }
+
+        let frequency = frequency.max(1) as u32;
+        self.last_frequency = Some(frequency);
+        Some(frequency)
+    }
+}
+
+impl Default for PitchGenerator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,288 @@
+// RTTTL (Ring Tone Text Transfer Language) パーサ
+//
+// 書式: <名前>:<デフォルト値>:<音符>
+// - デフォルト値: d=<音長>,o=<オクターブ>,b=<テンポ>
+// - 音符: [音長]<c|d|e|f|g|a|b|p>[#][.][オクターブ][.] をカンマ区切りで並べる (p は休符)
+// オクターブは国際式 (a4 が 440Hz) とする
+//
+// 厳格モードは仕様通りの入力だけを受け付ける
+// - 名前は 10 文字以内、デフォルト値は d, o, b をこの順で全て指定
+// - 音長は 1, 2, 4, 8, 16, 32、オクターブは 4 ~ 7、テンポは仕様の値のみ
+// - 空白は不可、名前以外は小文字のみ
+// 寛容モードは出回っている崩れた RTTTL も受け付ける
+// - 空白と大文字を許容、デフォルト値の省略 (d=4, o=6, b=63) と順不同、未知のキーは無視
+// - 音長は 64 分音符まで、オクターブは 0 ~ 8、テンポは 1 ~ 1300 (仕様の最大値)、h は b として扱う
+
+use std::fmt;
+
+use crate::score::{midi_note_to_frequency, Score, ScoreEvent, TICKS_PER_QUARTER};
+
+const TICKS_PER_WHOLE: u32 = TICKS_PER_QUARTER * 4;
+
+// 仕様で定められているテンポ
+const STRICT_TEMPOS: [u32; 36] = [
+    25, 28, 31, 35, 40, 45, 50, 56, 63, 70, 80, 90, 100, 112, 125, 140, 160, 180,
+    200, 225, 250, 285, 320, 355, 400, 450, 500, 565, 635, 715, 800, 900, 1000, 1100, 1200, 1300,
+];
+// 寛容モードのテンポの上限 (厳格モードで受け付ける値は全て受け付ける)
+const LENIENT_MAX_TEMPO: u32 = STRICT_TEMPOS[STRICT_TEMPOS.len() - 1];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtttlMode {
+    Strict,
+    Lenient,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RtttlErrorKind {
+    // ':' で区切られた 3 つのセクションがない
+    MissingSection,
+    // 名前が長すぎる (厳格モード)
+    NameTooLong,
+    // デフォルト値の書式誤り、または未知のキー
+    InvalidDefault,
+    // 音符の書式誤り
+    InvalidNote,
+    InvalidDuration(u32),
+    InvalidOctave(u32),
+    InvalidTempo(u32),
+    // 空白や大文字 (厳格モード)
+    UnexpectedCharacter(char),
+}
+
+// エラー位置は 1 始まりの文字位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RtttlError {
+    pub column: usize,
+    pub kind: RtttlErrorKind,
+}
+
+impl fmt::Display for RtttlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: ", self.column)?;
+        match self.kind {
+            RtttlErrorKind::MissingSection => write!(f, "expected '<name>:<defaults>:<notes>'"),
+            RtttlErrorKind::NameTooLong => write!(f, "name is longer than 10 characters"),
+            RtttlErrorKind::InvalidDefault => write!(f, "invalid default value"),
+            RtttlErrorKind::InvalidNote => write!(f, "invalid note"),
+            RtttlErrorKind::InvalidDuration(value) => write!(f, "invalid duration {}", value),
+            RtttlErrorKind::InvalidOctave(value) => write!(f, "invalid octave {}", value),
+            RtttlErrorKind::InvalidTempo(value) => write!(f, "invalid tempo {}", value),
+            RtttlErrorKind::UnexpectedCharacter(ch) => write!(f, "unexpected character '{}'", ch),
+        }
+    }
+}
+
+impl std::error::Error for RtttlError {}
+
+// 演奏用の楽譜と曲名
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ringtone {
+    pub name: String,
+    pub score: Score,
+}
+
+struct Defaults {
+    duration: u32,
+    octave: u32,
+    bpm: u32,
+}
+
+fn error(column: usize, kind: RtttlErrorKind) -> RtttlError {
+    RtttlError { column, kind }
+}
+
+fn check_duration(value: u32, mode: RtttlMode, column: usize) -> Result<u32, RtttlError> {
+    let valid = match mode {
+        RtttlMode::Strict => matches!(value, 1 | 2 | 4 | 8 | 16 | 32),
+        RtttlMode::Lenient => matches!(value, 1 | 2 | 4 | 8 | 16 | 32 | 64),
+    };
+    if valid { Ok(value) } else { Err(error(column, RtttlErrorKind::InvalidDuration(value))) }
+}
+
+fn check_octave(value: u32, mode: RtttlMode, column: usize) -> Result<u32, RtttlError> {
+    let valid = match mode {
+        RtttlMode::Strict => (4..=7).contains(&value),
+        RtttlMode::Lenient => value <= 8,
+    };
+    if valid { Ok(value) } else { Err(error(column, RtttlErrorKind::InvalidOctave(value))) }
+}
+
+fn check_tempo(value: u32, mode: RtttlMode, column: usize) -> Result<u32, RtttlError> {
+    let valid = match mode {
+        RtttlMode::Strict => STRICT_TEMPOS.contains(&value),
+        RtttlMode::Lenient => (1..=LENIENT_MAX_TEMPO).contains(&value),
+    };
+    if valid { Ok(value) } else { Err(error(column, RtttlErrorKind::InvalidTempo(value))) }
+}
+
+// 先頭の数字列を読み取る (数字がなければ None)
+fn take_number(chars: &[(usize, char)], index: &mut usize) -> Option<u32> {
+    let mut value: Option<u32> = None;
+    while let Some(digit) = chars.get(*index).and_then(|(_, ch)| ch.to_digit(10)) {
+        value = Some(value.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        *index += 1;
+    }
+    value
+}
+
+fn parse_defaults(section: &[(usize, char)], mode: RtttlMode, end_column: usize) -> Result<Defaults, RtttlError> {
+    // 仕様上の省略時の値
+    let mut defaults = Defaults { duration: 4, octave: 6, bpm: 63 };
+    let mut specified: Vec<char> = Vec::new();
+
+    for item in section.split(|(_, ch)| *ch == ',') {
+        let column = item.first().map_or(end_column, |(column, _)| *column);
+        if item.is_empty() && mode == RtttlMode::Lenient {
+            continue;
+        }
+        let mut index = 0;
+        let key = match item.get(index) {
+            Some((_, key)) => *key,
+            None => return Err(error(column, RtttlErrorKind::InvalidDefault)),
+        };
+        index += 1;
+        if item.get(index).map(|(_, ch)| *ch) != Some('=') {
+            return Err(error(column, RtttlErrorKind::InvalidDefault));
+        }
+        index += 1;
+        let value_column = item.get(index).map_or(end_column, |(column, _)| *column);
+        let value = take_number(item, &mut index).ok_or(error(value_column, RtttlErrorKind::InvalidDefault))?;
+        if index != item.len() {
+            return Err(error(item[index].0, RtttlErrorKind::InvalidDefault));
+        }
+        match key {
+            'd' => defaults.duration = check_duration(value, mode, value_column)?,
+            'o' => defaults.octave = check_octave(value, mode, value_column)?,
+            'b' => defaults.bpm = check_tempo(value, mode, value_column)?,
+            _ if mode == RtttlMode::Lenient => continue,
+            _ => return Err(error(column, RtttlErrorKind::InvalidDefault)),
+        }
+        specified.push(key);
+    }
+
+    if mode == RtttlMode::Strict && specified != [ 'd', 'o', 'b' ] {
+        let column = section.first().map_or(end_column, |(column, _)| *column);
+        return Err(error(column, RtttlErrorKind::InvalidDefault));
+    }
+    Ok(defaults)
+}
+
+fn parse_note(item: &[(usize, char)], defaults: &Defaults, mode: RtttlMode, end_column: usize) -> Result<ScoreEvent, RtttlError> {
+    let column = item.first().map_or(end_column, |(column, _)| *column);
+    let mut index = 0;
+
+    let duration_column = column;
+    let duration = match take_number(item, &mut index) {
+        Some(value) => check_duration(value, mode, duration_column)?,
+        None => defaults.duration,
+    };
+
+    let pitch_class: Option<i32> = match item.get(index).map(|(_, ch)| *ch) {
+        Some('c') => Some(0),
+        Some('d') => Some(2),
+        Some('e') => Some(4),
+        Some('f') => Some(5),
+        Some('g') => Some(7),
+        Some('a') => Some(9),
+        Some('b') => Some(11),
+        Some('h') if mode == RtttlMode::Lenient => Some(11),
+        Some('p') => None,
+        _ => return Err(error(item.get(index).map_or(end_column, |(column, _)| *column), RtttlErrorKind::InvalidNote)),
+    };
+    index += 1;
+
+    let mut sharp = false;
+    if item.get(index).map(|(_, ch)| *ch) == Some('#') {
+        sharp = true;
+        index += 1;
+    }
+
+    // 付点はオクターブの前後どちらにも書かれる
+    let mut dotted = false;
+    if item.get(index).map(|(_, ch)| *ch) == Some('.') {
+        dotted = true;
+        index += 1;
+    }
+    let octave_column = item.get(index).map_or(end_column, |(column, _)| *column);
+    let octave = match take_number(item, &mut index) {
+        Some(value) => check_octave(value, mode, octave_column)?,
+        None => defaults.octave,
+    };
+    if !dotted && item.get(index).map(|(_, ch)| *ch) == Some('.') {
+        dotted = true;
+        index += 1;
+    }
+    if let Some((column, _)) = item.get(index) {
+        return Err(error(*column, RtttlErrorKind::InvalidNote));
+    }
+
+    let base = TICKS_PER_WHOLE / duration;
+    let ticks = if dotted { base + base / 2 } else { base };
+    Ok(match pitch_class {
+        None => ScoreEvent::Rest { ticks },
+        Some(pitch_class) => {
+            let note = (octave as i32 + 1) * 12 + pitch_class + if sharp { 1 } else { 0 };
+            ScoreEvent::Note { frequency: midi_note_to_frequency(note as u8), ticks }
+        },
+    })
+}
+
+pub fn parse(source: &str, mode: RtttlMode) -> Result<Ringtone, RtttlError> {
+    // 文字位置を保持したまま前処理する
+    // 厳格モードでも名前には大文字を使える
+    let mut chars: Vec<(usize, char)> = Vec::new();
+    let mut in_name = true;
+    for (i, ch) in source.trim_end_matches(['\r', '\n']).chars().enumerate() {
+        let column = i + 1;
+        if ch == ':' {
+            in_name = false;
+        }
+        match mode {
+            RtttlMode::Strict => {
+                if ch.is_whitespace() || (ch.is_ascii_uppercase() && !in_name) {
+                    return Err(error(column, RtttlErrorKind::UnexpectedCharacter(ch)));
+                }
+                chars.push((column, ch));
+            },
+            RtttlMode::Lenient => {
+                if !ch.is_whitespace() {
+                    chars.push((column, ch.to_ascii_lowercase()));
+                }
+            },
+        }
+    }
+    let end_column = source.chars().count() + 1;
+
+    let sections: Vec<&[(usize, char)]> = chars.splitn(3, |(_, ch)| *ch == ':').collect();
+    if sections.len() != 3 {
+        return Err(error(end_column, RtttlErrorKind::MissingSection));
+    }
+    let name: String = sections[0].iter().map(|(_, ch)| *ch).collect();
+    if mode == RtttlMode::Strict && name.chars().count() > 10 {
+        return Err(error(1, RtttlErrorKind::NameTooLong));
+    }
+    // 名前は元の表記 (大文字や空白を含む) のまま残す
+    let name = match mode {
+        RtttlMode::Strict => name,
+        RtttlMode::Lenient => source.split(':').next().unwrap_or("").trim().to_string(),
+    };
+
+    let defaults = parse_defaults(sections[1], mode, end_column)?;
+
+    let mut score = Score::new(defaults.bpm);
+    // 空の音符 (",,") はその位置のカンマをエラー位置とする
+    let mut item_column = sections[2].first().map_or(end_column, |(column, _)| *column);
+    for item in sections[2].split(|(_, ch)| *ch == ',') {
+        if let Some((column, _)) = item.last() {
+            item_column = *column + 2;
+        } else if mode == RtttlMode::Lenient {
+            continue;
+        } else {
+            return Err(error(item_column, RtttlErrorKind::InvalidNote));
+        }
+        score.events.push(parse_note(item, &defaults, mode, end_column)?);
+    }
+
+    Ok(Ringtone { name, score })
+}
@@ -0,0 +1,319 @@
+use std::fmt;
+
+use crate::note::Note;
+
+// 4 分音符 1 つあたりの tick 数 (音長の分解能)
+pub const TICKS_PER_QUARTER: u32 = 480;
+
+// 楽譜の 1 要素
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreEvent {
+    // frequency [Hz] の音を ticks の長さだけ鳴らす
+    Note { frequency: u32, ticks: u32 },
+    // ticks の長さだけ休む
+    Rest { ticks: u32 },
+    // 以降のテンポを変更する (長さ 0)
+    Tempo { bpm: u32 },
+}
+
+// ループ区間 [start, end) (イベントのインデックス)
+// repeat が None の場合は無限ループ、Some(n) の場合は区間を追加で n 回繰り返す
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopPoints {
+    pub start: usize,
+    pub end: usize,
+    pub repeat: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScoreError {
+    InvalidTempo(u32),
+    InvalidGate(u8),
+    InvalidLoop(LoopPoints),
+}
+
+impl fmt::Display for ScoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScoreError::InvalidTempo(bpm) => write!(f, "invalid tempo: {} bpm", bpm),
+            ScoreError::InvalidGate(percent) => write!(f, "invalid gate: {}%", percent),
+            ScoreError::InvalidLoop(points) => write!(f, "invalid loop points: {:?}", points),
+        }
+    }
+}
+
+impl std::error::Error for ScoreError {}
+
+// 楽譜 (音符と休符の列 + テンポ + ループ区間)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Score {
+    pub events: Vec<ScoreEvent>,
+    // 初期テンポ (4 分音符/分)
+    pub tempo_bpm: u32,
+    // 音符の長さのうち実際に音を出す割合 (同じ音の連続を聴き分けられるように 100% 未満にする)
+    pub gate_percent: u8,
+    pub loop_points: Option<LoopPoints>,
+}
+
+impl Score {
+    pub fn new(tempo_bpm: u32) -> Self {
+        Score {
+            events: Vec::new(),
+            tempo_bpm,
+            gate_percent: 90,
+            loop_points: None,
+        }
+    }
+
+    #[must_use]
+    pub fn note(mut self, frequency: u32, ticks: u32) -> Self {
+        self.events.push(ScoreEvent::Note { frequency, ticks });
+        self
+    }
+
+    #[must_use]
+    pub fn rest(mut self, ticks: u32) -> Self {
+        self.events.push(ScoreEvent::Rest { ticks });
+        self
+    }
+
+    #[must_use]
+    pub fn tempo(mut self, bpm: u32) -> Self {
+        self.events.push(ScoreEvent::Tempo { bpm });
+        self
+    }
+
+    #[must_use]
+    pub fn gate(mut self, percent: u8) -> Self {
+        self.gate_percent = percent;
+        self
+    }
+
+    #[must_use]
+    pub fn with_loop(mut self, start: usize, end: usize, repeat: Option<u32>) -> Self {
+        self.loop_points = Some(LoopPoints { start, end, repeat });
+        self
+    }
+
+    // 演奏可能かチェックする
+    // 長さ 0 のループ区間は無限ループになるので NG
+    pub fn validate(&self) -> Result<(), ScoreError> {
+        if self.tempo_bpm == 0 {
+            return Err(ScoreError::InvalidTempo(0));
+        }
+        if self.gate_percent == 0 || self.gate_percent > 100 {
+            return Err(ScoreError::InvalidGate(self.gate_percent));
+        }
+        for event in self.events.iter() {
+            if let ScoreEvent::Tempo { bpm: 0 } = event {
+                return Err(ScoreError::InvalidTempo(0));
+            }
+        }
+        if let Some(points) = self.loop_points {
+            if points.start >= points.end || points.end > self.events.len() {
+                return Err(ScoreError::InvalidLoop(points));
+            }
+            let loop_ticks: u32 = self.events[points.start..points.end].iter().map(event_ticks).sum();
+            if loop_ticks == 0 {
+                return Err(ScoreError::InvalidLoop(points));
+            }
+        }
+        Ok(())
+    }
+
+    // ループを除いた全体の長さ [tick]
+    pub fn total_ticks(&self) -> u32 {
+        self.events.iter().map(event_ticks).sum()
+    }
+}
+
+fn event_ticks(event: &ScoreEvent) -> u32 {
+    match event {
+        ScoreEvent::Note { ticks, .. } => *ticks,
+        ScoreEvent::Rest { ticks } => *ticks,
+        ScoreEvent::Tempo { .. } => 0,
+    }
+}
+
+// MIDI ノート番号 (C4 = 60) を標準のピッチ (A4 = 440Hz) での周波数 [Hz] に変換する
+// 範囲外 (128 以上) の場合は 0
+pub fn midi_note_to_frequency(note: u8) -> u32 {
+    Note::from_midi(note).map_or(0, |note| note.frequency())
+}
+
+// tick 数をテンポに応じた時間 [us] に変換する
+// 速いテンポで 0us に丸められると同じ時刻のイベントがまとめて飛ばされるので、長さのあるイベントは最短 1us にする
+pub fn ticks_to_us(ticks: u32, bpm: u32) -> i64 {
+    let us = ticks as i64 * 60_000_000 / (bpm.max(1) as i64 * TICKS_PER_QUARTER as i64);
+    if ticks > 0 { us.max(1) } else { 0 }
+}
+
+// 演奏状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PlayerStatus {
+    pub playing: bool,
+    // 演奏中のイベントのインデックス
+    pub position: usize,
+    // ループ区間を何回繰り返したか
+    pub loop_iteration: u32,
+    // 演奏開始からの経過時間 [us]
+    pub elapsed_us: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CurrentEvent {
+    index: usize,
+    // 今鳴らすべき周波数 (休符やゲート後の消音区間は None)
+    frequency: Option<u32>,
+    gate_end_us: i64,
+    end_us: i64,
+}
+
+// 楽譜の演奏制御 (ハードウェア非依存)
+// - 時刻 [us] を与えて update() すると、その時刻に出力すべき音 (output()) が決まる
+// - 各イベントの開始時刻は演奏開始時刻からの絶対時刻で管理するので、起床の遅れが蓄積しない
+pub struct ScorePlayer {
+    score: Option<Score>,
+    current: Option<CurrentEvent>,
+    tempo_bpm: u32,
+    loop_iteration: u32,
+    start_us: i64,
+    now_us: i64,
+}
+
+impl ScorePlayer {
+    pub fn new() -> Self {
+        ScorePlayer {
+            score: None,
+            current: None,
+            tempo_bpm: 120,
+            loop_iteration: 0,
+            start_us: 0,
+            now_us: 0,
+        }
+    }
+
+    // 演奏を開始する (演奏中の楽譜は破棄される)
+    pub fn start(&mut self, score: Score, now_us: i64) -> Result<(), ScoreError> {
+        score.validate()?;
+        self.tempo_bpm = score.tempo_bpm;
+        self.loop_iteration = 0;
+        self.start_us = now_us;
+        self.now_us = now_us;
+        self.score = Some(score);
+        self.enter(0, now_us);
+        Ok(())
+    }
+
+    pub fn cancel(&mut self) {
+        self.score = None;
+        self.current = None;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.current.is_some()
+    }
+
+    // 中断していた時間 delta_us だけ以降のイベントの時刻を遅らせる (中断した位置から再開する)
+    pub fn shift(&mut self, delta_us: i64) {
+        self.start_us += delta_us;
+        self.now_us += delta_us;
+        if let Some(current) = self.current.as_mut() {
+            current.gate_end_us += delta_us;
+            current.end_us += delta_us;
+        }
+    }
+
+    // 現在出力すべき周波数 (None は消音)
+    pub fn output(&self) -> Option<u32> {
+        self.current.and_then(|current| current.frequency)
+    }
+
+    // 次に出力が変わる可能性のある時刻
+    pub fn next_deadline_us(&self) -> Option<i64> {
+        self.current.map(|current| {
+            if current.frequency.is_some() { current.gate_end_us } else { current.end_us }
+        })
+    }
+
+    // 時刻 now_us までに発生するイベントを全て処理する
+    pub fn update(&mut self, now_us: i64) {
+        self.now_us = self.now_us.max(now_us);
+        while let Some(current) = self.current {
+            if current.frequency.is_some() && current.gate_end_us <= now_us && current.gate_end_us < current.end_us {
+                // ゲート終了 (消音区間へ)
+                self.current = Some(CurrentEvent { frequency: None, ..current });
+            } else if current.end_us <= now_us {
+                self.enter(current.index + 1, current.end_us);
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn status(&self) -> PlayerStatus {
+        PlayerStatus {
+            playing: self.is_playing(),
+            position: self.current.map_or(0, |current| current.index),
+            loop_iteration: self.loop_iteration,
+            elapsed_us: if self.is_playing() { self.now_us - self.start_us } else { 0 },
+        }
+    }
+
+    // index 番目のイベントを start_us から開始する
+    // 長さ 0 のイベント (テンポ変更) とループ終端はここでまとめて処理する
+    fn enter(&mut self, mut index: usize, start_us: i64) {
+        let Some(score) = self.score.as_ref() else {
+            self.current = None;
+            return;
+        };
+        loop {
+            if let Some(points) = score.loop_points {
+                let can_repeat = match points.repeat {
+                    None => true,
+                    Some(repeat) => self.loop_iteration < repeat,
+                };
+                if index == points.end && can_repeat {
+                    index = points.start;
+                    self.loop_iteration += 1;
+                }
+            }
+            match score.events.get(index) {
+                None => {
+                    self.current = None;
+                    return;
+                }
+                Some(ScoreEvent::Tempo { bpm }) => {
+                    self.tempo_bpm = *bpm;
+                    index += 1;
+                }
+                Some(ScoreEvent::Note { frequency, ticks }) => {
+                    let length_us = ticks_to_us(*ticks, self.tempo_bpm);
+                    self.current = Some(CurrentEvent {
+                        index,
+                        frequency: Some(*frequency),
+                        gate_end_us: start_us + length_us * score.gate_percent as i64 / 100,
+                        end_us: start_us + length_us,
+                    });
+                    return;
+                }
+                Some(ScoreEvent::Rest { ticks }) => {
+                    let end_us = start_us + ticks_to_us(*ticks, self.tempo_bpm);
+                    self.current = Some(CurrentEvent {
+                        index,
+                        frequency: None,
+                        gate_end_us: end_us,
+                        end_us,
+                    });
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl Default for ScorePlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,163 @@
+// OLED の画面キャプチャ (ハードウェア非依存)
+// - 画面を PBM (P4) にして base64 で 1 行に収め、シリアルコンソールに出力する
+// - ログと混ざっても取り出せるように、行に SCREENSHOT_PREFIX と連番を付ける
+//   例: "@OLED 12 UDQKMTI4IDY0Cv..."
+// - ホスト側では rustorch-test の oled_capture で PBM や GIF に変換する
+
+use std::fmt;
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+
+use crate::frame_buffer::{FrameBuffer, HEIGHT, WIDTH};
+
+pub const SCREENSHOT_PREFIX: &str = "@OLED";
+
+const BASE64_TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScreenshotError {
+    // base64 として読めない
+    InvalidBase64,
+    // P4 形式の PBM ではない
+    InvalidPbm,
+    // 画面と大きさが違う (幅, 高さ)
+    UnexpectedSize(u32, u32),
+    // 連番が読めない
+    InvalidSequence,
+}
+
+impl fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenshotError::InvalidBase64 => write!(f, "invalid base64"),
+            ScreenshotError::InvalidPbm => write!(f, "not a binary PBM (P4) image"),
+            ScreenshotError::UnexpectedSize(width, height) => write!(f, "unexpected image size {}x{} (expected {}x{})", width, height, WIDTH, HEIGHT),
+            ScreenshotError::InvalidSequence => write!(f, "invalid sequence number"),
+        }
+    }
+}
+
+impl std::error::Error for ScreenshotError {}
+
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| bits | (*byte as u32) << (16 - i * 8));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(BASE64_TABLE[(bits >> (18 - i * 6) & 0x3F) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+pub fn base64_decode(text: &str) -> Result<Vec<u8>, ScreenshotError> {
+    let text = text.trim_end_matches('=');
+    let mut result = Vec::with_capacity(text.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    for ch in text.bytes() {
+        let value = BASE64_TABLE.iter().position(|c| *c == ch).ok_or(ScreenshotError::InvalidBase64)?;
+        bits = bits << 6 | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            result.push((bits >> bit_count) as u8);
+        }
+    }
+    // 余りは 0 埋めのビットだけのはず
+    if bit_count >= 6 {
+        return Err(ScreenshotError::InvalidBase64);
+    }
+    Ok(result)
+}
+
+// PBM (P4): 1 が黒なので点灯を 0 (白) にする
+// 行の先頭から左詰めで 1 ピクセル 1 ビット
+pub fn to_pbm(frame: &FrameBuffer) -> Vec<u8> {
+    let mut result = format!("P4\n{} {}\n", WIDTH, HEIGHT).into_bytes();
+    let row_bytes = WIDTH.div_ceil(8);
+    for y in 0..HEIGHT {
+        for byte in 0..row_bytes {
+            let mut value = 0u8;
+            for bit in 0..8 {
+                let x = byte * 8 + bit;
+                if x < WIDTH && frame.get_pixel(Point::new(x as i32, y as i32)).is_off() {
+                    value |= 0x80 >> bit;
+                }
+            }
+            result.push(value);
+        }
+    }
+    result
+}
+
+pub fn from_pbm(data: &[u8]) -> Result<FrameBuffer, ScreenshotError> {
+    // ヘッダは "P4", 幅, 高さ の 3 語 (# から行末まではコメント) で、その後に空白 1 文字
+    let mut words = Vec::new();
+    let mut position = 0;
+    while words.len() < 3 {
+        while position < data.len() && (data[position].is_ascii_whitespace() || data[position] == b'#') {
+            if data[position] == b'#' {
+                while position < data.len() && data[position] != b'\n' {
+                    position += 1;
+                }
+            } else {
+                position += 1;
+            }
+        }
+        let start = position;
+        while position < data.len() && !data[position].is_ascii_whitespace() {
+            position += 1;
+        }
+        if start == position {
+            return Err(ScreenshotError::InvalidPbm);
+        }
+        words.push(std::str::from_utf8(&data[start..position]).map_err(|_| ScreenshotError::InvalidPbm)?);
+    }
+    if words[0] != "P4" {
+        return Err(ScreenshotError::InvalidPbm);
+    }
+    let width: u32 = words[1].parse().map_err(|_| ScreenshotError::InvalidPbm)?;
+    let height: u32 = words[2].parse().map_err(|_| ScreenshotError::InvalidPbm)?;
+    if width != WIDTH || height != HEIGHT {
+        return Err(ScreenshotError::UnexpectedSize(width, height));
+    }
+
+    let pixels = data.get(position + 1..).ok_or(ScreenshotError::InvalidPbm)?;
+    let row_bytes = WIDTH.div_ceil(8) as usize;
+    if pixels.len() < row_bytes * HEIGHT as usize {
+        return Err(ScreenshotError::InvalidPbm);
+    }
+    let mut frame = FrameBuffer::new();
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let byte = pixels[y as usize * row_bytes + x as usize / 8];
+            if byte & (0x80 >> (x % 8)) == 0 {
+                frame.set_pixel(Point::new(x as i32, y as i32), BinaryColor::On);
+            }
+        }
+    }
+    Ok(frame)
+}
+
+// シリアルコンソールに出力する 1 行 (改行は含まない)
+pub fn encode_line(sequence: u32, frame: &FrameBuffer) -> String {
+    format!("{} {} {}", SCREENSHOT_PREFIX, sequence, base64_encode(&to_pbm(frame)))
+}
+
+// ログの 1 行から画面を取り出す (キャプチャの行でなければ None)
+// 行頭にログのタイムスタンプや色のエスケープシーケンスが付いていてもよい
+pub fn decode_line(line: &str) -> Option<Result<(u32, FrameBuffer), ScreenshotError>> {
+    let start = line.find(SCREENSHOT_PREFIX)?;
+    let mut words = line[start + SCREENSHOT_PREFIX.len()..].split_whitespace();
+    let result = (|| {
+        let sequence = words.next().and_then(|word| word.parse().ok()).ok_or(ScreenshotError::InvalidSequence)?;
+        let data = base64_decode(words.next().unwrap_or(""))?;
+        Ok((sequence, from_pbm(&data)?))
+    })();
+    Some(result)
+}
@@ -0,0 +1,112 @@
+// 名前付きの効果音
+// 楽譜は MML で記述し、演奏時に変換する (全ての MML が正しいことはテストで確認している)
+
+use crate::mml;
+use crate::pitch_effect::{PitchEffect, Sweep, SweepMode};
+use crate::score::Score;
+use crate::tone::{Instrument, Timbre};
+use crate::voice::VoicePriority;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundEffect {
+    // ボタン操作・カーソル移動
+    Click,
+    // 決定
+    Confirm,
+    // 取り消し・戻る
+    Cancel,
+    // 操作できない
+    Error,
+    // フェーズの切り替わり (作業 → 休憩など)
+    PhaseChange,
+    // タイマー満了
+    TimerDone,
+    // 大当たり
+    Jackpot,
+    // 高い音から急降下するレーザー音
+    Laser,
+    // 上下を繰り返すサイレン
+    Siren,
+}
+
+impl SoundEffect {
+    pub const ALL: [SoundEffect; 9] = [
+        SoundEffect::Click,
+        SoundEffect::Confirm,
+        SoundEffect::Cancel,
+        SoundEffect::Error,
+        SoundEffect::PhaseChange,
+        SoundEffect::TimerDone,
+        SoundEffect::Jackpot,
+        SoundEffect::Laser,
+        SoundEffect::Siren,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SoundEffect::Click => "click",
+            SoundEffect::Confirm => "confirm",
+            SoundEffect::Cancel => "cancel",
+            SoundEffect::Error => "error",
+            SoundEffect::PhaseChange => "phase_change",
+            SoundEffect::TimerDone => "timer_done",
+            SoundEffect::Jackpot => "jackpot",
+            SoundEffect::Laser => "laser",
+            SoundEffect::Siren => "siren",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        SoundEffect::ALL.iter().copied().find(|effect| effect.name() == name)
+    }
+
+    // 操作音 (UI 音を無効にしている場合は鳴らさない)
+    pub fn is_ui_feedback(&self) -> bool {
+        matches!(self, SoundEffect::Click | SoundEffect::Confirm | SoundEffect::Cancel)
+    }
+
+    // 鳴らすボイス (操作音と通知はアプリの音楽を中断して鳴らし、鳴り終わると音楽を再開する)
+    pub fn voice(&self) -> VoicePriority {
+        match self {
+            SoundEffect::Click | SoundEffect::Confirm | SoundEffect::Cancel => VoicePriority::Ui,
+            SoundEffect::Error | SoundEffect::PhaseChange | SoundEffect::TimerDone => VoicePriority::Alert,
+            SoundEffect::Jackpot | SoundEffect::Laser | SoundEffect::Siren => VoicePriority::Music,
+        }
+    }
+
+    pub fn mml(&self) -> &'static str {
+        match self {
+            SoundEffect::Click => "T240 L64 O7 C",
+            SoundEffect::Confirm => "T240 L32 O6 C G",
+            SoundEffect::Cancel => "T240 L32 O6 G C",
+            SoundEffect::Error => "T180 L16 O4 C R32 C",
+            SoundEffect::PhaseChange => "T160 L8 O5 G > C E4",
+            SoundEffect::TimerDone => "T180 L8 O6 C E G > C4 R8 < C E G > C4",
+            SoundEffect::Jackpot => "T200 L16 O5 C E G > C E G > C4 R16 C4",
+            // 高さの変化は instrument() のスイープで付ける
+            SoundEffect::Laser => "T240 L8 O7 C",
+            SoundEffect::Siren => "T120 L1 O5 A",
+        }
+    }
+
+    pub fn score(&self) -> Score {
+        mml::parse(self.mml()).unwrap()
+    }
+
+    pub fn instrument(&self) -> Instrument {
+        match self {
+            SoundEffect::Click | SoundEffect::Confirm | SoundEffect::Cancel => Instrument::beep(),
+            SoundEffect::Error => Instrument::new(),
+            SoundEffect::PhaseChange | SoundEffect::TimerDone => Instrument::pluck(),
+            SoundEffect::Jackpot => Instrument::new(),
+            SoundEffect::Laser => Instrument::new().timbre(Timbre::Pulse25).pitch(PitchEffect {
+                sweep: Some(Sweep { target_percent: 20, duration_ms: 120, mode: SweepMode::Once }),
+                ..PitchEffect::NONE
+            }),
+            SoundEffect::Siren => Instrument::new().pitch(PitchEffect {
+                sweep: Some(Sweep { target_percent: 150, duration_ms: 500, mode: SweepMode::PingPong }),
+                ..PitchEffect::NONE
+            }),
+        }
+    }
+}
@@ -0,0 +1,371 @@
+// スプライトシートとアニメーション (ハードウェア非依存)
+// - 1 枚の BMP に同じ大きさのフレームを左上から右へ、右端まで並べたら次の行へと格子状に並べる
+// - 透過はマスク画像 (同じ大きさの BMP で点灯が不透明) か、透過色 (消灯を透過するなど) で指定する
+// - Animator は表示スレッドが持ち、アプリが描画コマンドを送り直さなくてもフレームを進める
+//
+// 使い方:
+//   let sheet = SpriteSheet::new(include_bytes!("../asserts/images/spinner.bmp"), Size::new(12, 12))?
+//       .transparent(BinaryColor::Off);
+//   let animation = Animation::new(sheet).frames(0..8, 100);
+//   context.display.lock().unwrap().start_animation(0, Sprite::new(animation, Point::new(58, 52)))?;
+
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use embedded_graphics::{pixelcolor::BinaryColor, prelude::*};
+use tinybmp::Bmp;
+
+use crate::frame_buffer::FrameBuffer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpriteError {
+    // BMP として読めない画像
+    InvalidImage,
+    // フレームの大きさが 0 か、シートより大きい
+    InvalidFrameSize,
+    // マスクの大きさがシートと違う
+    MaskSizeMismatch,
+    // シートにないフレーム番号
+    FrameOutOfRange(usize),
+    // アニメーションのフレームがない
+    EmptyAnimation,
+}
+
+impl fmt::Display for SpriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpriteError::InvalidImage => write!(f, "image is not a valid BMP"),
+            SpriteError::InvalidFrameSize => write!(f, "frame size does not fit in the sprite sheet"),
+            SpriteError::MaskSizeMismatch => write!(f, "mask size differs from the sprite sheet"),
+            SpriteError::FrameOutOfRange(index) => write!(f, "frame {} is not in the sprite sheet", index),
+            SpriteError::EmptyAnimation => write!(f, "animation has no frames"),
+        }
+    }
+}
+
+impl std::error::Error for SpriteError {}
+
+// 1 ピクセル 1 ビットの画像 (行毎に左詰め)
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bitmap {
+    size: Size,
+    bits: Vec<u8>,
+}
+
+impl Bitmap {
+    fn from_bmp(data: &[u8]) -> Result<Self, SpriteError> {
+        let bmp = Bmp::<BinaryColor>::from_slice(data).map_err(|_| SpriteError::InvalidImage)?;
+        let size = bmp.bounding_box().size;
+        let mut bitmap = Bitmap { size, bits: vec![0; (size.width * size.height).div_ceil(8) as usize] };
+        for Pixel(point, color) in bmp.pixels() {
+            if color.is_on() {
+                let index = (point.y as u32 * size.width + point.x as u32) as usize;
+                bitmap.bits[index / 8] |= 0x80 >> (index % 8);
+            }
+        }
+        Ok(bitmap)
+    }
+
+    fn get(&self, point: Point) -> bool {
+        let index = (point.y as u32 * self.size.width + point.x as u32) as usize;
+        self.bits[index / 8] & (0x80 >> (index % 8)) != 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Flip {
+    #[default]
+    None,
+    Horizontal,
+    Vertical,
+    // 180 度回転と同じ
+    Both,
+}
+
+// 格子状にフレームを並べた画像 (複製してもデータは共有する)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteSheet {
+    image: Arc<Bitmap>,
+    mask: Option<Arc<Bitmap>>,
+    transparent: Option<BinaryColor>,
+    frame_size: Size,
+}
+
+impl SpriteSheet {
+    pub fn new(image: &[u8], frame_size: Size) -> Result<Self, SpriteError> {
+        let image = Bitmap::from_bmp(image)?;
+        if frame_size.width == 0 || frame_size.height == 0 ||
+           frame_size.width > image.size.width || frame_size.height > image.size.height {
+            return Err(SpriteError::InvalidFrameSize);
+        }
+        Ok(SpriteSheet { image: Arc::new(image), mask: None, transparent: None, frame_size })
+    }
+
+    // マスク画像 (点灯したピクセルだけを描く)
+    pub fn mask(mut self, mask: &[u8]) -> Result<Self, SpriteError> {
+        let mask = Bitmap::from_bmp(mask)?;
+        if mask.size != self.image.size {
+            return Err(SpriteError::MaskSizeMismatch);
+        }
+        self.mask = Some(Arc::new(mask));
+        Ok(self)
+    }
+
+    // この色のピクセルは描かない
+    #[must_use]
+    pub fn transparent(mut self, color: BinaryColor) -> Self {
+        self.transparent = Some(color);
+        self
+    }
+
+    pub fn frame_size(&self) -> Size {
+        self.frame_size
+    }
+
+    pub fn columns(&self) -> u32 {
+        self.image.size.width / self.frame_size.width
+    }
+
+    // 端数の行や列はフレームに数えない
+    pub fn frame_count(&self) -> usize {
+        (self.columns() * (self.image.size.height / self.frame_size.height)) as usize
+    }
+
+    // index 番目のフレームを top_left に描く
+    pub fn draw(&self, target: &mut FrameBuffer, index: usize, top_left: Point, flip: Flip) -> Result<(), SpriteError> {
+        if index >= self.frame_count() {
+            return Err(SpriteError::FrameOutOfRange(index));
+        }
+        let Size { width, height } = self.frame_size;
+        let origin = Point::new(
+            (index as u32 % self.columns() * width) as i32,
+            (index as u32 / self.columns() * height) as i32,
+        );
+        for y in 0..height {
+            for x in 0..width {
+                let source_x = if matches!(flip, Flip::Horizontal | Flip::Both) { width - 1 - x } else { x };
+                let source_y = if matches!(flip, Flip::Vertical | Flip::Both) { height - 1 - y } else { y };
+                let source = origin + Point::new(source_x as i32, source_y as i32);
+                if self.mask.as_ref().is_some_and(|mask| !mask.get(source)) {
+                    continue;
+                }
+                let color = if self.image.get(source) { BinaryColor::On } else { BinaryColor::Off };
+                if Some(color) == self.transparent {
+                    continue;
+                }
+                target.set_pixel(top_left + Point::new(x as i32, y as i32), color);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationFrame {
+    // シート内のフレーム番号
+    pub index: usize,
+    pub duration_ms: u32,
+}
+
+// シートのフレームを表示する順番と時間
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Animation {
+    sheet: SpriteSheet,
+    frames: Vec<AnimationFrame>,
+    looping: bool,
+}
+
+impl Animation {
+    // 既定では繰り返す
+    pub fn new(sheet: SpriteSheet) -> Self {
+        Animation { sheet, frames: Vec::new(), looping: true }
+    }
+
+    #[must_use]
+    pub fn frame(mut self, index: usize, duration_ms: u32) -> Self {
+        self.frames.push(AnimationFrame { index, duration_ms });
+        self
+    }
+
+    // 同じ時間のフレームを続けて追加する
+    #[must_use]
+    pub fn frames(mut self, indices: Range<usize>, duration_ms: u32) -> Self {
+        self.frames.extend(indices.map(|index| AnimationFrame { index, duration_ms }));
+        self
+    }
+
+    // 繰り返さない場合は最後のフレームで止まる
+    #[must_use]
+    pub fn looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn sheet(&self) -> &SpriteSheet {
+        &self.sheet
+    }
+
+    pub fn animation_frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    // 1 周の時間
+    pub fn duration_ms(&self) -> u64 {
+        self.frames.iter().map(|frame| frame.duration_ms as u64).sum()
+    }
+
+    pub fn validate(&self) -> Result<(), SpriteError> {
+        if self.frames.is_empty() {
+            return Err(SpriteError::EmptyAnimation);
+        }
+        match self.frames.iter().find(|frame| frame.index >= self.sheet.frame_count()) {
+            Some(frame) => Err(SpriteError::FrameOutOfRange(frame.index)),
+            None => Ok(()),
+        }
+    }
+}
+
+// 画面上のアニメーション
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sprite {
+    pub animation: Animation,
+    pub position: Point,
+    pub flip: Flip,
+}
+
+impl Sprite {
+    pub fn new(animation: Animation, position: Point) -> Self {
+        Sprite { animation, position, flip: Flip::None }
+    }
+
+    #[must_use]
+    pub fn flip(mut self, flip: Flip) -> Self {
+        self.flip = flip;
+        self
+    }
+}
+
+// 表示スレッドでスプライトを識別する番号 (アプリが決める)
+pub type SpriteId = u8;
+
+struct ActiveSprite {
+    id: SpriteId,
+    sprite: Sprite,
+    // animation_frames() の何番目を表示中か
+    position: usize,
+    // 表示中のフレームを終える時刻 (None は止まっている)
+    deadline_ms: Option<u64>,
+}
+
+// 表示中のスプライトと、フレームを切り替える時刻の管理
+// 時刻は単調増加するミリ秒 (基準はどこでもよい)
+#[derive(Default)]
+pub struct Animator {
+    sprites: Vec<ActiveSprite>,
+}
+
+impl Animator {
+    pub fn new() -> Self {
+        Animator { sprites: Vec::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    pub fn contains(&self, id: SpriteId) -> bool {
+        self.sprites.iter().any(|active| active.id == id)
+    }
+
+    // 同じ番号のスプライトは置き換える (後から始めたものほど手前に描く)
+    pub fn start(&mut self, id: SpriteId, sprite: Sprite, now_ms: u64) -> Result<(), SpriteError> {
+        sprite.animation.validate()?;
+        self.stop(id);
+        let deadline_ms = Self::deadline(&sprite.animation, 0, now_ms);
+        self.sprites.push(ActiveSprite { id, sprite, position: 0, deadline_ms });
+        Ok(())
+    }
+
+    // 止めて消す (なければ false)
+    pub fn stop(&mut self, id: SpriteId) -> bool {
+        let count = self.sprites.len();
+        self.sprites.retain(|active| active.id != id);
+        self.sprites.len() != count
+    }
+
+    pub fn stop_all(&mut self) {
+        self.sprites.clear();
+    }
+
+    // アニメーションを続けたまま動かす (なければ false)
+    pub fn move_to(&mut self, id: SpriteId, position: Point, flip: Flip) -> bool {
+        match self.sprites.iter_mut().find(|active| active.id == id) {
+            Some(active) => {
+                active.sprite.position = position;
+                active.sprite.flip = flip;
+                true
+            },
+            None => false,
+        }
+    }
+
+    // 表示中のフレーム番号 (シート内の番号)
+    pub fn current_frame(&self, id: SpriteId) -> Option<usize> {
+        let active = self.sprites.iter().find(|active| active.id == id)?;
+        Some(active.sprite.animation.frames[active.position].index)
+    }
+
+    fn deadline(animation: &Animation, position: usize, start_ms: u64) -> Option<u64> {
+        let last = position + 1 == animation.frames.len();
+        if last && !animation.looping {
+            None
+        } else {
+            Some(start_ms + animation.frames[position].duration_ms as u64)
+        }
+    }
+
+    // 次にフレームを切り替える時刻 (切り替えるものがなければ None)
+    pub fn next_deadline_ms(&self) -> Option<u64> {
+        self.sprites.iter().filter_map(|active| active.deadline_ms).min()
+    }
+
+    // now_ms までに切り替わるフレームを進める (表示が変わったら true)
+    // 処理が遅れても時刻はずらさず、飛ばしたフレームは表示しない
+    pub fn advance(&mut self, now_ms: u64) -> bool {
+        let mut changed = false;
+        for active in self.sprites.iter_mut() {
+            let animation = &active.sprite.animation;
+            let previous = active.position;
+            while let Some(deadline_ms) = active.deadline_ms {
+                if deadline_ms > now_ms {
+                    break;
+                }
+                active.position = (active.position + 1) % animation.frames.len();
+                active.deadline_ms = Self::deadline(animation, active.position, deadline_ms);
+                // 長さ 0 のフレームだけのアニメーションで止まらなくならないように
+                if animation.duration_ms() == 0 {
+                    active.deadline_ms = None;
+                }
+            }
+            changed |= animation.frames[active.position].index != animation.frames[previous].index;
+        }
+        changed
+    }
+
+    // base にスプライトを重ねた画面
+    pub fn compose(&self, base: &FrameBuffer) -> FrameBuffer {
+        let mut frame = base.clone();
+        for active in self.sprites.iter() {
+            let sprite = &active.sprite;
+            let index = sprite.animation.frames[active.position].index;
+            // start() で確認済みなので失敗しない
+            sprite.animation.sheet.draw(&mut frame, index, sprite.position, sprite.flip).unwrap();
+        }
+        frame
+    }
+}
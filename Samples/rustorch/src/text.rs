@@ -0,0 +1,362 @@
+// OLED 向けのテキスト描画 (ハードウェア非依存)
+// - フォントの選択、色と白黒反転、枠内での寄せ、単語単位の折り返しと省略 (...)
+// - どのフォントも等幅なので、折り返しは文字数で計算する
+// - 日本語は build.rs で埋め込んだ BitmapFont で描く (半角英数字は全角のグリフで代用する)
+
+use embedded_graphics::{
+    mono_font::{
+        ascii::{FONT_10X20, FONT_4X6, FONT_6X10, FONT_8X13},
+        MonoFont, MonoTextStyle,
+    },
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Baseline, Text},
+};
+
+use crate::bitmap_font::BitmapFont;
+use crate::led_format::NUMBER_SEGMENT_TABLE;
+
+pub const ELLIPSIS: &str = "...";
+
+// 7 セグ風の大きな数字 (タイマー表示向け)
+// ビット配置は LedDriver と同じ (bit7: a, bit6: b, ... bit1: g)
+const DIGIT_WIDTH: u32 = 18;
+const DIGIT_HEIGHT: u32 = 31;
+const DIGIT_SPACING: u32 = 4;
+// セグメントの太さ
+const DIGIT_THICKNESS: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    // 4x6
+    Small,
+    // 6x10 (DisplayDriver::draw_text() と同じ)
+    Normal,
+    // 8x13
+    Medium,
+    // 10x20
+    Large,
+    // 18x31 の 7 セグ風数字 ('0'~'9', ':', '.', '-', ' ' のみ、それ以外は空白)
+    Digits,
+    // 埋め込みのビットマップフォント (かな・漢字、ASCII 以外も描ける)
+    Bitmap(&'static BitmapFont),
+}
+
+impl Font {
+    fn mono_font(self) -> Option<&'static MonoFont<'static>> {
+        match self {
+            Font::Small => Some(&FONT_4X6),
+            Font::Normal => Some(&FONT_6X10),
+            Font::Medium => Some(&FONT_8X13),
+            Font::Large => Some(&FONT_10X20),
+            Font::Digits | Font::Bitmap(_) => None,
+        }
+    }
+
+    // 1 文字の大きさ (文字間隔を含まない)
+    pub fn character_size(self) -> Size {
+        match self.mono_font() {
+            Some(font) => font.character_size,
+            None => match self {
+                Font::Bitmap(font) => Size::new(font.width, font.height),
+                _ => Size::new(DIGIT_WIDTH, DIGIT_HEIGHT),
+            },
+        }
+    }
+
+    // 文字送り (1 文字の幅 + 文字間隔)
+    pub fn advance(self) -> u32 {
+        match self.mono_font() {
+            Some(font) => font.character_size.width + font.character_spacing,
+            None => match self {
+                // 文字間隔はグリフに含まれている
+                Font::Bitmap(font) => font.width,
+                _ => DIGIT_WIDTH + DIGIT_SPACING,
+            },
+        }
+    }
+
+    pub fn line_height(self) -> u32 {
+        self.character_size().height
+    }
+
+    // columns 文字の行の幅 (末尾の文字間隔は含まない)
+    pub fn text_width(self, columns: usize) -> u32 {
+        if columns == 0 {
+            return 0;
+        }
+        self.advance() * columns as u32 - (self.advance() - self.character_size().width)
+    }
+
+    // width に収まる文字数
+    pub fn columns_in(self, width: u32) -> usize {
+        ((width + self.advance() - self.character_size().width) / self.advance()) as usize
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HorizontalAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerticalAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextStyle {
+    pub font: Font,
+    // 文字の色
+    pub color: BinaryColor,
+    // 枠全体を color で塗り、文字を反対の色で抜く
+    pub inverted: bool,
+    pub horizontal: HorizontalAlign,
+    pub vertical: VerticalAlign,
+    // 単語単位で折り返す (false なら改行文字でのみ改行し、はみ出た分は省略する)
+    pub wrap: bool,
+}
+
+impl TextStyle {
+    // 6x10、点灯色、左上寄せ、折り返しなし
+    pub const fn new() -> Self {
+        TextStyle {
+            font: Font::Normal,
+            color: BinaryColor::On,
+            inverted: false,
+            horizontal: HorizontalAlign::Left,
+            vertical: VerticalAlign::Top,
+            wrap: false,
+        }
+    }
+
+    #[must_use]
+    pub fn font(mut self, font: Font) -> Self {
+        self.font = font;
+        self
+    }
+
+    #[must_use]
+    pub fn color(mut self, color: BinaryColor) -> Self {
+        self.color = color;
+        self
+    }
+
+    #[must_use]
+    pub fn inverted(mut self, inverted: bool) -> Self {
+        self.inverted = inverted;
+        self
+    }
+
+    #[must_use]
+    pub fn align(mut self, horizontal: HorizontalAlign, vertical: VerticalAlign) -> Self {
+        self.horizontal = horizontal;
+        self.vertical = vertical;
+        self
+    }
+
+    #[must_use]
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 行を max_columns 文字に切り詰め、切り詰めた場合は末尾を ... にする
+fn truncate(line: &str, max_columns: usize, force_ellipsis: bool) -> String {
+    let length = line.chars().count();
+    if length <= max_columns && !force_ellipsis {
+        return line.to_string();
+    }
+    let ellipsis_length = ELLIPSIS.len().min(max_columns);
+    let keep = length.min(max_columns - ellipsis_length);
+    let mut result: String = line.chars().take(keep).collect();
+    result.push_str(&ELLIPSIS[..ellipsis_length]);
+    result
+}
+
+// 1 段落を単語単位で折り返す (1 行に収まらない単語は途中で切る)
+fn wrap_paragraph(paragraph: &str, max_columns: usize, lines: &mut Vec<String>) {
+    let first = lines.len();
+    let mut line = String::new();
+    let mut line_length = 0;
+    for word in paragraph.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        if line_length > 0 && line_length + 1 + word.len() <= max_columns {
+            line.push(' ');
+            line.extend(word.iter());
+            line_length += 1 + word.len();
+            continue;
+        }
+        if line_length > 0 {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > max_columns {
+            lines.push(word.drain(..max_columns).collect());
+        }
+        line_length = word.len();
+        line = word.into_iter().collect();
+    }
+    // 空の段落も 1 行として残す
+    if line_length > 0 || lines.len() == first {
+        lines.push(line);
+    }
+}
+
+// max_columns 文字 x max_lines 行に収まるように行に分ける
+// - 収まらなかった場合は最後の行の末尾を ... にする
+pub fn layout_lines(text: &str, max_columns: usize, max_lines: usize, wrap: bool) -> Vec<String> {
+    if max_columns == 0 || max_lines == 0 {
+        return Vec::new();
+    }
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        if wrap {
+            wrap_paragraph(paragraph, max_columns, &mut lines);
+        } else {
+            lines.push(truncate(paragraph, max_columns, false));
+        }
+    }
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = lines.pop().unwrap();
+        lines.push(truncate(&last, max_columns, true));
+    }
+    lines
+}
+
+// 枠内での各行の左上座標と内容
+pub fn layout(text: &str, area: &Rectangle, style: &TextStyle) -> Vec<(Point, String)> {
+    let font = style.font;
+    let max_columns = font.columns_in(area.size.width);
+    let max_lines = (area.size.height / font.line_height()) as usize;
+    let lines = layout_lines(text, max_columns, max_lines, style.wrap);
+
+    let block_height = lines.len() as u32 * font.line_height();
+    let top = match style.vertical {
+        VerticalAlign::Top => 0,
+        VerticalAlign::Middle => area.size.height.saturating_sub(block_height) / 2,
+        VerticalAlign::Bottom => area.size.height.saturating_sub(block_height),
+    };
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let width = font.text_width(line.chars().count());
+            let left = match style.horizontal {
+                HorizontalAlign::Left => 0,
+                HorizontalAlign::Center => area.size.width.saturating_sub(width) / 2,
+                HorizontalAlign::Right => area.size.width.saturating_sub(width),
+            };
+            let y = top + i as u32 * font.line_height();
+            (area.top_left + Point::new(left as i32, y as i32), line)
+        })
+        .collect()
+}
+
+// 7 セグ風の数字を 1 文字描く
+fn draw_digit<D>(target: &mut D, ch: char, top_left: Point, color: BinaryColor) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let (w, h, t) = (DIGIT_WIDTH as i32, DIGIT_HEIGHT as i32, DIGIT_THICKNESS as i32);
+    let middle = (h - t) / 2;
+    let style = PrimitiveStyle::with_fill(color);
+    let rect = |x: i32, y: i32, width: i32, height: i32| {
+        Rectangle::new(top_left + Point::new(x, y), Size::new(width as u32, height as u32)).into_styled(style)
+    };
+
+    let segments = match ch {
+        '0'..='9' => NUMBER_SEGMENT_TABLE[(ch as u8 - b'0') as usize],
+        '-' => 0x02,
+        ':' => {
+            rect((w - t) / 2, middle - 2 * t, t, t).draw(target)?;
+            return rect((w - t) / 2, middle + 2 * t, t, t).draw(target);
+        },
+        '.' => return rect((w - t) / 2, h - t, t, t).draw(target),
+        _ => 0x00,
+    };
+    // a, b, c, d, e, f, g の順
+    let shapes = [
+        rect(t, 0, w - 2 * t, t),
+        rect(w - t, t, t, middle - t),
+        rect(w - t, middle + t, t, h - middle - 2 * t),
+        rect(t, h - t, w - 2 * t, t),
+        rect(0, middle + t, t, h - middle - 2 * t),
+        rect(0, t, t, middle - t),
+        rect(t, middle, w - 2 * t, t),
+    ];
+    for (i, shape) in shapes.iter().enumerate() {
+        if segments & (0x80 >> i) != 0 {
+            shape.draw(target)?;
+        }
+    }
+    Ok(())
+}
+
+// ビットマップフォントで 1 文字描く
+// - 収録されていない半角英数字は全角のグリフで代用し、それもなければ四角 (豆腐) を描く
+fn draw_bitmap_char<D>(target: &mut D, font: &BitmapFont, ch: char, top_left: Point, color: BinaryColor) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    if ch == ' ' || ch == '\u{3000}' {
+        return Ok(());
+    }
+    let fullwidth = match ch {
+        '!'..='~' => char::from_u32(ch as u32 - '!' as u32 + 0xFF01),
+        _ => None,
+    };
+    let Some(glyph) = font.glyph(ch).or_else(|| fullwidth.and_then(|ch| font.glyph(ch))) else {
+        let size = Size::new(font.width.saturating_sub(2), font.height.saturating_sub(2));
+        return Rectangle::new(top_left + Point::new(1, 1), size)
+            .into_styled(PrimitiveStyle::with_stroke(color, 1))
+            .draw(target);
+    };
+    let pixels = (0..font.height).flat_map(|y| (0..font.width).map(move |x| (x, y)))
+        .filter(|(x, y)| glyph.pixel(*x, *y))
+        .map(|(x, y)| Pixel(top_left + Point::new(x as i32, y as i32), color));
+    target.draw_iter(pixels)
+}
+
+// 枠内にテキストを描く
+pub fn draw_text_box<D>(target: &mut D, text: &str, area: &Rectangle, style: &TextStyle) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let color = if style.inverted {
+        area.into_styled(PrimitiveStyle::with_fill(style.color)).draw(target)?;
+        style.color.invert()
+    } else {
+        style.color
+    };
+    for (point, line) in layout(text, area, style) {
+        match style.font.mono_font() {
+            Some(font) => {
+                let character_style = MonoTextStyle::new(font, color);
+                Text::with_baseline(&line, point, character_style, Baseline::Top).draw(target)?;
+            },
+            None => {
+                for (i, ch) in line.chars().enumerate() {
+                    let top_left = point + Point::new((i as u32 * style.font.advance()) as i32, 0);
+                    match style.font {
+                        Font::Bitmap(font) => draw_bitmap_char(target, font, ch, top_left, color)?,
+                        _ => draw_digit(target, ch, top_left, color)?,
+                    }
+                }
+            },
+        }
+    }
+    Ok(())
+}
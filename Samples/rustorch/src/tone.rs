@@ -0,0 +1,179 @@
+// ブザーの音量と音色 (PWM のデューティ比で表現する)
+// - 圧電ブザーはデューティ比 50% の矩形波が最も大きく鳴り、デューティ比を下げるほど
+//   音が小さく、倍音の多い細い音になる
+// - 音色 (Timbre) で最大音量時のデューティ比を決め、音量とエンベロープでそれを縮める
+
+use crate::pitch_effect::PitchEffect;
+
+// エンベロープの変化中にデューティ比を更新する周期 [us]
+pub const ENVELOPE_STEP_US: i64 = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timbre {
+    // デューティ比 50% (最も大きく柔らかい音)
+    Square,
+    // デューティ比 25%
+    Pulse25,
+    // デューティ比 12.5%
+    Pulse12,
+    // デューティ比 6.25% (小さく硬い音)
+    Pulse6,
+}
+
+impl Timbre {
+    // 最大音量時のデューティ比 [‰]
+    pub fn duty_permille(&self) -> u32 {
+        match self {
+            Timbre::Square => 500,
+            Timbre::Pulse25 => 250,
+            Timbre::Pulse12 => 125,
+            Timbre::Pulse6 => 63,
+        }
+    }
+}
+
+// 発音開始からの音量変化 (アタック → ディケイ → サスティン)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Envelope {
+    // 0 から最大音量に達するまでの時間 [ms]
+    pub attack_ms: u32,
+    // 最大音量からサスティンレベルに下がるまでの時間 [ms]
+    pub decay_ms: u32,
+    // 最大音量に対する割合 [%]
+    pub sustain_percent: u8,
+}
+
+impl Envelope {
+    // 変化なし (発音と同時に最大音量)
+    pub const FLAT: Envelope = Envelope { attack_ms: 0, decay_ms: 0, sustain_percent: 100 };
+
+    pub fn new(attack_ms: u32, decay_ms: u32, sustain_percent: u8) -> Self {
+        Envelope { attack_ms, decay_ms, sustain_percent: sustain_percent.min(100) }
+    }
+
+    // 発音開始から elapsed_us 経過時点の音量 [‰]
+    pub fn level_permille(&self, elapsed_us: i64) -> u32 {
+        let elapsed_us = elapsed_us.max(0);
+        let attack_us = self.attack_ms as i64 * 1000;
+        let decay_us = self.decay_ms as i64 * 1000;
+        let sustain = self.sustain_percent as i64 * 10;
+        let level = if elapsed_us < attack_us {
+            elapsed_us * 1000 / attack_us
+        } else if elapsed_us < attack_us + decay_us {
+            1000 - (1000 - sustain) * (elapsed_us - attack_us) / decay_us
+        } else {
+            sustain
+        };
+        level as u32
+    }
+
+    // 発音開始からこの時間 [us] が経過すると音量が変化しなくなる
+    pub fn settle_us(&self) -> i64 {
+        (self.attack_ms as i64 + self.decay_ms as i64) * 1000
+    }
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Envelope::FLAT
+    }
+}
+
+// 音量・音色・エンベロープ・高さの変化の組
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instrument {
+    // 0 ~ 100% (範囲外は 100% に丸める)
+    pub volume_percent: u8,
+    pub timbre: Timbre,
+    pub envelope: Envelope,
+    pub pitch: PitchEffect,
+}
+
+impl Instrument {
+    pub fn new() -> Self {
+        Instrument {
+            volume_percent: 100,
+            timbre: Timbre::Square,
+            envelope: Envelope::FLAT,
+            pitch: PitchEffect::NONE,
+        }
+    }
+
+    #[must_use]
+    pub fn volume(mut self, percent: u8) -> Self {
+        self.volume_percent = percent.min(100);
+        self
+    }
+
+    #[must_use]
+    pub fn timbre(mut self, timbre: Timbre) -> Self {
+        self.timbre = timbre;
+        self
+    }
+
+    #[must_use]
+    pub fn envelope(mut self, envelope: Envelope) -> Self {
+        self.envelope = envelope;
+        self
+    }
+
+    #[must_use]
+    pub fn pitch(mut self, pitch: PitchEffect) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    // 木琴のような減衰音
+    pub fn pluck() -> Self {
+        Instrument::new().envelope(Envelope::new(0, 300, 20))
+    }
+
+    // 立ち上がりの遅い柔らかい音
+    pub fn soft() -> Self {
+        Instrument::new().timbre(Timbre::Pulse25).envelope(Envelope::new(60, 0, 100))
+    }
+
+    // 小さく短い電子音 (操作音向け)
+    pub fn beep() -> Self {
+        Instrument::new().timbre(Timbre::Pulse12).volume(60)
+    }
+}
+
+impl Default for Instrument {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 全アプリ共通の音量とミュート (BuzzerDriver が全ての出力に適用する)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MasterVolume {
+    pub volume_percent: u8,
+    pub muted: bool,
+}
+
+impl MasterVolume {
+    pub fn new() -> Self {
+        MasterVolume { volume_percent: 100, muted: false }
+    }
+}
+
+impl Default for MasterVolume {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 出力するデューティ比 [‰]
+pub fn output_duty_permille(instrument: &Instrument, master: &MasterVolume, elapsed_us: i64) -> u32 {
+    if master.muted {
+        return 0;
+    }
+    let level = instrument.envelope.level_permille(elapsed_us) as u64;
+    let duty = instrument.timbre.duty_permille() as u64
+        * instrument.volume_percent.min(100) as u64
+        * master.volume_percent.min(100) as u64
+        * level
+        / (100 * 100 * 1000);
+    duty as u32
+}
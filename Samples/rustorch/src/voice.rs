@@ -0,0 +1,252 @@
+// 優先度付きの発音チャンネル (ボイス) の切り替え (ハードウェア非依存)
+// - ブザーは 1 音しか出せないので、鳴っているボイスのうち最も優先度の高いものだけを出力する
+// - 優先度の高いボイスが鳴り終わると、中断していたボイスを中断した位置から再開する
+// - 同じボイスへの発音は、そのボイスで鳴っている音を置き換える
+
+use crate::pitch_effect::PitchGenerator;
+use crate::score::{PlayerStatus, Score, ScoreError, ScorePlayer};
+use crate::tone::Instrument;
+
+// 優先度の低い順に並べる (比較演算で優先度を判定する)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VoicePriority {
+    // アプリの音楽・効果音
+    Music,
+    // 操作音
+    Ui,
+    // タイマー満了などの通知
+    Alert,
+}
+
+impl VoicePriority {
+    pub const ALL: [VoicePriority; 3] = [VoicePriority::Music, VoicePriority::Ui, VoicePriority::Alert];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            VoicePriority::Music => "music",
+            VoicePriority::Ui => "ui",
+            VoicePriority::Alert => "alert",
+        }
+    }
+
+    // コマンドキューが一杯の時に空くまで待つか (待たない場合はコマンドを捨てる)
+    // 通知は取りこぼすと気付けないので待つ
+    pub fn waits_on_overflow(&self) -> bool {
+        matches!(self, VoicePriority::Alert)
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+struct Voice {
+    player: ScorePlayer,
+    generator: PitchGenerator,
+    // 単音・和音を鳴らしている場合の構成音とアルペジオの間隔 [us]
+    tone: Option<(Vec<u32>, i64)>,
+    // このボイスだけに適用する音色 (None は共通の音色)
+    instrument: Option<Instrument>,
+    // 優先度の高いボイスに中断された時刻
+    paused_at_us: Option<i64>,
+}
+
+impl Voice {
+    fn new() -> Self {
+        Voice {
+            player: ScorePlayer::new(),
+            generator: PitchGenerator::new(),
+            tone: None,
+            instrument: None,
+            paused_at_us: None,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.player.is_playing() || self.tone.is_some()
+    }
+
+    fn stop(&mut self) {
+        self.player.cancel();
+        self.generator.stop();
+        self.tone = None;
+        self.instrument = None;
+        self.paused_at_us = None;
+    }
+
+    // 今鳴らすべき音から高さの計算をやり直す
+    fn retrigger(&mut self, instrument: &Instrument, now_us: i64) {
+        if self.player.is_playing() {
+            match self.player.output() {
+                Some(frequency) => self.generator.start(vec![frequency], 0, &instrument.pitch, now_us),
+                None => self.generator.stop(),
+            }
+        } else if let Some((frequencies, step_us)) = self.tone.as_ref() {
+            self.generator.start(frequencies.clone(), *step_us, &instrument.pitch, now_us);
+        } else {
+            self.generator.stop();
+        }
+    }
+}
+
+// 出力すべき音
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MixerOutput {
+    // None は消音
+    pub frequency: Option<u32>,
+    // 新しい音が始まった (同じ高さでもエンベロープを最初からかけ直す)
+    pub note_started: bool,
+}
+
+pub struct Mixer {
+    voices: [Voice; 3],
+    // ボイス個別の音色がない場合の音色
+    instrument: Instrument,
+    // 出力中のボイス
+    current: Option<VoicePriority>,
+}
+
+impl Mixer {
+    pub fn new() -> Self {
+        Mixer {
+            voices: [Voice::new(), Voice::new(), Voice::new()],
+            instrument: Instrument::new(),
+            current: None,
+        }
+    }
+
+    pub fn set_instrument(&mut self, instrument: Instrument) {
+        self.instrument = instrument;
+    }
+
+    // 出力中のボイスの音色
+    pub fn instrument(&self) -> Instrument {
+        self.current
+            .and_then(|priority| self.voices[priority.index()].instrument)
+            .unwrap_or(self.instrument)
+    }
+
+    // 出力中のボイス
+    pub fn current(&self) -> Option<VoicePriority> {
+        self.current
+    }
+
+    pub fn is_active(&self, priority: VoicePriority) -> bool {
+        self.voices[priority.index()].is_active()
+    }
+
+    // 単音 (frequencies が複数あれば step_us 間隔のアルペジオ) を止めるまで鳴らす
+    pub fn start_tone(&mut self, priority: VoicePriority, frequencies: Vec<u32>, step_us: i64) {
+        let voice = &mut self.voices[priority.index()];
+        voice.stop();
+        voice.tone = Some((frequencies, step_us));
+        self.restart_if_current(priority);
+    }
+
+    // 楽譜を演奏する (instrument を指定するとこの楽譜だけその音色で鳴らす)
+    pub fn play(&mut self, priority: VoicePriority, score: Score, instrument: Option<Instrument>, now_us: i64)
+        -> Result<(), ScoreError>
+    {
+        let voice = &mut self.voices[priority.index()];
+        voice.stop();
+        voice.player.start(score, now_us)?;
+        voice.instrument = instrument;
+        // 優先度の高いボイスが鳴っている間は開始を待つ (出力を始めた時点で最初から演奏する)
+        voice.paused_at_us = Some(now_us);
+        self.restart_if_current(priority);
+        Ok(())
+    }
+
+    pub fn stop(&mut self, priority: VoicePriority) {
+        self.voices[priority.index()].stop();
+    }
+
+    pub fn stop_all(&mut self) {
+        for voice in self.voices.iter_mut() {
+            voice.stop();
+        }
+    }
+
+    pub fn status(&mut self, priority: VoicePriority, now_us: i64) -> PlayerStatus {
+        // 中断中のボイスは中断した位置のまま
+        if self.current == Some(priority) {
+            self.voices[priority.index()].player.update(now_us);
+        }
+        self.voices[priority.index()].player.status()
+    }
+
+    // 出力中のボイスで次に出力が変わる可能性のある時刻
+    pub fn next_deadline_us(&self, now_us: i64) -> Option<i64> {
+        let priority = self.current?;
+        let voice = &self.voices[priority.index()];
+        let pitch = self.instrument().pitch;
+        [voice.player.next_deadline_us(), voice.generator.next_update_us(&pitch, now_us)]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    // 時刻 now_us に出力すべき音を求める
+    pub fn update(&mut self, now_us: i64) -> MixerOutput {
+        let mut note_started = false;
+        loop {
+            let top = VoicePriority::ALL.iter().rev().copied().find(|priority| self.is_active(*priority));
+            if top != self.current {
+                // 中断するボイスは再開まで時刻を止める
+                if let Some(previous) = self.current {
+                    let voice = &mut self.voices[previous.index()];
+                    if voice.is_active() {
+                        voice.paused_at_us = Some(now_us);
+                        voice.generator.stop();
+                    }
+                }
+                self.current = top;
+                if let Some(priority) = top {
+                    let instrument = self.instrument();
+                    let voice = &mut self.voices[priority.index()];
+                    if let Some(paused_at_us) = voice.paused_at_us.take() {
+                        voice.player.shift(now_us - paused_at_us);
+                    }
+                    voice.retrigger(&instrument, now_us);
+                }
+                note_started = true;
+            }
+            let Some(priority) = top else {
+                return MixerOutput { frequency: None, note_started };
+            };
+
+            let instrument = self.instrument();
+            let voice = &mut self.voices[priority.index()];
+            if voice.player.is_playing() {
+                let before = voice.player.status();
+                voice.player.update(now_us);
+                let after = voice.player.status();
+                // 音符が切り替わったら (同じ高さの連続でも) エンベロープを最初からやり直す
+                if (before.position, before.loop_iteration) != (after.position, after.loop_iteration) {
+                    voice.retrigger(&instrument, now_us);
+                    note_started = true;
+                }
+                if !voice.player.is_playing() {
+                    // 演奏が終わったら中断していたボイスに戻る
+                    voice.stop();
+                    continue;
+                }
+            }
+            let frequency = voice.generator.update(&instrument.pitch, now_us);
+            return MixerOutput { frequency, note_started };
+        }
+    }
+
+    // 出力中のボイスに新しい音を設定したら最初から鳴らし直す
+    fn restart_if_current(&mut self, priority: VoicePriority) {
+        if self.current == Some(priority) {
+            self.current = None;
+        }
+    }
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
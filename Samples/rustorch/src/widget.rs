@@ -0,0 +1,678 @@
+// OLED 向けのウィジェット (ハードウェア非依存)
+// - 状態を持つウィジェットを Screen に配置し、ボタン入力でフォーカスを移して操作する
+// - 描画は DisplayFrame に積むだけなので、DisplayDriver::submit() でまとめて表示する
+// - フォーカス中のウィジェットが使わなかった ↑↓ はフォーカスの移動に使う
+//
+// 使い方:
+//   let mut screen = Screen::new();
+//   let areas = layout::stack(layout::SCREEN, Direction::Vertical, &[Length::Fixed(10), Length::Fill]);
+//   let volume = screen.add(areas[0], Spinner::new("Volume", 50, 0, 100).step(10));
+//   ...
+//   for (id, event) in screen.handle(context.button.lock().unwrap().was_released(Button::MASK)) { ... }
+//   context.display.lock().unwrap().submit(screen.draw(), SubmitPolicy::DropIfBusy)?;
+
+use std::any::Any;
+use std::cell::Cell;
+
+use embedded_graphics::{
+    pixelcolor::BinaryColor,
+    prelude::*,
+    primitives::Rectangle,
+};
+
+use crate::button::Button;
+use crate::display_command::{fill, outlined_fill, stroke, DisplayFrame};
+use crate::text::{Font, HorizontalAlign, TextStyle, VerticalAlign};
+
+// ボタン入力を受けたウィジェットからの通知
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WidgetEvent {
+    // 使わなかった (Screen がフォーカスの移動に使う)
+    Ignored,
+    // 使ったが値は変わらなかった (端で止まったなど)
+    Consumed,
+    // 値が変わった
+    Changed,
+    // A で決定した
+    Activated,
+    // B で取り消した
+    Cancelled,
+}
+
+// Screen から具体的な型に戻すため
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+pub trait Widget: AsAny {
+    // area に描く (focused はフォーカスがあるかどうか)
+    fn draw(&self, frame: &mut DisplayFrame, area: Rectangle, focused: bool);
+
+    // ボタン 1 つ分 (Button::UP などのいずれか 1 ビット) の入力
+    fn handle(&mut self, _button: u8) -> WidgetEvent {
+        WidgetEvent::Ignored
+    }
+
+    // フォーカスを受け取るか (表示だけのウィジェットは false)
+    fn is_focusable(&self) -> bool {
+        true
+    }
+}
+
+// 画面の分割
+pub mod layout {
+    use embedded_graphics::{prelude::*, primitives::Rectangle};
+
+    use crate::frame_buffer::{HEIGHT, WIDTH};
+
+    pub const SCREEN: Rectangle = Rectangle::new(Point::zero(), Size::new(WIDTH, HEIGHT));
+    // Font::Normal の 1 行分
+    pub const ROW_HEIGHT: u32 = 10;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Direction {
+        Vertical,
+        Horizontal,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Length {
+        Fixed(u32),
+        // 固定長の残りを Fill 同士で等分する (端数は先頭から 1 ずつ配る)
+        Fill,
+    }
+
+    // area を direction 方向に並べて分割する
+    pub fn stack(area: Rectangle, direction: Direction, lengths: &[Length]) -> Vec<Rectangle> {
+        let total = match direction {
+            Direction::Vertical => area.size.height,
+            Direction::Horizontal => area.size.width,
+        };
+        let fixed: u32 = lengths.iter().map(|length| match length {
+            Length::Fixed(length) => *length,
+            Length::Fill => 0,
+        }).sum();
+        let fill_count = lengths.iter().filter(|length| **length == Length::Fill).count() as u32;
+        let rest = total.saturating_sub(fixed);
+
+        let mut offset = 0;
+        let mut fill_index = 0;
+        lengths.iter().map(|length| {
+            let length = match length {
+                Length::Fixed(length) => (*length).min(total.saturating_sub(offset)),
+                Length::Fill => {
+                    let length = rest / fill_count + if fill_index < rest % fill_count { 1 } else { 0 };
+                    fill_index += 1;
+                    length
+                },
+            };
+            let rectangle = match direction {
+                Direction::Vertical => Rectangle::new(area.top_left + Point::new(0, offset as i32), Size::new(area.size.width, length)),
+                Direction::Horizontal => Rectangle::new(area.top_left + Point::new(offset as i32, 0), Size::new(length, area.size.height)),
+            };
+            offset += length;
+            rectangle
+        }).collect()
+    }
+
+    // ROW_HEIGHT ずつの行に分割する (収まらない端数は捨てる)
+    pub fn rows(area: Rectangle) -> Vec<Rectangle> {
+        let count = area.size.height / ROW_HEIGHT;
+        stack(area, Direction::Vertical, &vec![Length::Fixed(ROW_HEIGHT); count as usize])
+    }
+
+    // 周囲を margin ずつ狭める
+    pub fn inset(area: Rectangle, margin: u32) -> Rectangle {
+        area.offset(-(margin as i32))
+    }
+}
+
+fn text_style(focused: bool) -> TextStyle {
+    TextStyle::new().inverted(focused).align(HorizontalAlign::Left, VerticalAlign::Middle)
+}
+
+// スクロールするリスト (メニュー)
+// ↑↓ で選択、A で決定 (Activated)、B で取り消し (Cancelled)
+pub struct ListMenu {
+    items: Vec<String>,
+    selected: usize,
+    // 先頭に表示している項目 (描画時に選択中の項目が見えるように調整する)
+    first_visible: Cell<usize>,
+    wrap_around: bool,
+}
+
+impl ListMenu {
+    pub fn new<S: ToString>(items: &[S]) -> Self {
+        ListMenu {
+            items: items.iter().map(|item| item.to_string()).collect(),
+            selected: 0,
+            first_visible: Cell::new(0),
+            wrap_around: true,
+        }
+    }
+
+    #[must_use]
+    pub fn wrap_around(mut self, enable: bool) -> Self {
+        self.wrap_around = enable;
+        self
+    }
+
+    #[must_use]
+    pub fn with_selected(mut self, index: usize) -> Self {
+        self.select(index);
+        self
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.items.len().saturating_sub(1));
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    // area に表示する項目の範囲
+    pub fn visible_range(&self, area: Rectangle) -> std::ops::Range<usize> {
+        let rows = (area.size.height / layout::ROW_HEIGHT).max(1) as usize;
+        let mut first = self.first_visible.get();
+        if self.selected < first {
+            first = self.selected;
+        } else if self.selected >= first + rows {
+            first = self.selected + 1 - rows;
+        }
+        first = first.min(self.items.len().saturating_sub(rows));
+        self.first_visible.set(first);
+        first..(first + rows).min(self.items.len())
+    }
+}
+
+impl Widget for ListMenu {
+    fn draw(&self, frame: &mut DisplayFrame, area: Rectangle, focused: bool) {
+        let range = self.visible_range(area);
+        let overflow = range.len() < self.items.len();
+        // スクロールバーの分だけ項目の幅を狭める
+        let item_width = if overflow { area.size.width - 3 } else { area.size.width };
+        for (row, index) in range.clone().enumerate() {
+            let row_area = Rectangle::new(
+                area.top_left + Point::new(0, (row as u32 * layout::ROW_HEIGHT) as i32),
+                Size::new(item_width, layout::ROW_HEIGHT),
+            );
+            let selected = index == self.selected;
+            frame.draw_text_box(self.items[index].clone(), row_area, text_style(selected && focused));
+            if selected && !focused {
+                frame.draw_rectangle(row_area, stroke(1));
+            }
+        }
+        if overflow {
+            let bar_height = area.size.height * range.len() as u32 / self.items.len() as u32;
+            let bar_top = area.size.height * range.start as u32 / self.items.len() as u32;
+            let bar = Rectangle::new(area.top_left + Point::new(area.size.width as i32 - 2, bar_top as i32), Size::new(2, bar_height.max(2)));
+            frame.draw_rectangle(bar, fill());
+        }
+    }
+
+    fn handle(&mut self, button: u8) -> WidgetEvent {
+        let count = self.items.len();
+        if count == 0 {
+            return WidgetEvent::Ignored;
+        }
+        // 折り返さない場合、端から先の ↑↓ は使わない (Screen がフォーカスを移す)
+        match button {
+            Button::UP if self.selected > 0 => self.selected -= 1,
+            Button::UP if self.wrap_around => self.selected = count - 1,
+            Button::DOWN if self.selected + 1 < count => self.selected += 1,
+            Button::DOWN if self.wrap_around => self.selected = 0,
+            Button::A => return WidgetEvent::Activated,
+            Button::B => return WidgetEvent::Cancelled,
+            _ => return WidgetEvent::Ignored,
+        }
+        WidgetEvent::Changed
+    }
+}
+
+// ラベル付きの数値 (←→ で増減)
+pub struct Spinner {
+    label: String,
+    value: i32,
+    min: i32,
+    max: i32,
+    step: i32,
+    // 単位など値の後ろに付ける文字列
+    suffix: String,
+}
+
+impl Spinner {
+    pub fn new(label: &str, value: i32, min: i32, max: i32) -> Self {
+        Spinner { label: label.to_string(), value: value.clamp(min, max), min, max, step: 1, suffix: String::new() }
+    }
+
+    #[must_use]
+    pub fn step(mut self, step: i32) -> Self {
+        self.step = step.max(1);
+        self
+    }
+
+    #[must_use]
+    pub fn suffix(mut self, suffix: &str) -> Self {
+        self.suffix = suffix.to_string();
+        self
+    }
+
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    pub fn set_value(&mut self, value: i32) {
+        self.value = value.clamp(self.min, self.max);
+    }
+}
+
+impl Widget for Spinner {
+    fn draw(&self, frame: &mut DisplayFrame, area: Rectangle, focused: bool) {
+        let value = if focused {
+            format!("<{}{}>", self.value, self.suffix)
+        } else {
+            format!("{}{}", self.value, self.suffix)
+        };
+        frame.draw_text_box(self.label.clone(), area, text_style(focused));
+        let value_style = TextStyle::new().inverted(false).color(if focused { BinaryColor::Off } else { BinaryColor::On })
+            .align(HorizontalAlign::Right, VerticalAlign::Middle);
+        frame.draw_text_box(value, area, value_style);
+    }
+
+    fn handle(&mut self, button: u8) -> WidgetEvent {
+        let previous = self.value;
+        match button {
+            Button::LEFT => self.value = (self.value - self.step).max(self.min),
+            Button::RIGHT => self.value = (self.value + self.step).min(self.max),
+            _ => return WidgetEvent::Ignored,
+        }
+        if self.value != previous { WidgetEvent::Changed } else { WidgetEvent::Consumed }
+    }
+}
+
+// 横長の進捗バー (表示のみ)
+pub struct ProgressBar {
+    value: u32,
+    max: u32,
+}
+
+impl ProgressBar {
+    pub fn new(max: u32) -> Self {
+        ProgressBar { value: 0, max: max.max(1) }
+    }
+
+    pub fn set_value(&mut self, value: u32) {
+        self.value = value.min(self.max);
+    }
+
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+
+    // 枠の内側で塗りつぶす幅
+    pub fn filled_width(&self, area: Rectangle) -> u32 {
+        let inner = area.size.width.saturating_sub(4);
+        (inner as u64 * self.value as u64 / self.max as u64) as u32
+    }
+}
+
+impl Widget for ProgressBar {
+    fn draw(&self, frame: &mut DisplayFrame, area: Rectangle, _focused: bool) {
+        frame.draw_rectangle(area, outlined_fill(1));
+        let inner = Rectangle::new(area.top_left + Point::new(2, 2), Size::new(self.filled_width(area), area.size.height.saturating_sub(4)));
+        frame.draw_rectangle(inner, fill());
+    }
+
+    fn is_focusable(&self) -> bool {
+        false
+    }
+}
+
+// 円弧を近似する折れ線の分割数
+const GAUGE_SEGMENTS: u32 = 16;
+
+// 半円のメーター (表示のみ)
+pub struct Gauge {
+    value: i32,
+    min: i32,
+    max: i32,
+}
+
+impl Gauge {
+    pub fn new(min: i32, max: i32) -> Self {
+        Gauge { value: min, min, max: max.max(min + 1) }
+    }
+
+    pub fn set_value(&mut self, value: i32) {
+        self.value = value.clamp(self.min, self.max);
+    }
+
+    pub fn value(&self) -> i32 {
+        self.value
+    }
+
+    // 針の角度 [度] (左端 180 度から時計回りに右端 360 度まで)
+    pub fn needle_angle(&self) -> f32 {
+        180.0 + 180.0 * (self.value - self.min) as f32 / (self.max - self.min) as f32
+    }
+}
+
+impl Widget for Gauge {
+    fn draw(&self, frame: &mut DisplayFrame, area: Rectangle, _focused: bool) {
+        // 下辺の中央を中心にした、収まる最大の半円
+        let diameter = area.size.width.min(area.size.height * 2).saturating_sub(1) | 1;
+        let radius = diameter / 2;
+        let center = area.top_left + Point::new(area.size.width as i32 / 2, radius as i32);
+        // 円弧は折れ線で近似する
+        let points = (0..=GAUGE_SEGMENTS).map(|i| {
+            let angle = (180.0 + 180.0 * i as f32 / GAUGE_SEGMENTS as f32).to_radians();
+            center + Point::new((angle.cos() * radius as f32).round() as i32, (angle.sin() * radius as f32).round() as i32)
+        }).collect();
+        frame.draw_polyline(points, stroke(1));
+        let angle = self.needle_angle().to_radians();
+        let length = radius.saturating_sub(2) as f32;
+        let tip = center + Point::new((angle.cos() * length).round() as i32, (angle.sin() * length).round() as i32);
+        frame.draw_line(center, tip, stroke(1));
+        frame.draw_circle(center - Point::new(1, 1), 3, fill());
+    }
+
+    fn is_focusable(&self) -> bool {
+        false
+    }
+}
+
+// はい/いいえの確認 (←→ で選択、A で決定、B でいいえとして取り消し)
+pub struct Dialog {
+    message: String,
+    yes: bool,
+}
+
+impl Dialog {
+    pub fn new(message: &str) -> Self {
+        Dialog { message: message.to_string(), yes: false }
+    }
+
+    // 決定された (または選択中の) 答え
+    pub fn answer(&self) -> bool {
+        self.yes
+    }
+}
+
+impl Widget for Dialog {
+    fn draw(&self, frame: &mut DisplayFrame, area: Rectangle, focused: bool) {
+        frame.draw_rounded_rectangle(area, 3, outlined_fill(1));
+        let inner = layout::inset(area, 3);
+        let parts = layout::stack(inner, layout::Direction::Vertical, &[layout::Length::Fill, layout::Length::Fixed(layout::ROW_HEIGHT)]);
+        let message_style = TextStyle::new().wrap(true).align(HorizontalAlign::Center, VerticalAlign::Middle);
+        frame.draw_text_box(self.message.clone(), parts[0], message_style);
+
+        let buttons = layout::stack(parts[1], layout::Direction::Horizontal, &[layout::Length::Fill, layout::Length::Fill]);
+        for (label, area, selected) in [("Yes", buttons[0], self.yes), ("No", buttons[1], !self.yes)] {
+            let style = TextStyle::new().inverted(selected && focused).align(HorizontalAlign::Center, VerticalAlign::Middle);
+            frame.draw_text_box(label.to_string(), area, style);
+            if selected && !focused {
+                frame.draw_rectangle(area, stroke(1));
+            }
+        }
+    }
+
+    fn handle(&mut self, button: u8) -> WidgetEvent {
+        match button {
+            Button::LEFT | Button::RIGHT => {
+                self.yes = !self.yes;
+                WidgetEvent::Changed
+            },
+            Button::A => WidgetEvent::Activated,
+            Button::B => {
+                self.yes = false;
+                WidgetEvent::Cancelled
+            },
+            _ => WidgetEvent::Ignored,
+        }
+    }
+}
+
+// 一定時間だけ表示するメッセージ (表示のみ、tick() をフレーム毎に呼ぶ)
+pub struct Toast {
+    message: String,
+    remaining_frames: u32,
+}
+
+impl Toast {
+    pub fn new() -> Self {
+        Toast { message: String::new(), remaining_frames: 0 }
+    }
+
+    pub fn show(&mut self, message: &str, frames: u32) {
+        self.message = message.to_string();
+        self.remaining_frames = frames;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.remaining_frames > 0
+    }
+
+    // 消えた瞬間だけ true (画面を描き直すきっかけにする)
+    pub fn tick(&mut self) -> bool {
+        if self.remaining_frames == 0 {
+            return false;
+        }
+        self.remaining_frames -= 1;
+        self.remaining_frames == 0
+    }
+}
+
+impl Default for Toast {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Widget for Toast {
+    fn draw(&self, frame: &mut DisplayFrame, area: Rectangle, _focused: bool) {
+        if self.is_visible() {
+            let style = TextStyle::new().inverted(true).align(HorizontalAlign::Center, VerticalAlign::Middle);
+            frame.draw_text_box(self.message.clone(), area, style);
+        }
+    }
+
+    fn is_focusable(&self) -> bool {
+        false
+    }
+}
+
+// 桁毎に入力する数値 (←→ で桁を選び、↑↓ で増減、A で決定、B で取り消し)
+pub struct NumberEntry {
+    label: String,
+    // 上位桁から
+    digits: Vec<u8>,
+    cursor: usize,
+}
+
+impl NumberEntry {
+    pub fn new(label: &str, digit_count: usize, value: u32) -> Self {
+        let digit_count = digit_count.clamp(1, 9);
+        let mut entry = NumberEntry { label: label.to_string(), digits: vec![0; digit_count], cursor: 0 };
+        entry.set_value(value);
+        entry
+    }
+
+    // 桁数に収まらない上位桁は捨てる
+    pub fn set_value(&mut self, value: u32) {
+        let mut rest = value;
+        for digit in self.digits.iter_mut().rev() {
+            *digit = (rest % 10) as u8;
+            rest /= 10;
+        }
+    }
+
+    pub fn value(&self) -> u32 {
+        self.digits.iter().fold(0, |value, digit| value * 10 + *digit as u32)
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+}
+
+impl Widget for NumberEntry {
+    fn draw(&self, frame: &mut DisplayFrame, area: Rectangle, focused: bool) {
+        frame.draw_text_box(self.label.clone(), area, text_style(false));
+        let font = Font::Normal;
+        let width = font.text_width(self.digits.len());
+        let left = area.top_left.x + area.size.width as i32 - width as i32;
+        let top = area.top_left.y + (area.size.height.saturating_sub(font.line_height()) / 2) as i32;
+        for (i, digit) in self.digits.iter().enumerate() {
+            let cell = Rectangle::new(Point::new(left + (i as u32 * font.advance()) as i32, top), Size::new(font.advance(), font.line_height()));
+            let selected = focused && i == self.cursor;
+            frame.draw_text_box(digit.to_string(), cell, TextStyle::new().inverted(selected));
+        }
+        if focused {
+            let underline = Rectangle::new(Point::new(left, top + font.line_height() as i32), Size::new(width, 1));
+            frame.draw_rectangle(underline, fill());
+        }
+    }
+
+    fn handle(&mut self, button: u8) -> WidgetEvent {
+        let digit = &mut self.digits[self.cursor];
+        match button {
+            Button::UP => *digit = (*digit + 1) % 10,
+            Button::DOWN => *digit = (*digit + 9) % 10,
+            Button::LEFT => {
+                self.cursor = self.cursor.saturating_sub(1);
+                return WidgetEvent::Consumed;
+            },
+            Button::RIGHT => {
+                self.cursor = (self.cursor + 1).min(self.digits.len() - 1);
+                return WidgetEvent::Consumed;
+            },
+            Button::A => return WidgetEvent::Activated,
+            Button::B => return WidgetEvent::Cancelled,
+            _ => return WidgetEvent::Ignored,
+        }
+        WidgetEvent::Changed
+    }
+}
+
+// Screen に追加したウィジェットの番号
+pub type WidgetId = usize;
+
+// ウィジェットの配置とフォーカス
+pub struct Screen {
+    children: Vec<(Rectangle, Box<dyn Widget>)>,
+    focus: Option<WidgetId>,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Screen { children: Vec::new(), focus: None }
+    }
+
+    // 最初に追加したフォーカスを受け取るウィジェットに自動でフォーカスする
+    pub fn add<W: Widget + 'static>(&mut self, area: Rectangle, widget: W) -> WidgetId {
+        let id = self.children.len();
+        if self.focus.is_none() && widget.is_focusable() {
+            self.focus = Some(id);
+        }
+        self.children.push((area, Box::new(widget)));
+        id
+    }
+
+    pub fn focus(&self) -> Option<WidgetId> {
+        self.focus
+    }
+
+    pub fn set_focus(&mut self, id: WidgetId) {
+        if self.children.get(id).is_some_and(|(_, widget)| widget.is_focusable()) {
+            self.focus = Some(id);
+        }
+    }
+
+    pub fn get<W: Widget + 'static>(&self, id: WidgetId) -> Option<&W> {
+        self.children.get(id)?.1.as_ref().as_any().downcast_ref()
+    }
+
+    pub fn get_mut<W: Widget + 'static>(&mut self, id: WidgetId) -> Option<&mut W> {
+        self.children.get_mut(id)?.1.as_mut().as_any_mut().downcast_mut()
+    }
+
+    // フォーカスを前後のフォーカスを受け取るウィジェットに移す (端では止まる)
+    fn move_focus(&mut self, forward: bool) -> bool {
+        let Some(current) = self.focus else {
+            return false;
+        };
+        let next = if forward {
+            (current + 1..self.children.len()).find(|id| self.children[*id].1.is_focusable())
+        } else {
+            (0..current).rev().find(|id| self.children[*id].1.is_focusable())
+        };
+        match next {
+            Some(next) => {
+                self.focus = Some(next);
+                true
+            },
+            None => false,
+        }
+    }
+
+    // KeyMatrix::was_released() の値を渡す
+    // 押されたボタン毎にフォーカス中のウィジェットへ渡し、使われなかった ↑↓ でフォーカスを移す
+    // 戻り値はウィジェットからの通知 (Ignored 以外)
+    pub fn handle(&mut self, buttons: u8) -> Vec<(WidgetId, WidgetEvent)> {
+        let mut events = Vec::new();
+        for button in [Button::UP, Button::LEFT, Button::DOWN, Button::RIGHT, Button::A, Button::B] {
+            if buttons & button == 0 {
+                continue;
+            }
+            let Some(id) = self.focus else {
+                continue;
+            };
+            match self.children[id].1.handle(button) {
+                WidgetEvent::Ignored => {
+                    if button == Button::UP || button == Button::DOWN {
+                        self.move_focus(button == Button::DOWN);
+                    }
+                },
+                event => events.push((id, event)),
+            }
+        }
+        events
+    }
+
+    // 画面全体を描き直すフレーム
+    pub fn draw(&self) -> DisplayFrame {
+        let mut frame = DisplayFrame::new();
+        frame.clear();
+        self.draw_into(&mut frame);
+        frame
+    }
+
+    // 既存のフレームに重ねて描く
+    pub fn draw_into(&self, frame: &mut DisplayFrame) {
+        for (id, (area, widget)) in self.children.iter().enumerate() {
+            widget.draw(frame, *area, self.focus == Some(id));
+        }
+    }
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Self::new()
+    }
+}